@@ -0,0 +1,61 @@
+#version 450
+#pragma shader_stage(compute)
+#include <Definitions/Dataset.glslh>
+#include <Definitions/SimulationParams.glslh>
+#include <Definitions/Boundary.glslh>
+
+/* Chemicals in the cell at the given offset from the given position. Walls and
+ * the edges of the plane let no chemicals through, which is the same as them
+ * having as much of every chemical as the cell at the center. Under the torus
+ * boundary, the plane has no edges, and the chemicals spread across to the
+ * other side of it. */
+vec3 Neighbour(ivec2 position, ivec2 offset, vec3 center) {
+    ivec2 neighbour = position + offset;
+
+    if(!Evo_BoundCell(neighbour, imageSize(Evo_Field)))
+        return center;
+    if(imageLoad(Evo_Terrain, neighbour)[TERRAIN_WALL] > 0.5)
+        return center;
+
+    return imageLoad(Evo_Field, neighbour).xyz;
+}
+
+void main() {
+    uvec2 position  = gl_GlobalInvocationID.xy;
+    ivec2 dimension = imageSize(Evo_Field);
+
+    /* Quit out of extra jobs. */
+    if(position.x >= dimension.x) return;
+    if(position.y >= dimension.y) return;
+
+    ivec2 cell  = ivec2(position);
+    vec4  value = imageLoad(Evo_Field, cell);
+
+    /* Nothing spreads into walls. */
+    if(imageLoad(Evo_Terrain, cell)[TERRAIN_WALL] > 0.5) {
+        imageStore(Evo_Scratch, cell, value);
+        return;
+    }
+
+    /* Fraction of the difference to the neighbours on every axis that gets
+     * spread out in this step. */
+    vec2  size  = Params.field_size / vec2(dimension);
+    float delta = Params.delta / float(Params.diffusion_steps);
+    vec3  fraction_x = Params.diffusion.xyz * delta / (size.x * size.x);
+    vec3  fraction_y = Params.diffusion.xyz * delta / (size.y * size.y);
+
+    vec3 center = value.xyz;
+    vec3 laplacian_x = Neighbour(cell, ivec2(-1,  0), center)
+        + Neighbour(cell, ivec2( 1,  0), center)
+        - 2.0 * center;
+    vec3 laplacian_y = Neighbour(cell, ivec2( 0, -1), center)
+        + Neighbour(cell, ivec2( 0,  1), center)
+        - 2.0 * center;
+
+    value.xyz = clamp(
+        center + fraction_x * laplacian_x + fraction_y * laplacian_y,
+        0.0,
+        1.0);
+
+    imageStore(Evo_Scratch, cell, value);
+}
@@ -0,0 +1,28 @@
+#version 450
+#pragma shader_stage(compute)
+#include <Definitions/Dataset.glslh>
+#include <Definitions/SimulationParams.glslh>
+
+void main() {
+    uvec3 position  = gl_GlobalInvocationID.xyz;
+    ivec3 dimension = imageSize(Evo_Fields);
+
+    /* Quit out of extra jobs. */
+    if(position.x >= dimension.x) return;
+    if(position.y >= dimension.y) return;
+    if(position.z >= dimension.z) return;
+
+    imageStore(Evo_Fields, ivec3(position), vec4(0.0));
+
+    /* Wipe out the grass, if we were told to. */
+    if(Params.wipe_grass != 0 && position.z == 0) {
+        vec2 center = (vec2(position.xy) + 0.5) * Params.field_size / vec2(dimension.xy);
+        if(all(greaterThanEqual(center, Params.wipe_region.xz))
+            && all(lessThanEqual(center, Params.wipe_region.yw))) {
+
+            vec4 value = imageLoad(Evo_Field, ivec2(position.xy));
+            value.w = 0.0;
+            imageStore(Evo_Field, ivec2(position.xy), value);
+        }
+    }
+}
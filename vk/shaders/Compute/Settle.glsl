@@ -4,13 +4,15 @@
 #include <Definitions/SimulationParams.glslh>
 
 void main() {
-    uvec3 position  = gl_GlobalInvocationID.xyz;
-    ivec3 dimension = imageSize(Evo_PredatorFields);
+    uvec2 position  = gl_GlobalInvocationID.xy;
+    ivec2 dimension = imageSize(Evo_Field);
 
     /* Quit out of extra jobs. */
     if(position.x >= dimension.x) return;
     if(position.y >= dimension.y) return;
-    if(position.z >= dimension.z) return;
 
-    imageStore(Evo_PredatorFields, ivec3(position), vec4(0.0));
+    imageStore(
+        Evo_Field,
+        ivec2(position),
+        imageLoad(Evo_Scratch, ivec2(position)));
 }
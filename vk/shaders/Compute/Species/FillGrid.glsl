@@ -0,0 +1,32 @@
+#version 450
+#pragma shader_stage(compute)
+#include <Definitions/Dataset.glslh>
+#include <Definitions/SimulationParams.glslh>
+#include <Definitions/Boundary.glslh>
+#include <Definitions/Grid.glslh>
+
+#define INDIVIDUAL Evo_Individuals[gl_GlobalInvocationID.x]
+
+void main() {
+    /* Sometimes extra tasks will be spawned, make sure we quit out of them
+     * immediately so we don't wrongly write to something. */
+    if(gl_GlobalInvocationID.x <  LOWER)
+        return;
+    if(gl_GlobalInvocationID.x >= UPPER)
+        return;
+
+    /* Every individual starts the iteration unclaimed. */
+    INDIVIDUAL.claim = 0;
+
+    if(INDIVIDUAL.energy <= 0.0 || INDIVIDUAL.gestation > 0.0)
+        return;
+
+    uint cell = Evo_GridIndex(Evo_GridCell(INDIVIDUAL.position));
+    uint slot = atomicAdd(Evo_GridCounts[cell], 1);
+    if(slot < Params.grid_capacity) {
+        Evo_GridEntries[cell * Params.grid_capacity + slot] = uvec2(
+            gl_GlobalInvocationID.x,
+            Dispatch.species);
+        Evo_GridPositions[cell * Params.grid_capacity + slot] = INDIVIDUAL.position;
+    }
+}
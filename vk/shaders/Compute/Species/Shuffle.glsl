@@ -0,0 +1,280 @@
+#version 450
+#pragma shader_stage(compute)
+#include <Definitions/Dataset.glslh>
+#include <Definitions/SimulationParams.glslh>
+#include <Definitions/Boundary.glslh>
+#include <Definitions/Lineage.glslh>
+#include <Definitions/Matrix.glslh>
+#include <Definitions/Random.glslh>
+
+/* Shorthand for the individual. SPIR-V does not have refences as far as I know,
+ * so doing this, instead, is not that bad. */
+#define INDIVIDUAL Evo_Individuals[gl_GlobalInvocationID.x]
+
+/* End of the slice of the population buffer owned by the species. Reserving
+ * room for a newborn may push the upper bound of the species past it for a
+ * moment, so reads of the upper bound are clamped to it. */
+#define END (SPECIES.base + SPECIES.budget)
+
+/* Whether the individual at the given index may reproduce, that is, whether
+ * it has been born, is old enough, has enough energy and is done cooling down
+ * from its last reproduction. */
+bool Fertile(uint i) {
+    return Evo_Individuals[i].gestation <= 0.0
+        && Evo_Individuals[i].energy    >= SPECIES.reproduction_min
+        && Evo_Individuals[i].age       >= SPECIES.reproduction_age
+        && Evo_Individuals[i].cooldown  <= 0.0;
+}
+
+/* Whether the individual at the given index may be chosen as a mate. */
+bool Eligible(int i) {
+    bool near = SPECIES.local_mating == 0
+        || Evo_Distance(INDIVIDUAL.position, Evo_Individuals[i].position)
+            <= SPECIES.mating_radius;
+
+    return i != int(gl_GlobalInvocationID.x)
+        && Fertile(uint(i))
+        && near;
+}
+
+/* Chooses a mate for the individual using the strategy of the group. Returns
+ * the index of the mate or -1 if no suitable mate could be found. */
+int ChooseMate(inout uint rng) {
+    int lower = int(LOWER);
+    int upper = int(min(UPPER, END));
+    int mate  = -1;
+
+    if(SPECIES.mate_selection == MATE_SELECTION_GLOBAL_BEST) {
+        /* The individual with the most energy in the whole group. */
+        for(int i = lower; i < upper; ++i)
+            if(Eligible(i) && (mate < 0 || Evo_Individuals[i].energy > Evo_Individuals[mate].energy))
+                mate = i;
+    } else if(SPECIES.mate_selection == MATE_SELECTION_NEAREST) {
+        /* The closest individual within the radius of vision. */
+        float best = SPECIES.view_radius;
+        for(int i = lower; i < upper; ++i) {
+            float dist = Evo_Distance(INDIVIDUAL.position, Evo_Individuals[i].position);
+            if(Eligible(i) && dist <= best) {
+                best = dist;
+                mate = i;
+            }
+        }
+    } else if(SPECIES.mate_selection == MATE_SELECTION_TOURNAMENT) {
+        /* The best out of a few individuals drawn at random, out of the
+         * ones that are eligible, so that every tournament is as big as it
+         * can be. */
+        int eligible = 0;
+        for(int i = lower; i < upper; ++i)
+            if(Eligible(i))
+                ++eligible;
+
+        for(uint k = 0; k < SPECIES.tournament_size && eligible > 0; ++k) {
+            int pick = min(int(RandomFloat(rng) * float(eligible)), eligible - 1);
+            for(int i = lower; i < upper; ++i) {
+                if(!Eligible(i))
+                    continue;
+                if(pick-- > 0)
+                    continue;
+
+                if(mate < 0 || Evo_Individuals[i].energy > Evo_Individuals[mate].energy)
+                    mate = i;
+                break;
+            }
+        }
+    } else if(SPECIES.mate_selection == MATE_SELECTION_ROULETTE) {
+        /* An individual drawn with probability proportional to its energy. */
+        float total = 0.0;
+        for(int i = lower; i < upper; ++i)
+            if(Eligible(i))
+                total += Evo_Individuals[i].energy;
+
+        float target = RandomFloat(rng) * total;
+        for(int i = lower; i < upper; ++i) {
+            if(!Eligible(i))
+                continue;
+
+            mate = i;
+            target -= Evo_Individuals[i].energy;
+            if(target <= 0.0)
+                break;
+        }
+    }
+
+    return mate;
+}
+
+/* Chooses the individual a newborn of the given parents takes the place of,
+ * once the species has reached its budget, following its budget policy, and
+ * claims it, such that no other birth happening at the same time may take its
+ * place as well. Should another invocation claim the chosen individual first,
+ * the next one in line is chosen in its stead, except under the random policy,
+ * where the birth does not happen, just as if a spared individual was drawn.
+ * Returns the index of the individual or -1 if the birth should not happen. */
+int ChooseVictim(inout uint rng, int mate) {
+    int lower  = int(LOWER);
+    int upper  = int(min(UPPER, END));
+
+    /* Parents, individuals that are about to die anyway and individuals
+     * claimed by another birth are spared. */
+    #define SPARED(i) (i == int(gl_GlobalInvocationID.x) || i == mate || Evo_Individuals[i].energy < 0.0 || Evo_Individuals[i].claim != 0)
+
+    int victim = -1;
+    bool claimed = false;
+    while(!claimed) {
+        victim = -1;
+        if(SPECIES.budget_policy == BUDGET_POLICY_REPLACE_LOWEST_ENERGY) {
+            for(int i = lower; i < upper; ++i)
+                if(!SPARED(i) && (victim < 0 || Evo_Individuals[i].energy < Evo_Individuals[victim].energy))
+                    victim = i;
+        } else if(SPECIES.budget_policy == BUDGET_POLICY_REPLACE_OLDEST) {
+            for(int i = lower; i < upper; ++i)
+                if(!SPARED(i) && (victim < 0 || Evo_Individuals[i].age > Evo_Individuals[victim].age))
+                    victim = i;
+        } else if(SPECIES.budget_policy == BUDGET_POLICY_REPLACE_RANDOM) {
+            int i = min(lower + int(RandomFloat(rng) * float(upper - lower)), upper - 1);
+            if(!SPARED(i))
+                victim = i;
+        }
+        if(victim < 0)
+            break;
+
+        /* Losing the individual to another birth leaves it claimed, so it is
+         * passed over the next time around. */
+        claimed = atomicCompSwap(Evo_Individuals[victim].claim, 0, 1) == 0;
+        if(!claimed && SPECIES.budget_policy == BUDGET_POLICY_REPLACE_RANDOM) {
+            victim = -1;
+            break;
+        }
+    }
+
+    #undef SPARED
+    return victim;
+}
+
+void main() {
+    /* Sometimes extra tasks will be spawned, make sure we quit out of them
+     * immediately so we don't wrongly write to something. */
+    if(gl_GlobalInvocationID.x <  LOWER)
+        return;
+    if(gl_GlobalInvocationID.x >= UPPER)
+        return;
+
+    uint rng = RandomSeed(Params.seed, gl_GlobalInvocationID.x);
+    int a = -1;
+
+    if(Fertile(gl_GlobalInvocationID.x))
+        a = ChooseMate(rng);
+
+    /* Offspring must fit in the slice of the population buffer owned by the
+     * species, lest they overwrite the individuals of the next one. Room for
+     * them is reserved atomically, such that no two births ever share a slot,
+     * and given back right away if the slice turns out to be full. Once it
+     * is, they either take the place of another individual or are not born at
+     * all, following the budget policy of the species. */
+    int slot = -1;
+    if(a >= 0) {
+        uint reserved = atomicAdd(UPPER, 1);
+        if(reserved < END) {
+            slot = int(reserved);
+
+            /* Newborns are never taken the place of by other births happening
+             * alongside their own. */
+            Evo_Individuals[slot].claim = 1;
+        } else
+            atomicAdd(UPPER, uint(-1));
+    }
+    if(a >= 0 && slot < 0) {
+        slot = ChooseVictim(rng, a);
+        if(slot >= 0) {
+            Evo_LineageAdd(LINEAGE_DEATH, uint(slot));
+            atomicAdd(Evo_Ranges[Dispatch.species].replaced, 1);
+            LedgerAdd(LEDGER_EXPIRED, max(Evo_Individuals[slot].energy, 0.0));
+        } else {
+            atomicAdd(Evo_Ranges[Dispatch.species].rejected, 1);
+            a = -1;
+        }
+    }
+
+    if(a >= 0)
+    {
+        /* Reproduce together with the chosen mate. */
+        #define MATE Evo_Individuals[a]
+        #define OFFSPRING Evo_Individuals[slot]
+
+        MATE.energy         -= SPECIES.reproduction_cost;
+        INDIVIDUAL.energy   -= SPECIES.reproduction_cost;
+        MATE.cooldown        = SPECIES.reproduction_cooldown;
+        INDIVIDUAL.cooldown  = SPECIES.reproduction_cooldown;
+        MATE.offspring      += 1;
+        INDIVIDUAL.offspring += 1;
+
+        bool mother = SPECIES.offspring_placement == OFFSPRING_PLACEMENT_MOTHER;
+        if(mother || SPECIES.local_mating != 0) {
+            /* Be born somewhere around the initiating parent, the mother. */
+            float scatter = mother ? SPECIES.scatter_radius : SPECIES.offspring_jitter;
+            float angle   = RandomFloat(rng) * 2 * 3.1415;
+            float radius  = sqrt(RandomFloat(rng)) * scatter;
+
+            OFFSPRING.position = Evo_Bound(
+                INDIVIDUAL.position + radius * vec2(cos(angle), sin(angle)));
+        } else
+            /* Somewhere along the shortest way between the parents. */
+            OFFSPRING.position = Evo_Bound(MATE.position
+                + Evo_Offset(MATE.position, INDIVIDUAL.position) * OFFSPRING.biases[0][0]);
+        OFFSPRING.velocity = mix(MATE.velocity, INDIVIDUAL.velocity, OFFSPRING.biases[0][1]);
+        OFFSPRING.energy   = SPECIES.offspring_energy;
+        if(Params.energy_conservation != 0)
+            OFFSPRING.energy = 2.0 * SPECIES.reproduction_cost
+                * SPECIES.reproduction_efficiency;
+        LedgerAdd(LEDGER_INVESTED, 2.0 * SPECIES.reproduction_cost);
+        LedgerAdd(LEDGER_ENDOWED, OFFSPRING.energy);
+        OFFSPRING.age       = 0.0;
+        OFFSPRING.handling  = 0.0;
+        OFFSPRING.cooldown  = 0.0;
+        OFFSPRING.gestation = SPECIES.gestation;
+        OFFSPRING.offspring = 0;
+        OFFSPRING.gathered  = 0.0;
+
+        /* Be given an identity of its own, descending from both parents. */
+        OFFSPRING.id         = Evo_NextId();
+        OFFSPRING.parents[0] = INDIVIDUAL.id;
+        OFFSPRING.parents[1] = MATE.id;
+        OFFSPRING.birth_step = Params.step;
+        OFFSPRING.generation = max(INDIVIDUAL.generation, MATE.generation) + 1;
+
+        /* Inherit the signature, with mutation. */
+        for(int i = 0; i < 3; ++i)
+            OFFSPRING.signature[i] = clamp(
+                (MATE.signature[i] + INDIVIDUAL.signature[i]) / 2.0
+                    + RandomSigned(rng) * SPECIES.signature_mutation,
+                0.0,
+                1.0);
+
+        for(int i = 0; i < 2; ++i)
+            for(int j = 0; j < 4; ++j)
+                OFFSPRING.biases[i][j] = mix(
+                    MATE.biases[i][j],
+                    INDIVIDUAL.biases[i][j],
+                    OFFSPRING.biases[i][j]);
+
+        for(int i = 0; i < NN_OUTPUTS * NN_INPUTS; ++i)
+            OFFSPRING.weights[i] = mix(
+                MATE.weights[i],
+                INDIVIDUAL.weights[i],
+                OFFSPRING.weights[i]);
+
+        Evo_LineageAdd(LINEAGE_BIRTH, uint(slot));
+    }
+    else if(INDIVIDUAL.energy < 0.0)
+    {
+        /* Die. */
+        Evo_LineageAdd(LINEAGE_DEATH, gl_GlobalInvocationID.x);
+
+        uint lower = atomicAdd(LOWER, 1);
+        Evo_Individual tmp = Evo_Individuals[lower];
+        Evo_Individuals[lower] = INDIVIDUAL;
+        INDIVIDUAL = tmp;
+
+        return;
+    }
+}
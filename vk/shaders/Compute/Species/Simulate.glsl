@@ -0,0 +1,535 @@
+#version 450
+#pragma shader_stage(compute)
+#include <Definitions/Dataset.glslh>
+#include <Definitions/Matrix.glslh>
+#include <Definitions/SimulationParams.glslh>
+#include <Definitions/Random.glslh>
+#include <Definitions/Boundary.glslh>
+#include <Definitions/Grid.glslh>
+
+/* Shorthand for the individual. SPIR-V does not have refences as far as I know,
+ * so doing this, instead, is not that bad. */
+#define INDIVIDUAL Evo_Individuals[gl_GlobalInvocationID.x]
+
+/* Gradient of the given component of the field around the given cell, within
+ * the given radius of vision, in cells, along with its intensity. Cells past
+ * the edges of the plane wrap around under the torus boundary, the same way
+ * they do in `State::gradient()` on the host. */
+vec3 GradientIntensityAt(int x, int y, int component, vec2 view) {
+    float radius = min(view.x, view.y);
+    ivec2 low  = ivec2(round(vec2(x, y) - abs(radius)));
+    ivec2 high = ivec2(round(vec2(x, y) + abs(radius)));
+    Evo_BoundSpan(low, high, imageSize(Evo_Field));
+
+    vec2 center = vec2(x, y);
+    float center_val = clamp(
+        imageLoad(Evo_Field, ivec2(x, y))[component],
+        0.0,
+        1.0);
+
+    vec2 gradient = vec2(0.000001);
+    for(int i = low.y; i <= high.y; ++i) {
+        for(int j = low.x; j <= high.x; ++j) {
+            vec2 pos = vec2(j, i);
+
+            float dist = distance(center, pos);
+            vec2 direction = normalize(pos - center);
+
+            ivec2 cell = ivec2(j, i);
+            if(dist > radius || !Evo_BoundCell(cell, imageSize(Evo_Field)))
+                continue;
+            float val = clamp(
+                imageLoad(Evo_Field, cell)[component],
+                0.0,
+                1.0);
+
+            gradient += (val - center_val) * direction;
+        }
+    }
+
+    return vec3(normalize(gradient), length(gradient));
+}
+
+/* Factor by which the metabolism of the individual is scaled at the given
+ * temperature, the same way `Group::thermal_stress()` does on the host. */
+float ThermalStress(float temperature) {
+    if(SPECIES.temperature_tolerance <= 0.0)
+        return 1.0;
+
+    float dist = (temperature - SPECIES.preferred_temperature)
+        / SPECIES.temperature_tolerance;
+    return 1.0 + dist * dist;
+}
+
+/* Level of the given temperature between the lowest and highest temperatures
+ * on the plane, the same way `Temperature::level()` does on the host. */
+float TemperatureLevel(float temperature) {
+    float low  = Params.temperature_range.x;
+    float high = Params.temperature_range.y;
+    return (temperature - low) / max(high - low, 1.19209290e-07);
+}
+
+/* Moves from one position to another, sliding along any walls in the way, the
+ * same way `Terrain::slide()` does on the host. */
+vec2 Slide(vec2 from, vec2 to) {
+    if(!Evo_IsWall(to, Params.field_size) || Evo_IsWall(from, Params.field_size))
+        return to;
+    else if(!Evo_IsWall(vec2(to.x, from.y), Params.field_size))
+        return vec2(to.x, from.y);
+    else if(!Evo_IsWall(vec2(from.x, to.y), Params.field_size))
+        return vec2(from.x, to.y);
+    else
+        return from;
+}
+
+/* Senses the individuals alive within the radius of vision, out of the cells
+ * of the grid around the individual, writing the neighbour inputs to the
+ * network, as described in `Dataset.glslh`. Neighbours are seen where they
+ * were sorted into the grid, at the start of the iteration, regardless of
+ * whether they have moved or been killed since. This must be kept in line with
+ * `State::sense_neighbours()` on the host. */
+void SenseNeighbours(inout float nn_input[NN_INPUTS]) {
+    /* Offsets to the nearest predator, prey and kin, in that order. */
+    bool  found[3]     = bool[3](false, false, false);
+    vec2  offsets[3]   = vec2[3](vec2(0.0), vec2(0.0), vec2(0.0));
+    float distances[3] = float[3](
+        SPECIES.view_radius,
+        SPECIES.view_radius,
+        SPECIES.view_radius);
+    uint others = 0;
+
+    ivec2 low, high;
+    Evo_GridSpan(
+        INDIVIDUAL.position - SPECIES.view_radius,
+        INDIVIDUAL.position + SPECIES.view_radius,
+        low,
+        high);
+    for(int y = low.y; y <= high.y; ++y)
+        for(int x = low.x; x <= high.x; ++x)
+        {
+            ivec2 cell = ivec2(x, y);
+            if(!Evo_BoundCell(cell, ivec2(Params.grid_size)))
+                continue;
+
+            uint index = Evo_GridIndex(cell);
+            for(uint e = 0; e < Evo_GridCount(index); ++e)
+            {
+                uvec2 entry = Evo_GridEntries[index * Params.grid_capacity + e];
+                if(entry.x == gl_GlobalInvocationID.x)
+                    continue;
+
+                vec2  offset = Evo_Offset(
+                    INDIVIDUAL.position,
+                    Evo_GridPositions[index * Params.grid_capacity + e]);
+                float dist   = length(offset);
+                if(dist > SPECIES.view_radius)
+                    continue;
+                ++others;
+
+                bool kinds[3] = bool[3](
+                    (SPECIES.predators & (1u << entry.y)) != 0,
+                    (SPECIES.prey      & (1u << entry.y)) != 0,
+                    entry.y == Dispatch.species);
+                for(int n = 0; n < 3; ++n)
+                    if(kinds[n] && dist <= distances[n]) {
+                        found[n]     = true;
+                        offsets[n]   = offset;
+                        distances[n] = dist;
+                    }
+            }
+        }
+
+    int inputs[3] = int[3](NN_INPUT_PREDATOR, NN_INPUT_PREY, NN_INPUT_KIN);
+    for(int n = 0; n < 3; ++n) {
+        vec2  direction = vec2(0.0);
+        float dist      = 1.0;
+        if(found[n]) {
+            if(distances[n] > 0.0)
+                direction = offsets[n] / distances[n];
+            dist = distances[n] / SPECIES.view_radius;
+        }
+
+        nn_input[inputs[n] + 0] = direction.x;
+        nn_input[inputs[n] + 1] = direction.y;
+        nn_input[inputs[n] + 2] = dist;
+    }
+    nn_input[NN_INPUT_DENSITY] = float(others) / float(others + 1);
+}
+
+/* Casts the vision rays of the individual, writing their inputs to the
+ * network, as described in `Dataset.glslh`. This must be kept in line with
+ * `State::cast_rays()` on the host. */
+void CastRays(inout float nn_input[NN_INPUTS]) {
+    float heading = 0.0;
+    if(length(INDIVIDUAL.velocity) > 0.0)
+        heading = atan(INDIVIDUAL.velocity.y, INDIVIDUAL.velocity.x);
+
+    /* Rays are walked one cell of the field at a time. */
+    vec2  cell = Params.field_size / vec2(imageSize(Evo_Field));
+    float step = min(cell.x, cell.y);
+    float half_width = SPECIES.ray_width / 2.0;
+
+    uint rays = min(SPECIES.ray_count, NN_RAYS_MAX);
+    for(uint r = 0; r < rays; ++r)
+    {
+        float angle = heading;
+        if(rays > 1)
+            angle += SPECIES.field_of_view * (float(r) / float(rays - 1) - 0.5);
+        vec2 direction = vec2(cos(angle), sin(angle));
+
+        /* Find the first wall or edge of the plane along the ray, past which
+         * rays go on from the other side of the plane under the torus
+         * boundary. */
+        float hit  = SPECIES.view_radius;
+        int   kind = -1;
+        for(float t = step; t <= SPECIES.view_radius; t += step) {
+            vec2 point = INDIVIDUAL.position + direction * t;
+            bool edge  = Params.boundary != BOUNDARY_TORUS
+                && (any(lessThan(point, vec2(0.0)))
+                    || any(greaterThanEqual(point, Params.field_size)));
+            if(edge || Evo_IsWall(Evo_Bound(point), Params.field_size)) {
+                hit  = t;
+                kind = 3;
+                break;
+            }
+        }
+
+        /* Then for any individual closer than that, out of the cells of the
+         * grid around the ray. */
+        vec2  end = INDIVIDUAL.position + direction * hit;
+        ivec2 low, high;
+        Evo_GridSpan(
+            min(INDIVIDUAL.position, end) - half_width,
+            max(INDIVIDUAL.position, end) + half_width,
+            low,
+            high);
+        for(int y = low.y; y <= high.y; ++y)
+            for(int x = low.x; x <= high.x; ++x)
+            {
+                ivec2 cell = ivec2(x, y);
+                if(!Evo_BoundCell(cell, ivec2(Params.grid_size)))
+                    continue;
+
+                uint index = Evo_GridIndex(cell);
+                for(uint e = 0; e < Evo_GridCount(index); ++e)
+                {
+                    uvec2 entry = Evo_GridEntries[index * Params.grid_capacity + e];
+                    if(entry.x == gl_GlobalInvocationID.x || Evo_Individuals[entry.x].energy <= 0.0)
+                        continue;
+
+                    vec2  offset = Evo_Offset(INDIVIDUAL.position, Evo_Individuals[entry.x].position);
+                    float along  = dot(offset, direction);
+                    float across = abs(offset.x * direction.y - offset.y * direction.x);
+                    if(along <= 0.0 || along >= hit || across > half_width)
+                        continue;
+
+                    hit  = along;
+                    kind = -1;
+                    if((SPECIES.predators & (1u << entry.y)) != 0)
+                        kind = 0;
+                    else if((SPECIES.prey & (1u << entry.y)) != 0)
+                        kind = 1;
+                    else if(entry.y == Dispatch.species)
+                        kind = 2;
+                }
+            }
+
+        /* Average out the field over the part of the ray before the hit. */
+        vec4 field   = vec4(0.0);
+        uint samples = 0;
+        for(float t = step / 2.0; t < hit; t += step) {
+            vec2  point = Evo_Bound(INDIVIDUAL.position + direction * t);
+            ivec2 texel = ivec2(floor(point / cell));
+            texel = clamp(texel, ivec2(0), imageSize(Evo_Field) - 1);
+
+            field += clamp(imageLoad(Evo_Field, texel), 0.0, 1.0);
+            ++samples;
+        }
+        if(samples > 0)
+            field /= float(samples);
+
+        int base = NN_INPUT_RAYS + int(r) * NN_RAY_INPUTS;
+        nn_input[base + NN_RAY_DISTANCE] = hit / SPECIES.view_radius;
+        for(int k = 0; k < 4; ++k) {
+            nn_input[base + NN_RAY_KIND  + k] = k == kind ? 1.0 : 0.0;
+            nn_input[base + NN_RAY_FIELD + k] = field[k];
+        }
+    }
+}
+
+/* Push to be given to the individual, as a velocity, away from the
+ * individuals crowding around it. This must be kept in line with
+ * `State::separation()` on the host. */
+vec2 Separation() {
+    vec2  push   = vec2(0.0);
+    float radius = Params.separation_radius;
+
+    ivec2 low, high;
+    Evo_GridSpan(
+        INDIVIDUAL.position - radius,
+        INDIVIDUAL.position + radius,
+        low,
+        high);
+    for(int y = low.y; y <= high.y; ++y)
+        for(int x = low.x; x <= high.x; ++x)
+        {
+            ivec2 cell = ivec2(x, y);
+            if(!Evo_BoundCell(cell, ivec2(Params.grid_size)))
+                continue;
+
+            uint index = Evo_GridIndex(cell);
+            for(uint e = 0; e < Evo_GridCount(index); ++e)
+            {
+                uvec2 entry = Evo_GridEntries[index * Params.grid_capacity + e];
+                if(entry.x == gl_GlobalInvocationID.x || Evo_Individuals[entry.x].energy <= 0.0)
+                    continue;
+
+                vec2  offset = Evo_Offset(Evo_Individuals[entry.x].position, INDIVIDUAL.position);
+                float dist   = length(offset);
+                if(dist <= 0.0 || dist >= radius)
+                    continue;
+
+                push += offset / dist * (1.0 - dist / radius);
+            }
+        }
+
+    return push * Params.separation_force;
+}
+
+/* Closest individual alive within the attack radius of the individual, out of
+ * all the species it feeds on, and not yet claimed by another hunter, or -1 if
+ * there is none. */
+int NearestPrey() {
+    int   prey    = -1;
+    float nearest = SPECIES.attack_radius;
+    for(uint s = 0; s < Params.species; ++s)
+    {
+        if((SPECIES.prey & (1u << s)) == 0)
+            continue;
+
+        for(int i = int(Evo_Ranges[s].lower); i < int(Evo_Ranges[s].upper); ++i)
+        {
+            float dist = Evo_Distance(INDIVIDUAL.position, Evo_Individuals[i].position);
+            if(dist <= nearest
+                && Evo_Individuals[i].energy > 0.0
+                && Evo_Individuals[i].gestation <= 0.0
+                && Evo_Individuals[i].claim == 0) {
+                nearest = dist;
+                prey    = i;
+            }
+        }
+    }
+
+    return prey;
+}
+
+void main()
+{
+    /* Sometimes extra tasks will be spawned, make sure we quit out of them
+     * immediately so we don't wrongly write to something. */
+    if(gl_GlobalInvocationID.x <  LOWER)
+        return;
+    if(gl_GlobalInvocationID.x >= UPPER)
+        return;
+
+    /* Individuals killed earlier in this iteration, by species simulated
+     * before this one, are left for the shuffle to remove. */
+    if(INDIVIDUAL.energy < 0.0)
+        return;
+
+    /* Offspring that have not been born yet do nothing but wait. */
+    if(INDIVIDUAL.gestation > 0.0) {
+        INDIVIDUAL.gestation -= Params.delta;
+        return;
+    }
+
+    /* Coerce the individual back into bounds if necessary. */
+    INDIVIDUAL.position = Evo_Bound(INDIVIDUAL.position);
+
+    /* Figure out where we are in the simulation field. */
+    int field_x = int(floor(INDIVIDUAL.position.x / Params.field_size.x * imageSize(Evo_Field).x));
+    int field_y = int(floor(INDIVIDUAL.position.y / Params.field_size.y * imageSize(Evo_Field).y));
+    field_x = clamp(field_x, 0, imageSize(Evo_Field).x - 1);
+    field_y = clamp(field_y, 0, imageSize(Evo_Field).y - 1);
+
+    /* Start off by feeding on grass, if possible, taking only as much of it
+     * as we need. Other individuals may be grazing the same cell at the same
+     * time, so the grass is reserved before it is eaten, which keeps them
+     * from eating more of it than there is. This must be kept in line with
+     * `State::step()` on the host. */
+    float graze = 0.0;
+    if(SPECIES.grazes != 0) {
+        float efficiency = 1.0;
+        if(Params.energy_conservation != 0)
+            efficiency = SPECIES.feeding_efficiency;
+
+        float grass = clamp(imageLoad(Evo_Field, ivec2(field_x, field_y)).w, 0.0, 1.0);
+        graze = Evo_Graze(
+            ivec2(field_x, field_y),
+            imageSize(Evo_Field),
+            grass,
+            clamp((1.0 - INDIVIDUAL.energy) / efficiency, 0.0, grass));
+
+        float eat = graze * efficiency;
+        INDIVIDUAL.energy   += eat;
+        INDIVIDUAL.gathered += eat;
+        LedgerAdd(LEDGER_GRAZED, graze);
+        LedgerAdd(LEDGER_ASSIMILATED, eat);
+    }
+
+    /* Then hunt, if possible. Individuals that are done handling their last
+     * prey and are hungry attack the closest individual alive within their
+     * attack radius, out of all the species they feed on, which gets killed if
+     * the attack succeeds. */
+    INDIVIDUAL.handling = max(INDIVIDUAL.handling - Params.delta, 0.0);
+    if(SPECIES.prey != 0
+        && INDIVIDUAL.handling <= 0.0
+        && INDIVIDUAL.energy < SPECIES.hunting_threshold)
+    {
+        int prey = NearestPrey();
+
+        uint rng = RandomSeed(RandomHash(Params.seed), gl_GlobalInvocationID.x);
+        if(prey >= 0 && RandomFloat(rng) < SPECIES.capture_probability)
+        {
+            /* Claim the prey before eating it, such that no two hunters ever
+             * eat the same one. Should another hunter get to it first, go for
+             * the next closest one instead, which is what the host does, as it
+             * runs the hunters one after the other. */
+            while(prey >= 0 && atomicCompSwap(Evo_Individuals[prey].claim, 0, 1) != 0)
+                prey = NearestPrey();
+        }
+        else
+            prey = -1;
+
+        if(prey >= 0)
+        {
+            /* Eat. */
+            float energy = Evo_Individuals[prey].energy;
+            float before = INDIVIDUAL.energy;
+
+            INDIVIDUAL.energy = clamp(
+                INDIVIDUAL.energy + energy * SPECIES.energy_transfer,
+                0.0,
+                1.0);
+            INDIVIDUAL.handling  = SPECIES.handling_time;
+            INDIVIDUAL.gathered += INDIVIDUAL.energy - before;
+            Evo_Individuals[prey].energy = -1.0;
+
+            LedgerAdd(LEDGER_PREYED, energy);
+            LedgerAdd(LEDGER_ASSIMILATED, INDIVIDUAL.energy - before);
+        }
+    }
+
+    /* Create the input for the network. */
+    float nn_input[NN_INPUTS];
+    for(int i = 0; i < NN_INPUTS; ++i)
+        nn_input[i] = 0.0;
+
+    nn_input[NN_INPUT_VELOCITY + 0] = INDIVIDUAL.velocity.x;
+    nn_input[NN_INPUT_VELOCITY + 1] = INDIVIDUAL.velocity.y;
+
+    vec2 view = vec2(
+        SPECIES.view_radius / Params.field_size.x * imageSize(Evo_Field).x,
+        SPECIES.view_radius / Params.field_size.y * imageSize(Evo_Field).y
+    );
+    if(SPECIES.vision == VISION_GRADIENTS) {
+        vec3 red   = GradientIntensityAt(field_x, field_y, 0, view);
+        vec3 green = GradientIntensityAt(field_x, field_y, 1, view);
+        vec3 blue  = GradientIntensityAt(field_x, field_y, 2, view);
+        vec3 alpha = GradientIntensityAt(field_x, field_y, 3, view);
+
+        vec3 gradients[4] = vec3[4](red, green, blue, alpha);
+        for(int i = 0; i < 4; ++i)
+            for(int j = 0; j < 3; ++j)
+                nn_input[NN_INPUT_GRADIENTS + i * 3 + j] = gradients[i][j];
+    } else if(SPECIES.vision == VISION_RAYS)
+        CastRays(nn_input);
+
+    vec4 terrain = imageLoad(Evo_Terrain, ivec2(field_x, field_y));
+    if(Params.wall_proximity != 0)
+        nn_input[NN_INPUT_WALL_PROXIMITY] = 1.0 - clamp(
+            terrain[TERRAIN_CLEARANCE] / SPECIES.view_radius,
+            0.0,
+            1.0);
+
+    if(SPECIES.neighbour_inputs != 0)
+        SenseNeighbours(nn_input);
+
+    float temperature = imageLoad(Evo_Temperature, ivec2(field_x, field_y)).x
+        * Params.temperature;
+    if(Params.temperature_input != 0)
+        nn_input[NN_INPUT_TEMPERATURE] = TemperatureLevel(temperature);
+
+    /* Calculate an output value. */
+    float nn_output[NN_OUTPUTS];
+    for(int i = 0; i < NN_OUTPUTS; ++i) {
+        float value = INDIVIDUAL.biases[i / 4][i % 4];
+        for(int j = 0; j < NN_INPUTS; ++j)
+            value += INDIVIDUAL.weights[i * NN_INPUTS + j] * nn_input[j];
+
+        nn_output[i] = Sigmoid(value);
+    }
+
+    /* Perform the actions we got from the output. */
+    vec2 movement = vec2(
+        cos(nn_output[0] * 2 * 3.1415),
+        sin(nn_output[0] * 2 * 3.1415));
+
+    float speed = mix(0.0, SPECIES.max_speed, nn_output[1]);
+    movement *= Params.delta * speed / terrain[TERRAIN_COST];
+
+    float penalty = mix(
+        SPECIES.metabolism_min,
+        SPECIES.metabolism_max,
+        nn_output[1]);
+    penalty *= 1.0 + SPECIES.senescence * INDIVIDUAL.age;
+    penalty *= Params.metabolism;
+    penalty *= ThermalStress(temperature);
+    penalty *= Params.delta;
+
+    /* Walls are checked against the position the individual would end up at
+     * once it has been coerced back into bounds. */
+    vec2 target = Evo_Bound(INDIVIDUAL.position + movement);
+    target = Slide(INDIVIDUAL.position, target);
+
+    INDIVIDUAL.velocity  = target - INDIVIDUAL.position;
+    INDIVIDUAL.position  = target;
+    INDIVIDUAL.energy   -= penalty;
+    INDIVIDUAL.age      += Params.delta;
+    INDIVIDUAL.cooldown  = max(INDIVIDUAL.cooldown - Params.delta, 0.0);
+    LedgerAdd(LEDGER_METABOLISED, penalty);
+
+    /* Then get pushed apart from the individuals crowding around, if enabled.
+     * Pushes do not count towards the velocity of the individual. */
+    if(Params.crowding != 0) {
+        target = Evo_Bound(INDIVIDUAL.position + Separation() * Params.delta);
+
+        INDIVIDUAL.position = Slide(INDIVIDUAL.position, target);
+    }
+
+    /* Die of old age. */
+    if(SPECIES.lifespan > 0.0 && INDIVIDUAL.age >= SPECIES.lifespan) {
+        LedgerAdd(LEDGER_EXPIRED, INDIVIDUAL.energy);
+        INDIVIDUAL.energy = -1.0;
+    }
+
+    /* Coerce the individual back into bounds if necessary. */
+    INDIVIDUAL.position = Evo_Bound(INDIVIDUAL.position);
+
+    /* Update the tile. */
+    vec4 tile = imageLoad(
+        Evo_Fields,
+        ivec3(field_x, field_y, gl_GlobalInvocationID.x));
+
+    float intensity = nn_output[2];
+    tile.x = clamp(tile.x + INDIVIDUAL.signature.x * intensity, 0.0, 1.0);
+    tile.y = clamp(tile.y + INDIVIDUAL.signature.y * intensity, 0.0, 1.0);
+    tile.z = clamp(tile.z + INDIVIDUAL.signature.z * intensity, 0.0, 1.0);
+    tile.w = -graze;
+
+    imageStore(
+        Evo_Fields,
+        ivec3(field_x, field_y, gl_GlobalInvocationID.x),
+        tile);
+
+}
@@ -2,6 +2,53 @@
 #pragma shader_stage(compute)
 #include <Definitions/Dataset.glslh>
 #include <Definitions/SimulationParams.glslh>
+#include <Definitions/Boundary.glslh>
+
+/* Decomposes the given amount of a chemical at the given rate over the time
+ * since the last iteration, the same way `DecayMode::decay()` does on the
+ * host. */
+float Decay(float value, float rate, uint mode) {
+    if(mode == DECAY_MODE_EXPONENTIAL)
+        return clamp(value * exp(-rate * Params.delta), 0.0, 1.0);
+    else
+        return clamp(value - rate * Params.delta, 0.0, 1.0);
+}
+
+/* Average density of grass in the four neighbours of the given cell. Other
+ * invocations may or may not have updated their cells yet, which, for how
+ * slowly grass grows, makes no real difference. */
+float Neighbours(ivec2 cell) {
+    float total = 0.0;
+    ivec2 offsets[4] = ivec2[](ivec2(-1, 0), ivec2(1, 0), ivec2(0, -1), ivec2(0, 1));
+    for(int i = 0; i < 4; ++i) {
+        ivec2 neighbour = cell + offsets[i];
+        if(Evo_BoundCell(neighbour, imageSize(Evo_Field)))
+            total += imageLoad(Evo_Field, neighbour).w;
+    }
+
+    return total / 4.0;
+}
+
+/* Grows the given density of grass back over the time since the last
+ * iteration, at the given rate, the same way `Regrowth::regrow()` does on the
+ * host, counting down the time left before the grass may grow back. */
+float Regrow(float grass, float neighbours, inout float delay, float rate) {
+    float growth;
+    if(Params.regrowth_model == REGROWTH_LOGISTIC)
+        growth = rate * max(grass, Params.regrowth_seed) * (1.0 - grass) * Params.delta;
+    else if(Params.regrowth_model == REGROWTH_PATCHES)
+        growth = rate * max(grass, neighbours * Params.regrowth_spread) * (1.0 - grass) * Params.delta;
+    else if(Params.regrowth_model == REGROWTH_DELAYED) {
+        /* Only grow for the part of the step after the delay. */
+        float waited = min(Params.delta, delay);
+        delay -= waited;
+
+        growth = rate * (Params.delta - waited);
+    } else
+        growth = rate * Params.delta;
+
+    return clamp(grass + growth, 0.0, 1.0);
+}
 
 void main() {
     uvec2 position  = gl_GlobalInvocationID.xy;
@@ -11,58 +58,58 @@ void main() {
     if(position.x >= dimension.x) return;
     if(position.y >= dimension.y) return;
 
-    /* Weave all of the herbivore data. */
-    for(int z = int(Evo_LowerHerbivore); z < Evo_UpperHerbivore; ++z) {
-        vec4 delta = imageLoad(
-            Evo_HerbivoreFields,
-            ivec3(ivec2(position), z));
-        vec4 value = imageLoad(
-            Evo_Field,
-            ivec2(position));
+    /* Weave all of the data of every species. Grass that gets grazed has to
+     * wait before growing back. */
+    vec4 regrowth = imageLoad(Evo_Regrowth, ivec2(position));
+    for(uint s = 0; s < Params.species; ++s) {
+        for(int z = int(Evo_Ranges[s].lower); z < int(Evo_Ranges[s].upper); ++z) {
+            vec4 delta = imageLoad(
+                Evo_Fields,
+                ivec3(ivec2(position), z));
+            vec4 value = imageLoad(
+                Evo_Field,
+                ivec2(position));
 
-        value.x = clamp(value.x + delta.x, 0.0, 1.0);
-        value.y = clamp(value.y + delta.y, 0.0, 1.0);
-        value.z = clamp(value.z + delta.z, 0.0, 1.0);
-        value.w = clamp(value.w + delta.w, 0.0, 1.0);
+            value.x = clamp(value.x + delta.x, 0.0, 1.0);
+            value.y = clamp(value.y + delta.y, 0.0, 1.0);
+            value.z = clamp(value.z + delta.z, 0.0, 1.0);
+            value.w = clamp(value.w + delta.w, 0.0, 1.0);
+            if(delta.w < 0.0)
+                regrowth.x = Params.regrowth_delay;
 
-        imageStore(
-            Evo_Field,
-            ivec2(position),
-            value);
+            imageStore(
+                Evo_Field,
+                ivec2(position),
+                value);
+        }
     }
 
-    /* Weave all of the predator data. */
-    for(int z = int(Evo_LowerPredator); z < Evo_UpperPredator; ++z) {
-        vec4 delta = imageLoad(
-            Evo_PredatorFields,
-            ivec3(ivec2(position), z));
-        vec4 value = imageLoad(
-            Evo_Field,
-            ivec2(position));
-
-        value.x = clamp(value.x + delta.x, 0.0, 1.0);
-        value.y = clamp(value.y + delta.y, 0.0, 1.0);
-        value.z = clamp(value.z + delta.z, 0.0, 1.0);
-        value.w = clamp(value.w + delta.w, 0.0, 1.0);
-
-        imageStore(
-            Evo_Field,
-            ivec2(position),
-            value);
-    }
-
-    /* Perform growth and decay. */
+    /* Perform growth and decay. Grass grows as fast as the fertility of the
+     * terrain allows it to. */
     vec4 value = imageLoad(
         Evo_Field,
         ivec2(position));
+    float fertility = imageLoad(
+        Evo_Terrain,
+        ivec2(position))[TERRAIN_FERTILITY];
 
-    value.x = clamp(value.x - Params.decomposition_rate * Params.delta, 0.0, 1.0);
-    value.y = clamp(value.y - Params.decomposition_rate * Params.delta, 0.0, 1.0);
-    value.z = clamp(value.z - Params.decomposition_rate * Params.delta, 0.0, 1.0);
-    value.w = clamp(value.w + Params.growth_rate        * Params.delta, 0.0, 1.0);
+    value.x = Decay(value.x, Params.decomposition.x, Params.decay_mode.x);
+    value.y = Decay(value.y, Params.decomposition.y, Params.decay_mode.y);
+    value.z = Decay(value.z, Params.decomposition.z, Params.decay_mode.z);
+    float grass = value.w;
+    value.w = Regrow(
+        value.w,
+        Neighbours(ivec2(position)),
+        regrowth.x,
+        Params.growth_rate * fertility);
+    LedgerAdd(LEDGER_GROWN, value.w - grass);
 
     imageStore(
         Evo_Field,
         ivec2(position),
         value);
+    imageStore(
+        Evo_Regrowth,
+        ivec2(position),
+        regrowth);
 }
\ No newline at end of file
@@ -2,11 +2,12 @@
 #pragma shader_stage(fragment)
 
 layout(location = 0) in vec3 Normal;
+layout(location = 1) in vec3 Color;
 
 layout(location = 0) out vec4 AlbedoSpecular;
 layout(location = 1) out vec4 NormalShadow;
 
 void main() {
-    AlbedoSpecular = vec4(1.0, 1.0, 1.0, 0.5);
+    AlbedoSpecular = vec4(Color, 0.5);
     NormalShadow   = vec4(Normal, 1.0);
 }
@@ -0,0 +1,40 @@
+#version 450
+#pragma shader_stage(vertex)
+#include <Definitions/Dataset.glslh>
+#include <Definitions/RenderParams.glslh>
+
+layout(location = 0) in vec3 Position;
+layout(location = 1) in vec3 Normal;
+
+layout(location = 0) out vec3 OutNormal;
+layout(location = 1) out vec3 OutColor;
+
+/* Colors of the coldest and the warmest cells of the plane. These are kept
+ * dark, so that individuals stand out against them. */
+#define COLOR_COLD vec3(0.05, 0.10, 0.30)
+#define COLOR_WARM vec3(0.30, 0.08, 0.05)
+
+void main() {
+    /* Every instance is one cell of the plane, in row order. */
+    ivec2 size = imageSize(Evo_Temperature);
+    ivec2 cell = ivec2(gl_InstanceIndex % size.x, gl_InstanceIndex / size.x);
+
+    /* Stretch the square, which spans from minus one to one, over the cell. */
+    vec2 extent = Params.PlaneSize / vec2(size);
+    vec4 pos = vec4(
+        (vec2(cell) + (Position.xy + 1.0) / 2.0) * extent,
+        0.0,
+        1.0);
+
+    /* Write out the normals. */
+    OutNormal = (Params.ModelNormalTransform * vec4(Normal, 1.0)).xyz;
+
+    /* Cells are painted from cold to warm, following their temperature. */
+    float temperature = imageLoad(Evo_Temperature, cell).x;
+    float level = (temperature - Params.TemperatureRange.x)
+        / max(Params.TemperatureRange.y - Params.TemperatureRange.x, 1.19209290e-07);
+    OutColor = mix(COLOR_COLD, COLOR_WARM, clamp(level, 0.0, 1.0));
+
+    /* Put into view, project and dispatch. */
+    gl_Position = Params.ViewProjection * pos;
+}
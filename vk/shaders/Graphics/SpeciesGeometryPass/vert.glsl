@@ -0,0 +1,31 @@
+#version 450
+#pragma shader_stage(vertex)
+#include <Definitions/Dataset.glslh>
+#include <Definitions/RenderParams.glslh>
+
+layout(location = 0) in vec3 Position;
+layout(location = 1) in vec3 Normal;
+
+layout(location = 0) out vec3 OutNormal;
+layout(location = 1) out vec3 OutColor;
+
+void main() {
+    /* Transform the model point into a world point. Individuals that have
+     * not been born yet are collapsed down to a single point, and so are not
+     * drawn at all. */
+    float born = Evo_Individuals[gl_InstanceIndex].gestation > 0.0 ? 0.0 : 1.0;
+    vec4  pos  = Params.ModelTransformation * vec4(Position * born, 1.0);
+
+    /* Move this individual by the position it's in. */
+    pos.x += Evo_Individuals[gl_InstanceIndex].position.x;
+    pos.y += Evo_Individuals[gl_InstanceIndex].position.y;
+
+    /* Write out the normals. */
+    OutNormal = (Params.ModelNormalTransform * vec4(Normal, 1.0)).xyz;
+
+    /* Individuals are painted with the color of their signature. */
+    OutColor = Evo_Individuals[gl_InstanceIndex].signature.xyz;
+
+    /* Put into view, project and dispatch. */
+    gl_Position = Params.ViewProjection * pos;
+}
@@ -1,35 +1,29 @@
-use crate::settings::Group;
+use crate::settings::{Group, Simulation, GRASS, MAX_RAYS};
 use std::ops::Range;
 use std::convert::TryInto;
+use crate::statistics::EnergyLedger;
 
-/** Create a new population from the given preference group. */
+/** Create a new population from the given preference group, seeded from its
+ * genome file, if it has one. */
 pub fn population(group: &Group) -> Vec<Individual> {
 	let init2 = ||
 		if group.init_to_random {
-			[ rand::random(), rand::random() ]
+			[ crate::random::random(), crate::random::random() ]
 		} else {
 			[ 0.0, 0.0 ]
 		};
-	let init14 = ||
+	let init_inputs = || {
+		let mut weights = [0.0; NN_INPUTS];
 		if group.init_to_random {
-			[
-				rand::random(), rand::random(), rand::random(), rand::random(),
-				rand::random(), rand::random(), rand::random(), rand::random(),
-				rand::random(), rand::random(), rand::random(), rand::random(),
-				rand::random(), rand::random(),
-			]
-		} else {
-			[
-				0.0, 0.0, 0.0, 0.0, 0.0, 0.0,
-				0.0, 0.0, 0.0, 0.0, 0.0, 0.0,
-				0.0, 0.0
-			]
-		};
+			weights.iter_mut().for_each(|weight| *weight = crate::random::random());
+		}
+		weights
+	};
 	let init5 = ||
 		if group.init_to_random {
 			[
-				rand::random(), rand::random(), rand::random(),
-				rand::random(), rand::random(),
+				crate::random::random(), crate::random::random(), crate::random::random(),
+				crate::random::random(), crate::random::random(),
 			]
 		} else {
 			[
@@ -37,7 +31,7 @@ pub fn population(group: &Group) -> Vec<Individual> {
 			]
 		};
 
-	(0..group.budget)
+	let mut population = (0..group.budget)
 		.into_iter()
 		.map(|_| {
 			Individual {
@@ -59,40 +53,72 @@ pub fn population(group: &Group) -> Vec<Individual> {
 				},
 				velocity: init2(),
 				energy: init2()[0],
+				age: 0.0,
+				handling: 0.0,
+				cooldown: 0.0,
+				gestation: 0.0,
+				id: 0,
+				parents: [0, 0],
+				generation: 0,
+				birth_step: 0,
+				offspring: 0,
+				gathered: 0.0,
+				signature: [
+					group.signature.red,
+					group.signature.green,
+					group.signature.blue
+				],
 				weights: [
-					init14(), init14(), init14(),
-					init14(), init14(),
+					init_inputs(), init_inputs(), init_inputs(),
+					init_inputs(), init_inputs(),
 				],
 				biases: init5()
 			}
 		})
+		.collect::<Vec<_>>();
+
+	if let Err(what) = crate::genome::seed(group, &mut population) {
+		warn!("could not seed species {} from its genome file, keeping its \
+			initial genomes: {}", group.name, what);
+	}
+
+	population
+}
+
+/** Create new populations for all species in the simulation, in the order the
+ * species were declared. Every species gets as many individuals as its budget,
+ * and every individual gets its place in the whole population buffer, counting
+ * from one, as its identifier. The identifiers of individuals born later on
+ * start right after the last of these. */
+pub fn populations(simulation: &Simulation) -> Vec<Vec<Individual>> {
+	let mut id = 0;
+	simulation.species
+		.iter()
+		.map(|group| {
+			let mut population = population(group);
+			for individual in &mut population {
+				id += 1;
+				individual.id = id;
+			}
+
+			population
+		})
 		.collect()
 }
 
-/** Create a new population with the given parameters and serialize it to
- * `std430`, storing it in the given buffer, after a clear operation. */
-pub fn population_bytes_with_buffer(group: &Group, mut buf: Vec<u8>) -> Vec<u8> {
-	buf.clear();
-	{
-		let needed = group.budget as usize * Individual::BYTE_SIZE;
-		if buf.capacity() < needed {
-			buf.reserve_exact(needed - buf.capacity());
-		}
-	}
-	population(group)
-		.into_iter()
-		.fold(buf, |mut data, individual| {
+/** Create new populations for all species in the simulation and serialize them
+ * to `std430`, one after the other, in the order the species were declared.
+ * Every species takes up as many individuals as its budget. */
+pub fn populations_bytes(simulation: &Simulation) -> Vec<u8> {
+	populations(simulation)
+		.iter()
+		.flatten()
+		.fold(Vec::new(), |mut data, individual| {
 			individual.bytes(&mut data);
 			data
 		})
 }
 
-/** Create a new population with the given parameters and serialize it to
- * `std430`, storing it in a newly allocated buffer. */
-pub fn population_bytes(group: &Group) -> Vec<u8> {
-	population_bytes_with_buffer(group, Vec::with_capacity(group.budget as usize * Individual::BYTE_SIZE))
-}
-
 /** Matrix type. */
 #[repr(transparent)]
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -235,6 +261,11 @@ pub struct RenderParameters {
 	pub world_transformation: Matrix4,
 	/** Transformation matrix from world space into projected view space. */
 	pub projection: Matrix4,
+	/** Size of the simulation plane. */
+	pub plane: [f32; 2],
+	/** Lowest and highest temperatures on the plane, between which the
+	 * temperature is shown. */
+	pub temperature_range: [f32; 2],
 }
 impl RenderParameters {
 	/** Write out the bytes of this structure into a vector.
@@ -253,50 +284,92 @@ impl RenderParameters {
 			.transpose()
 			.bytes(buf);
 		written += self.projection.transpose().bytes(buf);
+		written += write_vec(buf, self.plane);
+		written += write_vec(buf, self.temperature_range);
 
 		written
 	}
 }
 
-/** Set of compute parameters. */
+/** Set of compute parameters shared by all species. */
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub struct ComputeParameters {
 	/** Time in seconds since the last iteration. */
 	pub delta: f32,
 	/** Growth rate of the grass on the field, in units per second. */
 	pub growth_rate: f32,
-	/** Decomposition rate of the chemicals on the field, in units per second. */
-	pub decomposition_rate: f32,
-	/** Radius of vision of individuals in the herbivore group. */
-	pub herbivore_view_radius: f32,
-	/** Radius of vision of individuals in the predator group. */
-	pub predator_view_radius: f32,
-	/** Maximum speed of a herbivore, in distance per second. */
-	pub herbivore_max_speed: f32,
-	/** Maximum speed of a predator, in distance per second. */
-	pub predator_max_speed: f32,
-	/** Energy needed to be put in by both herbivore parents to reproduce. */
-	pub herbivore_reproduction_cost: f32,
-	/** Energy needed to be put in by both predator parents to reproduce. */
-	pub predator_reproduction_cost: f32,
-	/** Minimum energy needed for two herbivores to decide to reproduce. */
-	pub herbivore_reproduction_min: f32,
-	/** Minimum energy needed for two predators to decide to reproduce. */
-	pub predator_reproduction_min: f32,
-	/** Energy newborns of the herbivore group start off with. */
-	pub herbivore_offspring_energy: f32,
-	/** Energy newborns of the predator group start off with. */
-	pub predator_offspring_energy: f32,
-	/** Penalty for existing and walking as a herbivore. The penalty value
-	 * will linearly scale from the first to the second point of this vector as
-	 * the walking speed increases from zero to one. */
-	pub herbivore_penalty: [f32; 2],
-	/** Penalty for existing and walking as a herbivore. The penalty value
-	 * will linearly scale from the first to the second point of this vector as
-	 * the walking speed increases from zero to one. */
-	pub predator_penalty: [f32; 2],
+	/** Seed for the random number generators in the shaders. This should be
+	 * changed at every iteration. */
+	pub seed: u32,
+	/** Whether energy is conserved between the field, prey, parents and
+	 * offspring. */
+	pub energy_conservation: u32,
 	/** Size of the simulation field. */
-	pub simulation: [f32; 2]
+	pub simulation: [f32; 2],
+	/** Number of species in the simulation. */
+	pub species: u32,
+	/** Whether individuals sense their proximity to the closest wall. */
+	pub wall_proximity: u32,
+	/** Number of steps the diffusion of the chemicals is split into. */
+	pub diffusion_steps: u32,
+	/** Regrowth model of the grass, as given by the discriminant of its
+	 * `settings::RegrowthModel` value. */
+	pub regrowth_model: u32,
+	/** Density grass grows back from under the logistic model. */
+	pub regrowth_seed: f32,
+	/** How strongly the grass around a cell seeds it under the patches
+	 * model. */
+	pub regrowth_spread: f32,
+	/** Decomposition rates of the red, green and blue chemicals. */
+	pub decomposition: [f32; 3],
+	/** Decay modes of the red, green and blue chemicals, as given by the
+	 * discriminants of their `settings::DecayMode` values. */
+	pub decay_mode: [u32; 3],
+	/** Diffusion coefficients of the red, green and blue chemicals. */
+	pub diffusion: [f32; 3],
+	/** Time during which grass does not grow back after being grazed under
+	 * the delayed model. */
+	pub regrowth_delay: f32,
+	/** Factor the metabolism of the individuals of all species is scaled
+	 * by. */
+	pub metabolism: f32,
+	/** Whether the grass within the wipe region should be removed before
+	 * the iteration. */
+	pub wipe_grass: u32,
+	/** Region the grass is removed from, in the same format as the spawn
+	 * range of a species. */
+	pub wipe_region: [f32; 4],
+	/** Width and height of every cell of the spatial grid. */
+	pub grid_cell_size: f32,
+	/** Maximum number of individuals in every cell of the spatial grid. */
+	pub grid_capacity: u32,
+	/** Number of cells across and down the spatial grid. */
+	pub grid_size: [u32; 2],
+	/** Whether individuals that get too close to each other are pushed
+	 * apart. */
+	pub crowding: u32,
+	/** Distance under which two individuals push each other apart. */
+	pub separation_radius: f32,
+	/** Speed at which an individual right on top of another one is pushed
+	 * away from it. */
+	pub separation_force: f32,
+	/** What happens at the edges of the plane, as the discriminant of the
+	 * boundary. */
+	pub boundary: u32,
+	/** Lowest and highest temperatures on the plane, before they get scaled
+	 * by the schedule of the temperature. */
+	pub temperature_range: [f32; 2],
+	/** Factor the temperature all over the plane is scaled by, following its
+	 * schedule. */
+	pub temperature: f32,
+	/** Whether individuals sense the temperature under them. */
+	pub temperature_input: u32,
+	/** Identifier of the first individual born during the iteration. */
+	pub next_id: u64,
+	/** Number of steps simulated before the iteration. */
+	pub step: u64,
+	/** Whether births and deaths are recorded for the lineage log. */
+	pub lineage: u32
 }
 impl ComputeParameters {
 	/** Write out the bytes of this structure into a vector.
@@ -311,24 +384,277 @@ impl ComputeParameters {
 		let mut written = 0;
 		written += write_vec(buf, [
 			self.delta,
-			self.growth_rate,
-			self.decomposition_rate,
-			self.herbivore_view_radius,
-			self.predator_view_radius,
-			self.herbivore_max_speed,
-			self.predator_max_speed,
-			self.herbivore_reproduction_cost,
-			self.predator_reproduction_cost,
-			self.herbivore_reproduction_min,
-			self.predator_reproduction_min,
-			self.herbivore_offspring_energy,
-			self.predator_offspring_energy
+			self.growth_rate
+		]);
+		written += write_u32(buf, self.seed);
+		written += write_u32(buf, self.energy_conservation);
+		written += write_vec(buf, self.simulation);
+		written += write_u32(buf, self.species);
+		written += write_u32(buf, self.wall_proximity);
+		written += write_u32(buf, self.diffusion_steps);
+		written += write_u32(buf, self.regrowth_model);
+		written += write_vec(buf, [
+			self.regrowth_seed,
+			self.regrowth_spread
+		]);
+
+		/* Offset 12N: Write the per-chemical vectors, padded out to full
+		 * four component vectors. */
+		written += write_vec(buf, [
+			self.decomposition[0],
+			self.decomposition[1],
+			self.decomposition[2],
+			0.0
 		]);
+		for mode in self.decay_mode.iter() {
+			written += write_u32(buf, *mode);
+		}
 		written += write_pad(buf, 4);
+		written += write_vec(buf, [
+			self.diffusion[0],
+			self.diffusion[1],
+			self.diffusion[2],
+			0.0
+		]);
+		written += write_vec(buf, [
+			self.regrowth_delay,
+			self.metabolism
+		]);
+		written += write_u32(buf, self.wipe_grass);
+		written += write_pad(buf, 4);
+		written += write_vec(buf, self.wipe_region);
+		written += write_vec(buf, [self.grid_cell_size]);
+		written += write_u32(buf, self.grid_capacity);
+		written += write_u32(buf, self.grid_size[0]);
+		written += write_u32(buf, self.grid_size[1]);
+		written += write_u32(buf, self.crowding);
+		written += write_vec(buf, [
+			self.separation_radius,
+			self.separation_force
+		]);
+		written += write_u32(buf, self.boundary);
+		written += write_vec(buf, self.temperature_range);
+		written += write_vec(buf, [self.temperature]);
+		written += write_u32(buf, self.temperature_input);
+		written += write_u64(buf, self.next_id);
+		written += write_u64(buf, self.step);
+		written += write_u32(buf, self.lineage);
 
-		written += write_vec(buf, self.herbivore_penalty);
-		written += write_vec(buf, self.predator_penalty);
-		written += write_vec(buf, self.simulation);
+		/* Pad the structure out to the next 4N alignment. */
+		written += write_pad(buf, 12);
+
+		written
+	}
+}
+
+/** Set of compute parameters specific to a single species. */
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SpeciesParameters {
+	/** Index of the first individual of this species in the population
+	 * buffer. */
+	pub base: u32,
+	/** Maximum number of individuals of this species. */
+	pub budget: u32,
+	/** Whether individuals of this species feed on grass. */
+	pub grazes: u32,
+	/** Bit mask of the species individuals of this species feed on, with the
+	 * bit at position `i` set if they feed on the species at index `i`. */
+	pub prey: u32,
+	/** Radius of vision of individuals. */
+	pub view_radius: f32,
+	/** Amount of energy consumed per second while standing still. */
+	pub metabolism_min: f32,
+	/** Amount of energy consumed per second while running at max speed. */
+	pub metabolism_max: f32,
+	/** Maximum speed of an individual, in distance per second. */
+	pub max_speed: f32,
+	/** Energy needed to be put in by both parents to reproduce. */
+	pub reproduction_cost: f32,
+	/** Minimum energy needed for two individuals to decide to reproduce. */
+	pub reproduction_min: f32,
+	/** Energy newborns start off with. */
+	pub offspring_energy: f32,
+	/** Mate selection strategy, as given by the discriminant of its
+	 * `settings::MateSelection` value. */
+	pub mate_selection: u32,
+	/** Number of candidates in every mating tournament. */
+	pub tournament_size: u32,
+	/** Whether individuals only mate within the mating radius. */
+	pub local_mating: u32,
+	/** Maximum distance between two partners under local mating. */
+	pub mating_radius: f32,
+	/** Maximum distance from the initiating parent at which offspring are
+	 * born under local mating. */
+	pub offspring_jitter: f32,
+	/** Fraction of the energy in grass actually gained by an individual. */
+	pub feeding_efficiency: f32,
+	/** Fraction of the energy paid by parents passed on to their offspring. */
+	pub reproduction_efficiency: f32,
+	/** Maximum distance between a hunting individual and its prey. */
+	pub attack_radius: f32,
+	/** Probability an attack succeeds. */
+	pub capture_probability: f32,
+	/** Fraction of the energy of the prey gained by the hunter. */
+	pub energy_transfer: f32,
+	/** Time, in seconds, spent handling prey before hunting again. */
+	pub handling_time: f32,
+	/** Energy below which individuals go out hunting. */
+	pub hunting_threshold: f32,
+	/** Minimum age an individual needs to have reached to reproduce. */
+	pub reproduction_age: f32,
+	/** Maximum age of an individual. Zero means they never die of old age. */
+	pub lifespan: f32,
+	/** Increase in the metabolism of an individual per second of age, as a
+	 * fraction of its base metabolism. */
+	pub senescence: f32,
+	/** Maximum deviation of the signature of an offspring from the average of
+	 * its parents, per chemical. */
+	pub signature_mutation: f32,
+	/** What happens to births once the species has reached its budget, as
+	 * given by the discriminant of its `settings::BudgetPolicy` value. */
+	pub budget_policy: u32,
+	/** Bit mask of the species that feed on this one, with the bit at
+	 * position `i` set if the species at index `i` does. */
+	pub predators: u32,
+	/** Whether individuals sense the individuals around them as inputs to
+	 * their networks. */
+	pub neighbour_inputs: u32,
+	/** How individuals see what is around them, as given by the discriminant
+	 * of its `settings::Vision` value. */
+	pub vision: u32,
+	/** Number of rays cast by every individual under ray vision. */
+	pub ray_count: u32,
+	/** Angle, in radians, the rays are spread out over. */
+	pub field_of_view: f32,
+	/** Width of every ray. */
+	pub ray_width: f32,
+	/** Temperature at which the metabolism of individuals is the lowest. */
+	pub preferred_temperature: f32,
+	/** Distance to the preferred temperature at which the metabolism of
+	 * individuals doubles, or zero if they do not feel the temperature. */
+	pub temperature_tolerance: f32,
+	/** Where offspring are placed, as given by the discriminant of its
+	 * `settings::OffspringPlacement` value. */
+	pub offspring_placement: u32,
+	/** Maximum distance from the mother at which offspring are placed around
+	 * her. */
+	pub scatter_radius: f32,
+	/** Time, in seconds, parents wait after reproducing before they may
+	 * reproduce again. */
+	pub reproduction_cooldown: f32,
+	/** Time, in seconds, between the conception and the birth of offspring. */
+	pub gestation: f32
+}
+impl SpeciesParameters {
+	/** Create the parameters for the species at the given index of the
+	 * simulation, whose individuals start at the given index of the
+	 * population buffer. */
+	pub fn new(simulation: &Simulation, species: usize, base: u32) -> Self {
+		let group = &simulation.species[species];
+		Self {
+			base,
+			budget: group.budget,
+			grazes: group.eats(GRASS) as u32,
+			prey: simulation.prey_of(species)
+				.fold(0, |mask, prey| mask | (1 << prey)),
+			view_radius: group.view_radius,
+			metabolism_min: group.metabolism_min,
+			metabolism_max: group.metabolism_max,
+			max_speed: group.max_speed,
+			reproduction_cost: group.reproduction_cost,
+			reproduction_min: group.reproduction_min,
+			offspring_energy: group.offspring_energy,
+			mate_selection: group.mate_selection as u32,
+			tournament_size: group.tournament_size,
+			local_mating: group.local_mating as u32,
+			mating_radius: group.mating_radius,
+			offspring_jitter: group.offspring_jitter,
+			feeding_efficiency: group.feeding_efficiency,
+			reproduction_efficiency: group.reproduction_efficiency,
+			attack_radius: group.attack_radius,
+			capture_probability: group.capture_probability,
+			energy_transfer: group.energy_transfer,
+			handling_time: group.handling_time,
+			hunting_threshold: group.hunting_threshold,
+			reproduction_age: group.reproduction_age,
+			lifespan: group.lifespan,
+			senescence: group.senescence,
+			signature_mutation: group.signature_mutation,
+			budget_policy: group.budget_policy as u32,
+			predators: simulation.predators_of(species)
+				.fold(0, |mask, predator| mask | (1 << predator)),
+			neighbour_inputs: group.neighbour_inputs as u32,
+			vision: group.vision as u32,
+			ray_count: group.rays.count.min(MAX_RAYS as u32),
+			field_of_view: group.rays.field_of_view.to_radians(),
+			ray_width: group.rays.width,
+			preferred_temperature: group.preferred_temperature,
+			temperature_tolerance: group.temperature_tolerance,
+			offspring_placement: group.offspring_placement as u32,
+			scatter_radius: group.scatter_radius,
+			reproduction_cooldown: group.reproduction_cooldown,
+			gestation: group.gestation
+		}
+	}
+
+	/** Write out the bytes of this structure into a vector.
+	 *
+	 * # Layout
+	 * The bytes written are guaranteed to be laid out in `std430`, as specified
+	 * in the OpenGL 4.5 Specification that can be found at
+	 * https://www.khronos.org/registry/OpenGL/specs/gl/glspec45.core.pdf, under
+	 * Section 7.6.2.2 (Standard Uniform Block Layout).
+	 */
+	pub fn bytes(&self, buf: &mut Vec<u8>) -> usize {
+		let mut written = 0;
+		written += write_u32(buf, self.base);
+		written += write_u32(buf, self.budget);
+		written += write_u32(buf, self.grazes);
+		written += write_u32(buf, self.prey);
+		written += write_vec(buf, [
+			self.view_radius,
+			self.metabolism_min,
+			self.metabolism_max,
+			self.max_speed,
+			self.reproduction_cost,
+			self.reproduction_min,
+			self.offspring_energy
+		]);
+		written += write_u32(buf, self.mate_selection);
+		written += write_u32(buf, self.tournament_size);
+		written += write_u32(buf, self.local_mating);
+		written += write_vec(buf, [
+			self.mating_radius,
+			self.offspring_jitter,
+			self.feeding_efficiency,
+			self.reproduction_efficiency,
+			self.attack_radius,
+			self.capture_probability,
+			self.energy_transfer,
+			self.handling_time,
+			self.hunting_threshold,
+			self.reproduction_age,
+			self.lifespan,
+			self.senescence,
+			self.signature_mutation
+		]);
+		written += write_u32(buf, self.budget_policy);
+		written += write_u32(buf, self.predators);
+		written += write_u32(buf, self.neighbour_inputs);
+		written += write_u32(buf, self.vision);
+		written += write_u32(buf, self.ray_count);
+		written += write_vec(buf, [
+			self.field_of_view,
+			self.ray_width,
+			self.preferred_temperature,
+			self.temperature_tolerance
+		]);
+		written += write_u32(buf, self.offspring_placement);
+		written += write_vec(buf, [
+			self.scatter_radius,
+			self.reproduction_cooldown,
+			self.gestation
+		]);
 
 		written
 	}
@@ -338,27 +664,68 @@ impl ComputeParameters {
  * the host device. */
 #[derive(Debug, Clone, PartialEq)]
 pub struct BackChannel {
-	/** Herbivore dispatch range for the next iteration. */
-	pub herbivores: Range<u32>,
-	/** Predator dispatch range for the next iteration. */
-	pub predators: Range<u32>
+	/** Energy ledger of the last iteration. */
+	pub ledger: EnergyLedger,
+	/** Dispatch range of every species for the next iteration. These are
+	 * indices into the whole population buffer, rather than into the slice of
+	 * the buffer belonging to the species. */
+	pub ranges: Vec<Range<u32>>,
+	/** Number of births in every species that did not happen because the
+	 * species had reached its budget, since the start of the simulation. */
+	pub rejected: Vec<u32>,
+	/** Number of individuals in every species that made room for a newborn
+	 * because the species had reached its budget, since the start of the
+	 * simulation. */
+	pub replaced: Vec<u32>
 }
 impl BackChannel {
-	/** Create this structure with arbitrary bytes from a buffer. */
-	pub fn from_bytes<A: AsRef<[u8]>>(bytes: A) -> Self {
+	/** Offset of the energy ledger into the back channel, in bytes. */
+	pub const LEDGER_OFFSET: usize = 0;
+	/** Offset of the dispatch ranges into the back channel, in bytes. */
+	pub const RANGES_OFFSET: usize = EnergyLedger::ENTRIES * 8;
+	/** Size of the dispatch range of a species, along with its birth
+	 * counters, in bytes. */
+	pub const RANGE_SIZE: usize = 16;
+
+	/** Size of a back channel holding the ranges for the given number of
+	 * species, in bytes. */
+	pub fn byte_size(species: usize) -> usize {
+		Self::RANGES_OFFSET + species * Self::RANGE_SIZE
+	}
+
+	/** Create this structure with arbitrary bytes from a buffer, holding the
+	 * ranges for the given number of species. */
+	pub fn from_bytes<A: AsRef<[u8]>>(bytes: A, species: usize) -> Self {
 		let data = bytes.as_ref();
+		let u = |offset: usize| u32::from_ne_bytes(
+			(&data[offset..offset + 4]).try_into().unwrap());
 
-		let a = u32::from_ne_bytes((&data[ 0..4 ]).try_into().unwrap());
-		let b = u32::from_ne_bytes((&data[ 4..8 ]).try_into().unwrap());
-		let c = u32::from_ne_bytes((&data[ 8..12]).try_into().unwrap());
-		let d = u32::from_ne_bytes((&data[12..16]).try_into().unwrap());
+		let mut ledger = [0; EnergyLedger::ENTRIES];
+		for (i, entry) in ledger.iter_mut().enumerate() {
+			*entry = read_u64(&data[Self::LEDGER_OFFSET + i * 8..]);
+		}
 
-		if a > b { panic!("lower herbivore bound > upper herbivore bound"); }
-		if c > d { panic!("lower predator bound > upper predator bound"); }
+		let ranges = (0..species)
+			.map(|i| {
+				let offset = Self::RANGES_OFFSET + i * Self::RANGE_SIZE;
+				let (lower, upper) = (u(offset), u(offset + 4));
+				if lower > upper {
+					panic!("lower bound > upper bound for species {}: {} > {}",
+						i, lower, upper);
+				}
+
+				lower..upper
+			})
+			.collect();
+		let counter = |index: usize| (0..species)
+			.map(|i| u(Self::RANGES_OFFSET + i * Self::RANGE_SIZE + index * 4))
+			.collect();
 
 		Self {
-			herbivores: a..b,
-			predators: c..d
+			ledger: EnergyLedger::from_fixed(ledger),
+			ranges,
+			rejected: counter(2),
+			replaced: counter(3)
 		}
 	}
 
@@ -372,36 +739,164 @@ impl BackChannel {
 	 */
 	pub fn bytes(&self, buf: &mut Vec<u8>) -> usize {
 		let mut written = 0;
-		written += write_u32(buf, self.herbivores.start);
-		written += write_u32(buf, self.herbivores.end);
+		for entry in self.ledger.to_fixed().iter() {
+			written += write_u64(buf, *entry);
+		}
 
-		written += write_u32(buf, self.predators.start);
-		written += write_u32(buf, self.predators.end);
+		for (i, range) in self.ranges.iter().enumerate() {
+			written += write_u32(buf, range.start);
+			written += write_u32(buf, range.end);
+			written += write_u32(buf, self.rejected[i]);
+			written += write_u32(buf, self.replaced[i]);
+		}
 
 		written
 	}
 }
 
-/** The data for an individual.
- * The input parameters for the individual are the following, in order:
- * - `0`:  Velocity X
- * - `1`:  Velocity Y
- * - `2`:  Red Gradient X
- * - `3`:  Red Gradient Y
- * - `4`:  Red Intensity
- * - `5`:  Green Gradient X
- * - `6`:  Green Gradient Y
- * - `7`:  Green Intensity
- * - `8`:  Blue Gradient X
- * - `9`:  Blue Gradient Y
- * - `10`: Blue Intensity
- *
- * The output parameters of the individual are the following, in order:
- * - `0`: Movement Angle ([0; 1[)
- * - `1`: Movement Speed
- * - `2`: Red Deposit
- * - `3`: Green Deposit
- * - `4`: Blue Deposit
+/** Events recorded in the lineage log. */
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum LineageEvent {
+	/** An individual was born, or was conceived, if it is born after a
+	 * gestation, or joined the simulation from outside of it. */
+	Birth = 0,
+	/** An individual died or left the simulation. */
+	Death = 1,
+}
+
+/** Birth or death of an individual, as recorded in the lineage log. */
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct LineageRecord {
+	/** What happened to the individual. */
+	pub event: LineageEvent,
+	/** Index of the species of the individual. */
+	pub species: u32,
+	/** Identifier of the individual. */
+	pub id: u64,
+	/** Identifiers of the parents of the individual, mother first. */
+	pub parents: [u64; 2],
+	/** Generation of the individual. */
+	pub generation: u32,
+	/** Step of the simulation the event happened at. */
+	pub step: u64,
+	/** Age of the individual when the event happened, in seconds. */
+	pub age: f32,
+}
+impl LineageRecord {
+	/** Size of a record in the lineage buffer, in bytes. */
+	pub const BYTE_SIZE: usize = 48;
+	/** Offset of the records into the lineage buffer, in bytes, past the
+	 * number of births and the number of records. */
+	pub const RECORDS_OFFSET: usize = 8;
+
+	/** Record of the given event having happened to the given individual of
+	 * the species at the given index, at the given step. */
+	pub fn new(event: LineageEvent, species: usize, individual: &Individual, step: u64) -> Self {
+		Self {
+			event,
+			species: species as u32,
+			id: individual.id,
+			parents: individual.parents,
+			generation: individual.generation,
+			step,
+			age: individual.age
+		}
+	}
+
+	/** Create this structure from the bytes of a record in the lineage
+	 * buffer, laid out as `Evo_LineageRecord` in `Dataset.glslh`.
+	 *
+	 * # Panics
+	 * This function panics if there are fewer than `LineageRecord::BYTE_SIZE`
+	 * bytes in the given buffer. */
+	pub fn from_bytes<A: AsRef<[u8]>>(bytes: A) -> Self {
+		let data = bytes.as_ref();
+		if data.len() < Self::BYTE_SIZE {
+			panic!("not enough bytes for a lineage record: {} < {}",
+				data.len(),
+				Self::BYTE_SIZE);
+		}
+
+		let u = |offset: usize| u32::from_ne_bytes(
+			(&data[offset..offset + 4]).try_into().unwrap());
+		let l = |offset: usize| read_u64(&data[offset..]);
+
+		Self {
+			id: l(0),
+			parents: [l(8), l(16)],
+			step: l(24),
+			event: if u(32) == LineageEvent::Death as u32 {
+				LineageEvent::Death
+			} else {
+				LineageEvent::Birth
+			},
+			species: u(36),
+			generation: u(40),
+			age: f32::from_bits(u(44))
+		}
+	}
+}
+
+/** Read a lineage buffer back from its bytes, as the number of births, the
+ * number of records written to it and the records themselves, leaving out the
+ * records that did not fit in it. */
+pub fn lineage_from_bytes<A: AsRef<[u8]>>(bytes: A) -> (u32, u32, Vec<LineageRecord>) {
+	let data = bytes.as_ref();
+	let u = |offset: usize| u32::from_ne_bytes(
+		(&data[offset..offset + 4]).try_into().unwrap());
+
+	let (births, count) = (u(0), u(4));
+	let records = data[LineageRecord::RECORDS_OFFSET..]
+		.chunks_exact(LineageRecord::BYTE_SIZE)
+		.take(count as usize)
+		.map(LineageRecord::from_bytes)
+		.collect();
+
+	(births, count, records)
+}
+
+/** Number of inputs to the neural network of every individual. */
+pub const NN_INPUTS: usize = NN_INPUT_RAYS + MAX_RAYS * NN_RAY_INPUTS;
+/** Number of outputs of the neural network of every individual. */
+pub const NN_OUTPUTS: usize = 5;
+
+/* Offsets of the inputs to the neural network of every individual. The layout
+ * of the inputs is described in `Dataset.glslh`, and these must match the
+ * `NN_INPUT_*` definitions in there. */
+/** Velocity, in X and Y order. */
+pub const NN_INPUT_VELOCITY: usize = 0;
+/** Gradients of the red, green and blue chemicals and of the grass, each as
+ * its X and Y direction, followed by its intensity. */
+pub const NN_INPUT_GRADIENTS: usize = 2;
+/** Proximity to the nearest wall. */
+pub const NN_INPUT_WALL_PROXIMITY: usize = 14;
+/** Direction, in X and Y order, and distance to the nearest predator. */
+pub const NN_INPUT_PREDATOR: usize = 15;
+/** Direction, in X and Y order, and distance to the nearest prey. */
+pub const NN_INPUT_PREY: usize = 18;
+/** Direction, in X and Y order, and distance to the nearest member of the
+ * same group. */
+pub const NN_INPUT_KIN: usize = 21;
+/** Density of individuals around. */
+pub const NN_INPUT_DENSITY: usize = 24;
+/** Temperature under the individual. */
+pub const NN_INPUT_TEMPERATURE: usize = 25;
+/** Inputs of every vision ray, one ray after the other, each laid out as
+ * given by the `NN_RAY_*` offsets. */
+pub const NN_INPUT_RAYS: usize = 26;
+
+/** Number of inputs of every vision ray. */
+pub const NN_RAY_INPUTS: usize = 9;
+/** Distance to the first thing hit by the ray. */
+pub const NN_RAY_DISTANCE: usize = 0;
+/** Whether the first thing hit is a predator, a prey, a member of the same
+ * group or a wall, in that order. */
+pub const NN_RAY_KIND: usize = 1;
+/** Average red, green, blue and grass values of the field passed over. */
+pub const NN_RAY_FIELD: usize = 5;
+
+/** The data for an individual. The inputs and outputs of its neural network
+ * are laid out as described in `Dataset.glslh`.
  */
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub struct Individual {
@@ -414,22 +909,82 @@ pub struct Individual {
 	/** Amount of energy this individual can still spend. */
 	pub energy: f32,
 
+	/** Amount of simulated time this individual has been alive for, in
+	 * seconds. */
+	pub age: f32,
+
+	/** Amount of simulated time, in seconds, left before this individual is
+	 * done handling its last prey and may hunt again. */
+	pub handling: f32,
+
+	/** Amount of simulated time, in seconds, left before this individual may
+	 * reproduce again. */
+	pub cooldown: f32,
+
+	/** Amount of simulated time, in seconds, left before this individual is
+	 * born. Individuals that have not been born yet take up their room in the
+	 * population, but do nothing and cannot be seen. */
+	pub gestation: f32,
+
+	/** Identifier of this individual, unique across all species over the
+	 * whole simulation. Individuals that have not been given one yet have an
+	 * identifier of zero. */
+	pub id: u64,
+
+	/** Identifiers of the parents of this individual, mother first, or zero
+	 * for individuals that did not have any. */
+	pub parents: [u64; 2],
+
+	/** Number of generations between this individual and the individuals
+	 * that did not have any parents, which are of generation zero. */
+	pub generation: u32,
+
+	/** Step of the simulation at which this individual was born, or was
+	 * conceived, for individuals born after a gestation. */
+	pub birth_step: u64,
+
+	/** Number of offspring this individual has had, whether they have been
+	 * born yet or not. */
+	pub offspring: u32,
+
+	/** Amount of energy this individual has gained from feeding on grass and
+	 * prey over its whole life. */
+	pub gathered: f32,
+
+	/** Chemical composition of the pheromones deposited by this individual,
+	 * in red, green and blue order. Each component is clamped between 0.0 and
+	 * 1.0 and is inherited, with mutation, by the offspring. */
+	pub signature: [f32; 3],
+
 	/** Weight matrix. This matrix is laid out such that a value at `[i][j]`
 	 * means the weight neuron `a[j]` will have on neuron `b[i]`, where `a` is
 	 * the input layer and `b` is the output layer. */
-	pub weights: [[f32; 14]; 5],
+	pub weights: [[f32; NN_INPUTS]; NN_OUTPUTS],
 
 	/** Array of output biases. */
-	pub biases: [f32; 5]
+	pub biases: [f32; NN_OUTPUTS]
 }
 impl Individual {
-	const BYTE_SIZE: usize = 0
+	/** Size of an individual in the population buffer, in bytes. */
+	pub const BYTE_SIZE: usize = 0
 	    + 8       		/* position */
 		+ 8      		/* velocity */
 		+ 4       		/* energy */
-	    + 12      		/* pad */
+		+ 4       		/* age */
+		+ 4       		/* handling */
+		+ 4       		/* cooldown */
+		+ 12      		/* signature */
+		+ 4       		/* gestation */
+		+ 8       		/* id */
+		+ 16      		/* parents */
+		+ 8       		/* birth step */
+		+ 4       		/* generation */
+		+ 4       		/* offspring */
+		+ 4       		/* gathered */
+		+ 4       		/* claim */
 		+ 32      		/* biases */
-		+ 64 * 4 * 4	/* weights */
+		+ NN_OUTPUTS * NN_INPUTS * 4	/* weights */
+		+ (16 - NN_OUTPUTS * NN_INPUTS * 4 % 16) % 16	/* pad */
 		+ 0;     		/* done */
 
 	/** Write out the bytes of this structure into a vector.
@@ -444,62 +999,103 @@ impl Individual {
 		let mut written = 0;
 		written += write_vec(buf, self.position);
 		written += write_vec(buf, self.velocity);
-		written += write_vec(buf, [self.energy]);
+		written += write_vec(buf, [self.energy, self.age, self.handling, self.cooldown]);
 
-		/* Offset 5N: Pad to the next 4N alignment. */
-		written += write_pad(buf, 12);
+		/* Offset 8N: Write the signature, with the gestation filling out the
+		 * rest of its vec4. */
+		written += write_vec(buf, &self.signature);
+		written += write_vec(buf, [self.gestation]);
+
+		/* Offset 12N: Write the lineage, then the offspring count and the
+		 * energy gathered, followed by the claim shaders put on individuals
+		 * during an iteration, which always starts out cleared. */
+		written += write_u64(buf, self.id);
+		written += write_u64(buf, self.parents[0]);
+		written += write_u64(buf, self.parents[1]);
+		written += write_u64(buf, self.birth_step);
+		written += write_u32(buf, self.generation);
+		written += write_u32(buf, self.offspring);
+		written += write_vec(buf, [self.gathered]);
+		written += write_u32(buf, 0);
 
-		/* Offset 8N: Write the weight vectors. */
+		/* Offset 24N: Write the bias vectors. */
 		written += write_vec(buf, &self.biases[0..4]);
 		written += write_vec(buf, &[self.biases[4], 0.0, 0.0, 0.0]);
 
-		/* Offset 16N: Write the A[0-3,0-3] sub-matrices. */
-		for i in 0..=3 {
-			for j in 0..=3 {
-				let column = |i: usize| -> [f32; 4] {
-					match j {
-						0 => [
-							self.weights[i][0],
-							self.weights[i][1],
-							self.weights[i][2],
-							self.weights[i][3],
-						],
-						1 => [
-							self.weights[i][4],
-							self.weights[i][5],
-							self.weights[i][6],
-							self.weights[i][7],
-						],
-						2 => [
-							self.weights[i][8],
-							self.weights[i][9],
-							self.weights[i][10],
-							self.weights[i][11],
-						],
-						3 => [
-							self.weights[i][12],
-							self.weights[i][13],
-							0.0,
-							0.0,
-						],
-						_ => unreachable!()
-					}
-				};
-				let row = |i: usize| match i {
-					i @ _ if i < 5  => column(i),
-					i @ _ if i < 16 => [0.0, 0.0, 0.0, 0.0],
-					_ => unreachable!()
-				};
-
-				written += write_vec(buf, &row(i * 4 + 0));
-				written += write_vec(buf, &row(i * 4 + 1));
-				written += write_vec(buf, &row(i * 4 + 2));
-				written += write_vec(buf, &row(i * 4 + 3));
-			}
+		/* Offset 32N: Write the weights, one output after the other, and pad
+		 * the structure out to the next 4N alignment. */
+		for output in &self.weights {
+			written += write_vec(buf, output);
 		}
+		written += write_pad(buf, Self::BYTE_SIZE - written);
 
 		debug_assert_eq!(written, Self::BYTE_SIZE, "Wrong byte size after write, please update Individual::BYTE_SIZE");
 	}
+
+	/** Create this structure from the bytes of an individual laid out by the
+	 * [`Individual::bytes()`] function.
+	 *
+	 * # Panics
+	 * This function panics if there are fewer than `Individual::BYTE_SIZE`
+	 * bytes in the given buffer. */
+	pub fn from_bytes<A: AsRef<[u8]>>(bytes: A) -> Self {
+		let data = bytes.as_ref();
+		if data.len() < Self::BYTE_SIZE {
+			panic!("not enough bytes for an individual: {} < {}",
+				data.len(),
+				Self::BYTE_SIZE);
+		}
+
+		let f = |offset: usize| f32::from_ne_bytes(
+			(&data[offset..offset + 4]).try_into().unwrap());
+		let u = |offset: usize| u32::from_ne_bytes(
+			(&data[offset..offset + 4]).try_into().unwrap());
+		let l = |offset: usize| read_u64(&data[offset..]);
+
+		let mut weights = [[0.0; NN_INPUTS]; NN_OUTPUTS];
+		for i in 0..NN_OUTPUTS {
+			for j in 0..NN_INPUTS {
+				weights[i][j] = f(128 + (i * NN_INPUTS + j) * 4);
+			}
+		}
+
+		Self {
+			position: [f(0), f(4)],
+			velocity: [f(8), f(12)],
+			energy: f(16),
+			age: f(20),
+			handling: f(24),
+			cooldown: f(28),
+			gestation: f(44),
+			id: l(48),
+			parents: [l(56), l(64)],
+			generation: u(80),
+			birth_step: l(72),
+			offspring: u(84),
+			gathered: f(88),
+			signature: [f(32), f(36), f(40)],
+			weights,
+			biases: [f(96), f(100), f(104), f(108), f(112)]
+		}
+	}
+
+	/** Whether this individual has been born, rather than still gestating.
+	 * Individuals that have not been born yet are left out of everything but
+	 * the room they take up. */
+	pub fn born(&self) -> bool {
+		self.gestation <= 0.0
+	}
+}
+
+/** Read a population back from its `std430` representation, as written by
+ * the [`populations_bytes()`] function, taking only the individuals in the
+ * given range. */
+pub fn population_from_bytes<A: AsRef<[u8]>>(bytes: A, range: Range<u32>) -> Vec<Individual> {
+	let data = bytes.as_ref();
+	range
+		.map(|i| i as usize * Individual::BYTE_SIZE)
+		.map(|offset| Individual::from_bytes(&data[offset..offset + Individual::BYTE_SIZE]))
+		.collect()
 }
 
 /** Writes the given number of zero bytes into the buffer. */
@@ -519,6 +1115,21 @@ fn write_u32(buf: &mut Vec<u8>, val: u32) -> usize {
 	data.len()
 }
 
+/** Writes the given number into the buffer, as its lower half followed by its
+ * upper half, the way a `uvec2` holds it in the shaders. */
+fn write_u64(buf: &mut Vec<u8>, val: u64) -> usize {
+	write_u32(buf, val as u32) + write_u32(buf, (val >> 32) as u32)
+}
+
+/** Reads a number written by the [`write_u64()`] function from the start of
+ * the given bytes. */
+fn read_u64(data: &[u8]) -> u64 {
+	let u = |offset: usize| u32::from_ne_bytes(
+		(&data[offset..offset + 4]).try_into().unwrap());
+
+	u64::from(u(0)) | u64::from(u(4)) << 32
+}
+
 /** Writes the given float vector into the buffer. */
 fn write_vec<A>(buf: &mut Vec<u8>, dat: A) -> usize
 	where A: AsRef<[f32]> {
@@ -533,3 +1144,63 @@ fn write_vec<A>(buf: &mut Vec<u8>, dat: A) -> usize
 		})
 		.sum()
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/** Individual with a different value in every one of its fields, such
+	 * that any two fields mixed up in the layout tell apart. */
+	fn individual() -> Individual {
+		let mut weights = [[0.0; NN_INPUTS]; NN_OUTPUTS];
+		for (i, output) in weights.iter_mut().enumerate() {
+			for (j, weight) in output.iter_mut().enumerate() {
+				*weight = 1000.0 + (i * NN_INPUTS + j) as f32;
+			}
+		}
+
+		Individual {
+			position: [1.0, 2.0],
+			velocity: [3.0, 4.0],
+			energy: 0.5,
+			age: 6.0,
+			handling: 7.0,
+			cooldown: 8.0,
+			gestation: 9.0,
+			id: 0x0123_4567_89ab_cdef,
+			parents: [0x1111_2222_3333_4444, 0x5555_6666_7777_8888],
+			generation: 10,
+			birth_step: 0x0000_0001_0000_0002,
+			offspring: 11,
+			gathered: 12.0,
+			signature: [0.1, 0.2, 0.3],
+			weights,
+			biases: [13.0, 14.0, 15.0, 16.0, 17.0]
+		}
+	}
+
+	#[test]
+	fn individual_round_trip() {
+		let individual = individual();
+		let mut buf = Vec::new();
+		individual.bytes(&mut buf);
+
+		assert_eq!(Individual::from_bytes(&buf), individual);
+	}
+
+	#[test]
+	fn individual_byte_size() {
+		let mut buf = Vec::new();
+		individual().bytes(&mut buf);
+
+		assert_eq!(buf.len(), Individual::BYTE_SIZE);
+		assert_eq!(Individual::BYTE_SIZE % 16, 0);
+	}
+
+	#[test]
+	fn network_inputs() {
+		assert_eq!(NN_INPUT_RAYS, NN_INPUT_TEMPERATURE + 1);
+		assert_eq!(NN_INPUTS, NN_INPUT_RAYS + MAX_RAYS * NN_RAY_INPUTS);
+		assert_eq!(NN_RAY_INPUTS, NN_RAY_FIELD + 4);
+	}
+}
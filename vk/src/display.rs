@@ -453,8 +453,14 @@ pub struct Display<A> {
 	square: Model,
 
 	geometry: Geometry,
-	herbivores_geometry: GeometryPipeline,
-	predators_geometry: GeometryPipeline,
+	species_geometry: GeometryPipeline,
+	plane_geometry: GeometryPipeline,
+
+	/** Number of cells on the simulation plane. */
+	plane_cells: u32,
+	/** Whether the temperature of the plane is shown, which it only is when
+	 * it changes over the plane. */
+	show_temperature: bool,
 
 	lighting: LightingPipeline
 }
@@ -495,30 +501,44 @@ impl<A> Display<A>
 			device,
 			crate::dataset::RenderParameters {
 				world_transformation,
-				projection
+				projection,
+				plane: [
+					prefs.simulation.plane_width,
+					prefs.simulation.plane_height
+				],
+				temperature_range: [
+					prefs.simulation.temperature.low,
+					prefs.simulation.temperature.high
+				]
 			});
 
-		/* Create the population pipelines. */
-		let herbivores_geometry = {
-			use crate::shaders::graphics::herbivore_geometry_pass as shaders;
+		/* Create the population pipeline, shared by all species. */
+		let species_geometry = {
+			use crate::shaders::graphics::species_geometry_pass as shaders;
 			GeometryPipeline::new(
 				device,
 				&flipbook,
-				Some("Display/HerbivoresGeometry/Pipeline"),
+				Some("Display/SpeciesGeometry/Pipeline"),
 				&params,
 				shaders::vertex_shader(),
 				shaders::fragment_shader())
 		};
-		let predators_geometry = {
-			use crate::shaders::graphics::predator_geometry_pass as shaders;
+
+		/* Create the plane pipeline. */
+		let plane_geometry = {
+			use crate::shaders::graphics::plane_geometry_pass as shaders;
 			GeometryPipeline::new(
 				device,
 				&flipbook,
-				Some("Display/PredatorsGeometry/Pipeline"),
+				Some("Display/PlaneGeometry/Pipeline"),
 				&params,
 				shaders::vertex_shader(),
 				shaders::fragment_shader())
 		};
+		let plane_cells = prefs.simulation.horizontal_granularity
+			* prefs.simulation.vertical_granularity;
+		let show_temperature = prefs.simulation.temperature.low
+			!= prefs.simulation.temperature.high;
 
 		/* Create the geometry buffers. */
 		let geometry = Geometry::new(
@@ -560,8 +580,10 @@ impl<A> Display<A>
 			tile,
 			square,
 			geometry,
-			herbivores_geometry,
-			predators_geometry,
+			species_geometry,
+			plane_geometry,
+			plane_cells,
+			show_temperature,
 			lighting
 		}
 	}
@@ -635,27 +657,32 @@ impl<A> Display<A>
 				depth_stencil_attachment: None
 			});
 
-		/* Draw the herbivores. */
-		pass.set_pipeline(&self.herbivores_geometry.pipeline);
-		pass.set_index_buffer(self.cube.indices());
-		pass.set_vertex_buffer(0, self.cube.vertices());
-		pass.set_bind_group(0, snapshot.bind_group(), &[]);
-		pass.set_bind_group(1, &self.params.bind, &[]);
-		pass.draw_indexed(
-			0..self.cube.index_count(),
-			0,
-			snapshot.herbivores().await);
-
-		/* Draw the predators. */
-		pass.set_pipeline(&self.predators_geometry.pipeline);
-		pass.set_index_buffer(self.cube.indices());
-		pass.set_vertex_buffer(0, self.cube.vertices());
-		pass.set_bind_group(0, snapshot.bind_group(), &[]);
-		pass.set_bind_group(1, &self.params.bind, &[]);
-		pass.draw_indexed(
-			0..self.cube.index_count(),
-			0,
-			snapshot.predators().await);
+		/* Draw the temperature of every cell of the plane, underneath the
+		 * individuals. */
+		if self.show_temperature {
+			pass.set_pipeline(&self.plane_geometry.pipeline);
+			pass.set_index_buffer(self.square.indices());
+			pass.set_vertex_buffer(0, self.square.vertices());
+			pass.set_bind_group(0, snapshot.bind_group(), &[]);
+			pass.set_bind_group(1, &self.params.bind, &[]);
+			pass.draw_indexed(
+				0..self.square.index_count(),
+				0,
+				0..self.plane_cells);
+		}
+
+		/* Draw the individuals of every species. */
+		for range in snapshot.ranges().await {
+			pass.set_pipeline(&self.species_geometry.pipeline);
+			pass.set_index_buffer(self.cube.indices());
+			pass.set_vertex_buffer(0, self.cube.vertices());
+			pass.set_bind_group(0, snapshot.bind_group(), &[]);
+			pass.set_bind_group(1, &self.params.bind, &[]);
+			pass.draw_indexed(
+				0..self.cube.index_count(),
+				0,
+				range);
+		}
 
 		/* Draw the  */
 	}
@@ -0,0 +1,390 @@
+use std::error::Error;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+use rand::Rng;
+use rand::seq::index;
+use crate::dataset::Individual;
+use crate::genome::{Genome, GenomeFile};
+use crate::settings::{Simulation, Event, EventKind, Parameter, Ranking};
+
+/** An action taken on the simulation, as resolved from a scripted event. */
+#[derive(Debug, Clone)]
+pub enum Action {
+	/** Kill the given fraction of the individuals of a species, optionally
+	 * only out of those within a region. */
+	Cull {
+		species: usize,
+		fraction: f32,
+		region: Option<[f32; 4]>
+	},
+	/** Add individuals to a species, with genomes drawn at random, at random
+	 * positions within a region, starting off with the given energy. */
+	Inject {
+		species: usize,
+		genomes: Arc<Vec<Genome>>,
+		count: u32,
+		region: [f32; 4],
+		energy: f32
+	},
+	/** Remove all of the grass within a region. */
+	WipeGrass {
+		region: [f32; 4]
+	},
+	/** Change the base value of an environment parameter. */
+	Set {
+		parameter: Parameter,
+		value: f32
+	},
+	/** Write the genomes of the individuals of a species out to a genome
+	 * file, optionally only out of those within a region, keeping only the
+	 * given number of the best of them by the given ranking, unless it is
+	 * zero. */
+	Export {
+		species: usize,
+		path: String,
+		count: u32,
+		region: Option<[f32; 4]>,
+		rank_by: Ranking
+	},
+}
+impl Action {
+	/** Culls or injects into the given population of the species this action
+	 * targets, as long as it is a cull or an injection. The population must
+	 * only hold individuals that are alive and may grow up to the given
+	 * budget, past which no more individuals are injected. */
+	pub fn populate(&self, population: &mut Vec<Individual>, budget: usize) {
+		match self {
+			Self::Cull { fraction, region, .. } => {
+				let targets = population.iter()
+					.enumerate()
+					.filter(|(_, i)| region.map_or(true, |region| contains(region, i.position)))
+					.map(|(index, _)| index)
+					.collect::<Vec<_>>();
+				let count = (targets.len() as f32 * fraction.clamp(0.0, 1.0)).round() as usize;
+
+				let mut culled = vec![false; population.len()];
+				let sample = crate::random::with(|rng| index::sample(rng, targets.len(), count));
+				for target in sample {
+					culled[targets[target]] = true;
+				}
+
+				let mut culled = culled.into_iter();
+				population.retain(|_| !culled.next().unwrap());
+			},
+			Self::Inject { genomes, count, region, energy, .. } => {
+				let room = budget.saturating_sub(population.len());
+
+				crate::random::with(|rng| {
+					for _ in 0..(*count as usize).min(room) {
+						let genome = &genomes[rng.gen_range(0..genomes.len())];
+						let [l, r, t, b] = bounds(*region);
+						let position = [
+							l + (r - l) * rng.gen::<f32>(),
+							t + (b - t) * rng.gen::<f32>()
+						];
+
+						population.push(genome.individual(position, *energy));
+					}
+				});
+			},
+			_ => {}
+		}
+	}
+
+	/** Writes the genomes of the given population of the species this action
+	 * targets out to a genome file, as long as it is an export, returning the
+	 * number of genomes written. The population must only hold individuals
+	 * that are alive. Nothing gets written if no individual is selected, as
+	 * genome files may not be empty, leaving whatever was there before. */
+	pub fn export(&self, population: &[Individual]) -> Result<usize, Box<dyn Error>> {
+		let (path, count, region, rank_by) = match self {
+			Self::Export { path, count, region, rank_by, .. } => (path, *count, *region, *rank_by),
+			_ => return Ok(0)
+		};
+
+		let mut selected = population.iter()
+			.filter(|i| i.born())
+			.filter(|i| region.map_or(true, |region| contains(region, i.position)))
+			.collect::<Vec<_>>();
+		match rank_by {
+			Ranking::Energy => selected.sort_by(|a, b| b.energy.total_cmp(&a.energy)),
+			Ranking::Age => selected.sort_by(|a, b| b.age.total_cmp(&a.age)),
+			Ranking::Generation => selected.sort_by(|a, b| b.generation.cmp(&a.generation))
+		}
+		if count > 0 {
+			selected.truncate(count as usize);
+		}
+
+		let genomes = selected.into_iter()
+			.map(Genome::from)
+			.collect::<Vec<_>>();
+		if genomes.is_empty() {
+			return Err(format!("no individuals to export to {}", path).into())
+		}
+		let written = genomes.len();
+		GenomeFile::new(genomes).save(path)?;
+
+		Ok(written)
+	}
+
+	/** The species this action targets, if any. */
+	pub fn species(&self) -> Option<usize> {
+		match self {
+			Self::Cull { species, .. }
+				| Self::Inject { species, .. }
+				| Self::Export { species, .. } => Some(*species),
+			_ => None
+		}
+	}
+}
+impl fmt::Display for Action {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Cull { species, fraction, region } =>
+				write!(f, "cull {:.0}% of species {} within {:?}",
+					fraction * 100.0, species, region),
+			Self::Inject { species, count, region, .. } =>
+				write!(f, "inject {} individuals into species {} within {:?}",
+					count, species, region),
+			Self::WipeGrass { region } =>
+				write!(f, "wipe the grass within {:?}", region),
+			Self::Set { parameter, value } =>
+				write!(f, "set {:?} to {}", parameter, value),
+			Self::Export { species, path, count: 0, .. } =>
+				write!(f, "export all individuals of species {} to {}",
+					species, path),
+			Self::Export { species, path, count, rank_by, .. } =>
+				write!(f, "export the {} best individuals by {:?} of species {} to {}",
+					count, rank_by, species, path)
+		}
+	}
+}
+
+/** Whether the given position lies within the given region, given in the
+ * same format as the spawn range of a species. */
+pub fn contains(region: [f32; 4], position: [f32; 2]) -> bool {
+	let [l, r, t, b] = bounds(region);
+	position[0] >= l && position[0] <= r && position[1] >= t && position[1] <= b
+}
+
+/** Left, right, top and bottom bounds of the given region, in order. */
+fn bounds(region: [f32; 4]) -> [f32; 4] {
+	[
+		f32::min(region[0], region[1]),
+		f32::max(region[0], region[1]),
+		f32::min(region[2], region[3]),
+		f32::max(region[2], region[3])
+	]
+}
+
+/** When a scripted event happens. */
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Trigger {
+	/** At the given time, in seconds of simulated time. */
+	Time(f32),
+	/** At the given step of the simulation. */
+	Step(u64),
+}
+
+/** Timeline of the events scripted to happen during the simulation. Both
+ * backends run the actions that are due before every step and advance the
+ * timeline after it. */
+#[derive(Debug, Clone, Default)]
+pub struct Timeline {
+	/** Actions that have yet to be taken, along with when. */
+	pending: Vec<(Trigger, Action)>,
+	/** Simulated time, in seconds, since the start of the simulation. */
+	time: f32,
+	/** Number of steps taken since the start of the simulation. */
+	steps: u64,
+}
+impl Timeline {
+	/** Creates the timeline of the events in the given simulation settings,
+	 * loading any genome files they need. */
+	pub fn new(simulation: &Simulation) -> Result<Self, Box<dyn Error>> {
+		let mut pending = Vec::with_capacity(simulation.events.len());
+		for event in &simulation.events {
+			let trigger = match event.at_step {
+				Some(step) => Trigger::Step(step),
+				None => Trigger::Time(event.at)
+			};
+			pending.push((trigger, Self::resolve(simulation, event)?));
+		}
+
+		Ok(Self {
+			pending,
+			time: 0.0,
+			steps: 0
+		})
+	}
+
+	/** Resolves the action the given event takes. */
+	fn resolve(simulation: &Simulation, event: &Event) -> Result<Action, Box<dyn Error>> {
+		let species = || simulation.species.iter()
+			.position(|group| group.name == event.species)
+			.ok_or_else(|| format!("event refers to species {}, which does \
+				not exist", event.species));
+
+		Ok(match event.kind {
+			EventKind::Cull => Action::Cull {
+				species: species()?,
+				fraction: event.fraction,
+				region: event.region
+			},
+			EventKind::Inject => {
+				let species = species()?;
+				let group = &simulation.species[species];
+
+				let [l, r, t, b] = bounds(event.region.unwrap_or(group.spawn_range));
+				let max_x = simulation.plane_width - 0.01;
+				let max_y = simulation.plane_height - 0.01;
+
+				Action::Inject {
+					species,
+					genomes: Arc::new(GenomeFile::load(&event.genomes)?.genomes),
+					count: event.count,
+					region: [
+						l.clamp(0.0, max_x),
+						r.clamp(0.0, max_x),
+						t.clamp(0.0, max_y),
+						b.clamp(0.0, max_y)
+					],
+					energy: group.offspring_energy
+				}
+			},
+			EventKind::WipeGrass => Action::WipeGrass {
+				region: bounds(event.region
+					.ok_or("grass wiping events need a region")?)
+			},
+			EventKind::Set => Action::Set {
+				parameter: event.parameter
+					.ok_or("parameter setting events need a parameter")?,
+				value: event.value
+			},
+			EventKind::Export => {
+				if event.genomes.is_empty() {
+					return Err("export events need a genome file".into())
+				}
+
+				Action::Export {
+					species: species()?,
+					path: event.genomes.clone(),
+					count: event.count,
+					region: event.region,
+					rank_by: event.rank_by
+				}
+			}
+		})
+	}
+
+	/** Takes out the actions that are due before the next step, in the order
+	 * their events were declared in. */
+	pub fn due(&mut self) -> Vec<Action> {
+		let (time, steps) = (self.time, self.steps);
+		let is_due = |trigger: &Trigger| match *trigger {
+			Trigger::Time(at) => at <= time,
+			Trigger::Step(at) => at <= steps
+		};
+
+		let mut due = Vec::new();
+		let mut i = 0;
+		while i < self.pending.len() {
+			if is_due(&self.pending[i].0) {
+				due.push(self.pending.remove(i).1);
+			} else {
+				i += 1;
+			}
+		}
+
+		due
+	}
+
+	/** Advances the timeline past a step covering the given time. */
+	pub fn advance(&mut self, delta: Duration) {
+		self.time += delta.as_secs_f32();
+		self.steps += 1;
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::settings::Preferences;
+
+	/** Event setting the growth rate to the given value, which tells it
+	 * apart from the others, at the given time or step. */
+	fn event(at: f32, at_step: Option<u64>, value: f32) -> Event {
+		Event {
+			at,
+			at_step,
+			kind: EventKind::Set,
+			parameter: Some(Parameter::GrowthRate),
+			value,
+			..Default::default()
+		}
+	}
+
+	/** Timeline of the given events. */
+	fn timeline(events: Vec<Event>) -> Timeline {
+		let mut simulation = Preferences::default().simulation;
+		simulation.events = events;
+
+		Timeline::new(&simulation).unwrap()
+	}
+
+	/** Values of the given actions setting a parameter, in order. */
+	fn values(actions: Vec<Action>) -> Vec<f32> {
+		actions.into_iter()
+			.map(|action| match action {
+				Action::Set { value, .. } => value,
+				action => panic!("unexpected action: {}", action)
+			})
+			.collect()
+	}
+
+	#[test]
+	fn events_come_due_in_order() {
+		let mut timeline = timeline(vec![
+			event(1.0, None, 1.0),
+			event(0.0, Some(2), 2.0),
+			event(0.0, None, 3.0),
+			event(0.5, None, 4.0),
+		]);
+
+		assert_eq!(values(timeline.due()), vec![3.0]);
+		timeline.advance(Duration::from_millis(500));
+		assert_eq!(values(timeline.due()), vec![4.0]);
+		timeline.advance(Duration::from_millis(500));
+		assert_eq!(values(timeline.due()), vec![1.0, 2.0]);
+	}
+
+	#[test]
+	fn events_come_due_once() {
+		let mut timeline = timeline(vec![event(0.0, None, 1.0), event(0.0, Some(0), 2.0)]);
+
+		assert_eq!(values(timeline.due()), vec![1.0, 2.0]);
+		assert!(timeline.due().is_empty());
+		timeline.advance(Duration::from_secs(1));
+		assert!(timeline.due().is_empty());
+	}
+
+	#[test]
+	fn steps_take_over_from_time() {
+		let mut timeline = timeline(vec![event(0.0, Some(3), 1.0)]);
+
+		for _ in 0..3 {
+			assert!(timeline.due().is_empty());
+			timeline.advance(Duration::from_secs(10));
+		}
+		assert_eq!(values(timeline.due()), vec![1.0]);
+	}
+
+	#[test]
+	fn missed_events_come_due_late() {
+		let mut timeline = timeline(vec![event(0.25, None, 1.0), event(0.75, None, 2.0)]);
+
+		timeline.advance(Duration::from_secs(1));
+		assert_eq!(values(timeline.due()), vec![1.0, 2.0]);
+	}
+}
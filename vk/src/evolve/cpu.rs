@@ -1,7 +1,16 @@
+use std::sync::Arc;
 use std::time::Duration;
 
-use crate::dataset::Individual;
-use crate::settings::{Simulation, Group};
+use rand::seq::SliceRandom;
+
+use crate::dataset::{self, Individual, NN_INPUTS, NN_OUTPUTS};
+use crate::settings::{Simulation, Group, Boundary, MateSelection, BudgetPolicy, OffspringPlacement, DecayMode, Regrowth, Season, Vision, GRASS, MAX_RAYS};
+use crate::statistics::{Statistics, EnergyLedger, Turnover};
+use crate::terrain::Terrain;
+use crate::events::{self, Action};
+use crate::immigration::Immigration;
+use crate::grid::Grid;
+use crate::lineage::Lineage;
 
 #[derive(Copy, Clone, Debug)]
 pub struct Cell {
@@ -9,45 +18,138 @@ pub struct Cell {
     pub green: f32,
     pub blue: f32,
     pub grass: f32,
+    /// Time left before the grass may grow back under the delayed regrowth model.
+    pub regrowth: f32,
 }
 
 #[derive(Clone, Debug)]
 pub struct Map {
     cells: Vec<Cell>,
+    terrain: Arc<Terrain>,
+    /// Size of every cell, in world units.
+    cell_size: [f32; 2],
     width: u32,
     #[cfg(debug_assertions)]
     height: u32,
+    /// What happens at the edges of the map, which wraps around under the torus boundary.
+    boundary: Boundary,
 }
 
 impl Map {
-    fn new(params: &Simulation) -> Self {
+    fn new(params: &Simulation, terrain: Arc<Terrain>) -> Self {
         let mut cells = Vec::with_capacity(
             (params.horizontal_granularity * params.vertical_granularity) as usize
         );
 
-        for _ in 0..cells.capacity() {
+        for grass in terrain.seed_grass(&params.regrowth) {
             cells.push(Cell {
                 red: 0.0,
                 green: 0.0,
                 blue: 0.0,
-                grass: 0.0,
+                grass,
+                regrowth: 0.0,
             })
         }
 
         Self {
             cells,
+            terrain,
+            cell_size: [
+                params.plane_width / params.horizontal_granularity as f32,
+                params.plane_height / params.vertical_granularity as f32,
+            ],
             width: params.horizontal_granularity,
             #[cfg(debug_assertions)]
             height: params.vertical_granularity,
+            boundary: params.boundary,
         }
     }
 
-    pub fn decay(&mut self, decay_red: f32, decay_green: f32, decay_blue: f32, grass_growth: f32) {
-        for mut c in self.cells.iter_mut() {
-            c.red -= decay_red;
-            c.green -= decay_green;
-            c.blue -= decay_blue;
-            c.grass += grass_growth;
+    /// Decays the chemicals on every cell at the given rates, following the given modes, over the
+    /// given amount of time, and grows the grass on every cell back at the given rate, following
+    /// the given regrowth model, returning the total amount of grass that was grown. Grass growth
+    /// is scaled by the fertility of the terrain under every cell.
+    pub fn decay(&mut self, rates: [f32; 3], modes: [DecayMode; 3], regrowth: &Regrowth, growth_rate: f32,
+                 delta: f32) -> f32 {
+        // grass spreads from the density its neighbours had before any of them grew
+        let source = self.cells.iter().map(|c| c.grass).collect::<Vec<_>>();
+        let (width, height) = (self.width as i64, (self.cells.len() / self.width as usize) as i64);
+        let boundary = self.boundary;
+        let neighbours = |x: i64, y: i64| {
+            let mut total = 0.0;
+            for (dx, dy) in [(-1, 0), (1, 0), (0, -1), (0, 1)].iter() {
+                let nx = boundary.cell(x + dx, width as u32);
+                let ny = boundary.cell(y + dy, height as u32);
+                if let (Some(nx), Some(ny)) = (nx, ny) {
+                    total += source[(ny as i64 * width + nx as i64) as usize];
+                }
+            }
+            total / 4.0
+        };
+
+        let mut grown = 0.0;
+        for (pos, mut c) in self.cells.iter_mut().enumerate() {
+            c.red = modes[0].decay(c.red, rates[0], delta);
+            c.green = modes[1].decay(c.green, rates[1], delta);
+            c.blue = modes[2].decay(c.blue, rates[2], delta);
+
+            let (x, y) = (pos as i64 % width, pos as i64 / width);
+            let fertility = self.terrain.tile(x as u32, y as u32).fertility;
+            let grass = regrowth.regrow(c.grass, neighbours(x, y), &mut c.regrowth, growth_rate * fertility,
+                                        delta);
+            grown += (grass - c.grass).max(0.0);
+            c.grass = grass;
+        }
+        grown
+    }
+
+    /// Spreads the chemicals on every cell out to its four neighbours, following the given
+    /// diffusion coefficients, in world units squared per second, over the given amount of time,
+    /// split into the given number of steps. Walls and the edges of the map let no chemicals
+    /// through.
+    pub fn diffuse(&mut self, rates: [f32; 3], delta: f32, steps: u32) {
+        let delta = delta / steps as f32;
+        let [cell_x, cell_y] = self.cell_size;
+        let mut fraction_x = [0.0; 3];
+        let mut fraction_y = [0.0; 3];
+        for c in 0..3 {
+            fraction_x[c] = rates[c] * delta / (cell_x * cell_x);
+            fraction_y[c] = rates[c] * delta / (cell_y * cell_y);
+        }
+
+        let (width, height) = (self.width as i64, (self.cells.len() / self.width as usize) as i64);
+        let chemicals = |c: &Cell| [c.red, c.green, c.blue];
+        let terrain = &self.terrain;
+        let boundary = self.boundary;
+        for _ in 0..steps {
+            let source = self.cells.clone();
+            for (pos, cell) in self.cells.iter_mut().enumerate() {
+                let (x, y) = (pos as i64 % width, pos as i64 / width);
+                if terrain.tile(x as u32, y as u32).wall {
+                    continue;
+                }
+
+                let center = chemicals(&source[pos]);
+                let neighbour = |dx: i64, dy: i64| {
+                    match (boundary.cell(x + dx, width as u32), boundary.cell(y + dy, height as u32)) {
+                        (Some(nx), Some(ny)) if !terrain.tile(nx, ny).wall =>
+                            chemicals(&source[(ny as i64 * width + nx as i64) as usize]),
+                        _ => center
+                    }
+                };
+                let (left, right) = (neighbour(-1, 0), neighbour(1, 0));
+                let (up, down) = (neighbour(0, -1), neighbour(0, 1));
+
+                let mut result = [0.0; 3];
+                for c in 0..3 {
+                    result[c] = (center[c]
+                        + fraction_x[c] * (left[c] + right[c] - 2.0 * center[c])
+                        + fraction_y[c] * (up[c] + down[c] - 2.0 * center[c])).clamp(0.0, 1.0);
+                }
+                cell.red = result[0];
+                cell.green = result[1];
+                cell.blue = result[2];
+            }
         }
     }
 
@@ -61,6 +163,22 @@ impl Map {
         }).map(|(_, cell)| cell)
     }
 
+    pub fn terrain(&self) -> &Terrain {
+        &self.terrain
+    }
+
+    /// Removes all of the grass from the cells whose centers lie within the given region.
+    pub fn wipe_grass(&mut self, region: [f32; 4]) {
+        let [cell_x, cell_y] = self.cell_size;
+        let width = self.width;
+        for (pos, c) in self.cells.iter_mut().enumerate() {
+            let (x, y) = (pos as u32 % width, pos as u32 / width);
+            if events::contains(region, [(x as f32 + 0.5) * cell_x, (y as f32 + 0.5) * cell_y]) {
+                c.grass = 0.0;
+            }
+        }
+    }
+
     #[inline(always)]
     pub fn cell_at(&self, x: u32, y: u32) -> &Cell {
         &self.cells[self.cell_index(x, y)]
@@ -91,60 +209,71 @@ impl Map {
 
 #[derive(Clone, Debug)]
 pub struct State {
-    pub herbivores: Vec<Individual>,
-    pub carnivores: Vec<Individual>,
+    /// Population of every species, in the order the species were declared in.
+    pub populations: Vec<Vec<Individual>>,
     pub map: Map,
+    pub ledger: EnergyLedger,
+    /// Turnover of every species since the start of the simulation.
+    pub turnover: Vec<Turnover>,
+    /// Individuals alive at the start of the step, sorted by where they are.
+    grid: Grid,
     params: Simulation,
+    diffusion_steps: u32,
 }
 
 impl State {
-    fn new(params: &Simulation) -> Self {
-        let population = {
-            let max_x = params.plane_width - 0.01;
-            let max_y = params.plane_height - 0.01;
-            move |params: &Group| {
-                let mut p = crate::dataset::population(params);
-                for mut i in p.iter_mut() {
-                    i.position = [
-                        i.position[0].clamp(0.0, max_x),
-                        i.position[1].clamp(0.0, max_y)
-                    ];
-                }
-                p
-            }
-        };
+    fn new(params: &Simulation, terrain: &Terrain) -> Self {
+        // species start off with as many individuals as they are set to, like on the GPU, where
+        // the rest of their budget is left for newborns
+        let mut populations = crate::dataset::populations(params);
+        for (population, group) in populations.iter_mut().zip(&params.species) {
+            population.truncate(group.individuals as usize);
+        }
+        let max_x = params.plane_width - 0.01;
+        let max_y = params.plane_height - 0.01;
+        for mut i in populations.iter_mut().flatten() {
+            i.position = [
+                i.position[0].clamp(0.0, max_x),
+                i.position[1].clamp(0.0, max_y)
+            ];
+        }
         Self {
-            herbivores: population(&params.herbivores),
-            carnivores: population(&params.predators),
-            map: Map::new(params),
+            populations,
+            map: Map::new(params, Arc::new(terrain.clone())),
+            ledger: Default::default(),
+            turnover: vec![Default::default(); params.species.len()],
+            grid: Grid::new(params),
             params: params.clone(),
+            diffusion_steps: params.diffusion_steps(),
         }
     }
 
     fn gradient<F: Fn(&Cell) -> f32>(&self, group: &Group, individual: &Individual, selector: F) -> (f32, f32, f32) {
+        // everything in here is measured in cells, rather than in world units
         let radius = f32::min(
-            group.view_radius / self.params.plane_width,
-            group.view_radius / self.params.plane_height,
+            group.view_radius / self.params.plane_width * self.params.horizontal_granularity as f32,
+            group.view_radius / self.params.plane_height * self.params.vertical_granularity as f32,
         );
-        let (top, bottom, left, right) = {
-            let [x, y] = individual.position;
+        let center = self.individual_pos(individual);
+        let boundary = self.params.boundary;
+        let (rows, columns) = {
+            let (x, y) = (center.0 as f32, center.1 as f32);
             (
-                (y - radius).clamp(0.0, self.params.plane_height).round() as u32,
-                (y + radius).clamp(0.0, self.params.plane_height).round() as u32,
-                (x - radius).clamp(0.0, self.params.plane_width).round() as u32,
-                (x + radius).clamp(0.0, self.params.plane_width).round() as u32,
+                boundary.span((y - radius).round() as i64, (y + radius).round() as i64,
+                              self.params.vertical_granularity),
+                boundary.span((x - radius).round() as i64, (x + radius).round() as i64,
+                              self.params.horizontal_granularity),
             )
         };
-        let center = self.individual_pos(individual);
         let center_val = selector(self.map.cell_at(center.0, center.1));
         let center = (center.0 as f32, center.1 as f32);
 
         let mut gradient = (f32::MIN_POSITIVE, f32::MIN_POSITIVE);
 
-        for i in top..=bottom {
-            for j in left..=right {
+        for i in rows {
+            for j in columns.clone() {
                 let (direction, dist) = {
-                    let (x, y) = (i as f32 - center.0, j as f32 - center.1);
+                    let (x, y) = (j as f32 - center.0, i as f32 - center.1);
                     let mag = (x.powf(2.0) + y.powf(2.0)).sqrt().max(f32::MIN_POSITIVE);
                     ((x / mag, y / mag), mag)
                 };
@@ -153,6 +282,12 @@ impl State {
                     continue;
                 }
 
+                // cells past the edges of the plane wrap around under the torus boundary
+                let (j, i) = match (boundary.cell(j, self.params.horizontal_granularity),
+                                    boundary.cell(i, self.params.vertical_granularity)) {
+                    (Some(j), Some(i)) => (j, i),
+                    _ => continue,
+                };
                 let val = selector(self.map.cell_at(j, i));
                 gradient = (
                     gradient.0 + direction.0 * (val - center_val),
@@ -177,60 +312,259 @@ impl State {
         ]
     }
 
-    fn step(&self, output: &mut State, delta: Duration) {
+    /// Senses the individuals alive within the radius of vision of the given individual of the
+    /// given species, at the given index into its population, as they were at the start of the
+    /// step, writing the neighbour inputs to its network, as described in Dataset.glslh. This
+    /// must be kept in line with `SenseNeighbours()` in Simulate.glsl.
+    fn sense_neighbours(&self, species: usize, index: usize, individual: &Individual,
+                        inputs: &mut [f32; NN_INPUTS]) {
+        let group = &self.params.species[species];
+        let (boundary, size) = (self.params.boundary, self.params.plane_size());
+        let is_predator = |s: usize| self.params.species[s].eats(&group.name);
+
+        // nearest predator, prey and kin, as their offset into the inputs, direction and distance
+        let mut nearest = [
+            (dataset::NN_INPUT_PREDATOR, [0.0; 2], f32::INFINITY),
+            (dataset::NN_INPUT_PREY, [0.0; 2], f32::INFINITY),
+            (dataset::NN_INPUT_KIN, [0.0; 2], f32::INFINITY),
+        ];
+        let mut others = 0;
+        let [x, y] = individual.position;
+        let radius = group.view_radius;
+        for (s, other) in self.grid.within([x - radius, y - radius], [x + radius, y + radius]) {
+            if (s, other) == (species, index) {
+                continue;
+            }
+            let other = &self.populations[s][other];
+            let offset = boundary.offset(individual.position, other.position, size);
+            let distance = (offset[0].powf(2.0) + offset[1].powf(2.0)).sqrt();
+            if distance > radius {
+                continue;
+            }
+            others += 1;
+
+            let kinds = [is_predator(s), group.eats(&self.params.species[s].name), s == species];
+            for (kind, nearest) in kinds.iter().zip(nearest.iter_mut()) {
+                if *kind && distance <= nearest.2 {
+                    let direction = if distance > 0.0 {
+                        [offset[0] / distance, offset[1] / distance]
+                    } else {
+                        [0.0, 0.0]
+                    };
+                    *nearest = (nearest.0, direction, distance);
+                }
+            }
+        }
+
+        for (index, direction, distance) in nearest.iter() {
+            inputs[*index..][..3].copy_from_slice(&[
+                direction[0],
+                direction[1],
+                (distance / group.view_radius).min(1.0),
+            ]);
+        }
+        inputs[dataset::NN_INPUT_DENSITY] = others as f32 / (others + 1) as f32;
+    }
+
+    /// Casts the vision rays of the given individual of the given species, as the individuals were
+    /// at the start of the step, writing their inputs to its network, as described in
+    /// Dataset.glslh. This must be kept in line with `CastRays()` in Simulate.glsl.
+    fn cast_rays(&self, species: usize, individual: &Individual, inputs: &mut [f32; NN_INPUTS]) {
+        let group = &self.params.species[species];
+        let terrain = self.map.terrain();
+        let (boundary, size) = (self.params.boundary, self.params.plane_size());
+        let [x, y] = individual.position;
+        let [vx, vy] = individual.velocity;
+        let heading = if vx != 0.0 || vy != 0.0 { vy.atan2(vx) } else { 0.0 };
+
+        // rays are walked one cell of the field at a time
+        let step = f32::min(
+            self.params.plane_width / self.params.horizontal_granularity as f32,
+            self.params.plane_height / self.params.vertical_granularity as f32,
+        );
+        let half_width = group.rays.width / 2.0;
+        let field_of_view = group.rays.field_of_view.to_radians();
+
+        let rays = (group.rays.count as usize).min(MAX_RAYS);
+        for ray in 0..rays {
+            let mut angle = heading;
+            if rays > 1 {
+                angle += field_of_view * (ray as f32 / (rays - 1) as f32 - 0.5);
+            }
+            let direction = [angle.cos(), angle.sin()];
+            let point = |t: f32| [x + direction[0] * t, y + direction[1] * t];
+
+            // find the first wall or edge of the plane along the ray, past which rays go on from the
+            // other side of the plane under the torus boundary
+            let mut hit = group.view_radius;
+            let mut kind = None;
+            let mut t = step;
+            while t <= group.view_radius {
+                let [px, py] = point(t);
+                let edge = boundary != Boundary::Torus
+                    && (px < 0.0 || py < 0.0 || px >= self.params.plane_width || py >= self.params.plane_height);
+                if edge || terrain.tile_at(boundary.bound([px, py], size)).wall {
+                    hit = t;
+                    kind = Some(3);
+                    break;
+                }
+                t += step;
+            }
+
+            // then for any individual closer than that, out of the cells of the grid around the
+            // ray. The individual itself is never in front of its own rays.
+            let end = point(hit);
+            let low = [x.min(end[0]) - half_width, y.min(end[1]) - half_width];
+            let high = [x.max(end[0]) + half_width, y.max(end[1]) + half_width];
+            for (s, index) in self.grid.within(low, high) {
+                let other = &self.populations[s][index];
+                let offset = boundary.offset(individual.position, other.position, size);
+                let along = offset[0] * direction[0] + offset[1] * direction[1];
+                let across = (offset[0] * direction[1] - offset[1] * direction[0]).abs();
+                if other.energy <= 0.0 || along <= 0.0 || along >= hit || across > half_width {
+                    continue;
+                }
+
+                hit = along;
+                kind = if self.params.species[s].eats(&group.name) {
+                    Some(0)
+                } else if group.eats(&self.params.species[s].name) {
+                    Some(1)
+                } else if s == species {
+                    Some(2)
+                } else {
+                    None
+                };
+            }
+
+            // average out the field over the part of the ray before the hit
+            let mut field = [0.0; 4];
+            let mut samples = 0;
+            let mut t = step / 2.0;
+            while t < hit {
+                let (cx, cy) = self.cell_of(boundary.bound(point(t), size));
+                let cell = self.map.cell_at(cx, cy);
+                for (sum, value) in field.iter_mut().zip(&[cell.red, cell.green, cell.blue, cell.grass]) {
+                    *sum += value.clamp(0.0, 1.0);
+                }
+                samples += 1;
+                t += step;
+            }
+            if samples > 0 {
+                field.iter_mut().for_each(|value| *value /= samples as f32);
+            }
+
+            let base = dataset::NN_INPUT_RAYS + ray * dataset::NN_RAY_INPUTS;
+            inputs[base + dataset::NN_RAY_DISTANCE] = hit / group.view_radius;
+            for k in 0..4 {
+                inputs[base + dataset::NN_RAY_KIND + k] = if kind == Some(k) { 1.0 } else { 0.0 };
+                inputs[base + dataset::NN_RAY_FIELD + k] = field[k];
+            }
+        }
+    }
+
+    /// Push to be given to an individual of the given species, as a velocity, away from the
+    /// individuals crowding around the given position, as they were at the start of the step,
+    /// other than the individual itself, at the given index into its population. This must be
+    /// kept in line with `Separation()` in Simulate.glsl.
+    fn separation(&self, species: usize, index: usize, position: [f32; 2]) -> [f32; 2] {
+        let radius = self.params.crowding.separation_radius;
+        let (boundary, size) = (self.params.boundary, self.params.plane_size());
+        let [x, y] = position;
+
+        let mut push = [0.0, 0.0];
+        for (s, other) in self.grid.within([x - radius, y - radius], [x + radius, y + radius]) {
+            if (s, other) == (species, index) {
+                continue;
+            }
+            let other = &self.populations[s][other];
+            if other.energy <= 0.0 {
+                continue;
+            }
+
+            let offset = boundary.offset(other.position, position, size);
+            let distance = (offset[0].powf(2.0) + offset[1].powf(2.0)).sqrt();
+            if distance <= 0.0 || distance >= radius {
+                continue;
+            }
+
+            let strength = 1.0 - distance / radius;
+            push[0] += offset[0] / distance * strength;
+            push[1] += offset[1] / distance * strength;
+        }
+
+        let force = self.params.crowding.separation_force;
+        [push[0] * force, push[1] * force]
+    }
+
+    fn step(&self, output: &mut State, delta: Duration, season: Season) {
         // This function *must* copy all (needed) state to output, which means all mutable fields,
         // otherwise state will get lost. The map is blindly copied at the beginning because it's
         // only read from the output (and updated there, obviously).
-        // `Individual`s use the group_step function to write to the output, which also handles removal
-        // of dead individuals (and avoids copying at all for those). Herbivores killed by predators
-        // must pay the price of a Vec remove call, but there's not much that can be done, since herbivores
-        // move first (so just setting a "dead" flag on them won't work)
+        // `Individual`s that are alive get copied over to the output by the group_step function
+        // before any of them moves, so that hunting individuals can always kill their prey in the
+        // output, regardless of whether the species of the prey moves before or after theirs.
+        // Killed prey are flagged by a negative energy and get removed by the shuffle.
 
         let delta = delta.as_secs_f32();
 
         (&mut output.map.cells[..]).copy_from_slice(&self.map.cells[..]);
+        output.ledger = Default::default();
+        output.turnover.clone_from(&self.turnover);
 
+        let terrain = self.map.terrain();
         let bounds_check = {
-            let max_x = self.params.plane_width;
-            let max_y = self.params.plane_height;
+            let (boundary, size) = (self.params.boundary, self.params.plane_size());
             move |pos: [f32; 2], vel: [f32; 2]| {
-                [
-                    (pos[0] + vel[0]).clamp(0.0, max_x),
-                    (pos[1] + vel[1]).clamp(0.0, max_y)
-                ]
+                terrain.slide(pos, boundary.bound([pos[0] + vel[0], pos[1] + vel[1]], size))
             }
         };
+        let wall_proximity = self.params.terrain.wall_proximity_input;
+        let crowding = self.params.crowding.enabled;
+
+        let conservation = self.params.energy_conservation;
+        let common_update = |species: usize, index: usize, map: &mut Map, ledger: &mut EnergyLedger,
+                             i: &mut Individual, (x, y)| {
+            let group = &self.params.species[species];
+            // temperature of the cell the individual starts the step in
+            let temperature = terrain.tile_at(i.position).temperature * season.temperature;
 
-        let common_update = |group: &Group, map: &mut Map, i: &mut Individual, (x, y)| {
             /* math go brrrr */
             let nn_result = {
-                let weights = ndarray::arr2(&i.weights);
-                let inputs = ndarray::arr1({
-                    let gradients = self.gradients(group, i);
-                    let [grad_r, grad_g, grad_b, grad_a] = gradients;
-                    &[
-                        i.velocity[0],
-                        i.velocity[1],
-                        grad_r.0, grad_r.1, grad_r.2,
-                        grad_g.0, grad_g.1, grad_g.2,
-                        grad_b.0, grad_b.1, grad_b.2,
-                        grad_a.0, grad_a.1, grad_a.2
-                    ]
-                }).into_shape((14, 1)).expect("Unable to reshape inputs to (14, 1)");
-                let biases = ndarray::arr1(&i.biases)
-                    .into_shape((5, 1)).expect("Unable to reshape biases to (5, 1)");
-                let mut result = weights.dot(&inputs) + biases;
-                debug_assert!(result.len() == 5, "Wrong result length");
+                // laid out as described in Dataset.glslh
+                let mut inputs = [0.0; NN_INPUTS];
+                inputs[dataset::NN_INPUT_VELOCITY..][..2].copy_from_slice(&i.velocity);
+                match group.vision {
+                    Vision::Gradients => for (n, gradient) in self.gradients(group, i).iter().enumerate() {
+                        inputs[dataset::NN_INPUT_GRADIENTS + n * 3..][..3]
+                            .copy_from_slice(&[gradient.0, gradient.1, gradient.2]);
+                    },
+                    Vision::Rays => self.cast_rays(species, i, &mut inputs),
+                }
+                if wall_proximity {
+                    inputs[dataset::NN_INPUT_WALL_PROXIMITY] =
+                        1.0 - (terrain.tile_at(i.position).clearance / group.view_radius).clamp(0.0, 1.0);
+                }
+                if group.neighbour_inputs {
+                    self.sense_neighbours(species, index, i, &mut inputs);
+                }
+                if self.params.temperature.input {
+                    inputs[dataset::NN_INPUT_TEMPERATURE] = self.params.temperature.level(temperature);
+                }
+
+                let weights = ndarray::Array2::from_shape_fn((NN_OUTPUTS, NN_INPUTS), |(o, j)| i.weights[o][j]);
+                let mut result = weights.dot(&ndarray::arr1(&inputs)) + ndarray::arr1(&i.biases);
+                debug_assert!(result.len() == NN_OUTPUTS, "Wrong result length");
                 result.map_inplace(|f| {
                     let exp = f.exp();
                     *f = exp / (exp + 1.0);
                 });
-                result.into_shape((5, )).expect("Unable to reshape result to (5,)")
+                result
             };
 
             #[cfg(debug_assertions)]
                 {
-                    for i in 0..5 {
+                    for i in 0..NN_OUTPUTS {
                         debug_assert!(!nn_result[i].is_nan(), "nn_result[{}] is NaN", i);
                     }
                 }
@@ -239,118 +573,242 @@ impl State {
             {
                 let theta = nn_result[0];
                 let magnitude = nn_result[1];
-                let mul = group.max_speed * delta;
+                let mul = group.max_speed * delta / terrain.tile_at(i.position).cost;
                 let movement = [
                     magnitude * f32::cos(theta * 2.0 * std::f32::consts::PI) * mul,
                     magnitude * f32::sin(theta * 2.0 * std::f32::consts::PI) * mul
                 ];
 
-                i.position = bounds_check(i.position, movement);
-                i.velocity = movement;
+                let position = bounds_check(i.position, movement);
+                i.velocity = [position[0] - i.position[0], position[1] - i.position[1]];
+                i.position = position;
+
+                // then get pushed apart from the individuals crowding around, if enabled. Pushes
+                // do not count towards the velocity of the individual.
+                if crowding {
+                    let push = self.separation(species, index, i.position);
+                    i.position = bounds_check(i.position, [push[0] * delta, push[1] * delta]);
+                }
 
                 let penalty = {
                     let v = delta * magnitude;
-                    group.metabolism_min * (1.0 - v) + group.metabolism_max * v
+                    let base = group.metabolism_min * (1.0 - v) + group.metabolism_max * v;
+                    base * (1.0 + group.senescence * i.age) * season.metabolism
+                        * group.thermal_stress(temperature)
                 };
 
                 debug_assert!(penalty > 0.0, "Invalid penalty ({:?}, delta = {:?}, magnitude = {:?})",
                               penalty, delta, magnitude);
 
                 i.energy -= penalty;
+                ledger.metabolised += penalty;
+            }
+
+            /* aging */
+            {
+                i.age += delta;
+                i.cooldown = (i.cooldown - delta).max(0.0);
+                if group.lifespan > 0.0 && i.age >= group.lifespan {
+                    ledger.expired += i.energy.max(0.0);
+                    i.energy = -1.0;
+                }
             }
 
             /* drop pheromones */
             {
+                let intensity = nn_result[2];
                 let mut cell = map.cell_at_mut(x, y);
-                cell.red = f32::clamp(nn_result[2], 0.0, 1.0);
-                cell.green = f32::clamp(nn_result[3], 0.0, 1.0);
-                cell.blue = f32::clamp(nn_result[4], 0.0, 1.0);
+                cell.red = f32::clamp(i.signature[0] * intensity, 0.0, 1.0);
+                cell.green = f32::clamp(i.signature[1] * intensity, 0.0, 1.0);
+                cell.blue = f32::clamp(i.signature[2] * intensity, 0.0, 1.0);
             }
         };
 
-        let herb_step = {
-            let map = &mut output.map;
-            move |i: &mut Individual| {
-                let (x, y) = self.individual_pos(i);
-                /* energy */
-                {
-                    let cell = map.cell_at_mut(x, y);
-                    let eat = cell.grass.min(1f32 - i.energy);
-                    i.energy += eat;
-                    cell.grass -= eat;
+        let species_step = |species: usize, index: usize, i: &mut Individual,
+                            populations: &mut Vec<Vec<Individual>>, map: &mut Map,
+                            ledger: &mut EnergyLedger| {
+            let (x, y) = self.individual_pos(i);
+            let settings = &self.params.species[species];
+            /* grazing */
+            // only as much grass as it takes to fill up, the same as in Simulate.glsl
+            if settings.eats(GRASS) {
+                let efficiency = if conservation {
+                    settings.feeding_efficiency
+                } else {
+                    1.0
+                };
+                let cell = map.cell_at_mut(x, y);
+                let graze = cell.grass.min((1f32 - i.energy) / efficiency).max(0.0);
+                let eat = graze * efficiency;
+                i.energy += eat;
+                i.gathered += eat;
+                cell.grass -= graze;
+                if graze > 0.0 {
+                    cell.regrowth = self.params.regrowth.delay;
                 }
-                common_update(&self.params.herbivores, map, i, (x, y));
+                ledger.grazed += graze;
+                ledger.assimilated += eat;
             }
-        };
-        group_step(&self.herbivores, &mut output.herbivores, herb_step);
-
-        let pred_step = {
-            let map = &mut output.map;
-            // Killing is implemented as setting energy to 0, such that the herbivore gets removed on the next
-            // iteration. Code that renders the state should skip any individual with negative energy.
-            let herb = &mut output.herbivores;
-            fn herbivores_around(vec: &mut Vec<Individual>, x: u32, y: u32, radius: f32) -> impl Iterator<Item=&mut Individual> {
-                let x = x as f32;
-                let y = y as f32;
-                let dist = radius.powf(2f32);
-                vec.iter_mut().filter(move |h| {
-                    h.energy > 0.0 && (h.position[0] - x).powf(2f32) + (h.position[1] - y).powf(2f32) < dist
-                })
-            }
-            move |i: &mut Individual| {
-                let (x, y) = self.individual_pos(i);
-                /* energy */
-                {
-                    if i.energy < 1.0 {
-                        if let Some(target) = herbivores_around(herb, x, y, 2.0).next() {
-                            i.energy = f32::clamp(i.energy + 0.5, 0.0, 1.0);
-                            target.energy = -1.0;
-                        }
+            /* hunting */
+            i.handling = (i.handling - delta).max(0.0);
+            if settings.hunts() && i.handling <= 0.0 && i.energy < settings.hunting_threshold {
+                // Killing is implemented as setting energy to -1, such that the prey gets removed on
+                // the next shuffle. Code that renders the state should skip any individual with
+                // negative energy.
+                let distance = |h: &Individual| {
+                    self.params.boundary.distance(i.position, h.position, self.params.plane_size())
+                };
+                let target = populations.iter_mut()
+                    .zip(&self.params.species)
+                    .filter(|(_, prey)| settings.eats(&prey.name))
+                    .flat_map(|(population, _)| population.iter_mut())
+                    .filter(|h| h.energy > 0.0 && h.born() && distance(h) <= settings.attack_radius)
+                    .min_by(|a, b| distance(a).total_cmp(&distance(b)));
+                if let Some(target) = target {
+                    if crate::random::random::<f32>() < settings.capture_probability {
+                        let before = i.energy;
+                        i.energy = f32::clamp(i.energy + target.energy * settings.energy_transfer, 0.0, 1.0);
+                        i.handling = settings.handling_time;
+                        i.gathered += i.energy - before;
+                        ledger.preyed += target.energy;
+                        ledger.assimilated += i.energy - before;
+                        target.energy = -1.0;
                     }
                 }
-                common_update(&self.params.predators, map, i, (x, y));
             }
+            common_update(species, index, map, ledger, i, (x, y));
         };
-        group_step(&self.carnivores, &mut output.carnivores, pred_step);
 
-        output.map.decay(
-            self.params.decomposition_rate,
-            self.params.decomposition_rate,
-            self.params.decomposition_rate,
-            self.params.growth_rate,
+        for (src, dest) in self.populations.iter().zip(output.populations.iter_mut()) {
+            group_step(src, dest);
+        }
+        for species in 0..output.populations.len() {
+            // taken out of the output for the duration of the step, so that the populations of its
+            // prey can be borrowed at the same time. Species may not feed on themselves.
+            let mut group = std::mem::take(&mut output.populations[species]);
+            // the index every individual had at the start of the step, which the grid refers to
+            let indices = self.populations[species].iter()
+                .enumerate()
+                .filter(|(_, i)| i.energy > 0.0)
+                .map(|(index, _)| index);
+            for (index, i) in indices.zip(group.iter_mut()).filter(|(_, i)| i.energy > 0.0) {
+                // offspring that have not been born yet do nothing but wait for their birth
+                if !i.born() {
+                    i.gestation -= delta;
+                    continue;
+                }
+                species_step(species, index, i, &mut output.populations, &mut output.map, &mut output.ledger);
+            }
+            output.populations[species] = group;
+        }
+
+        let rates = self.params.decomposition_rate.to_array();
+        output.ledger.grown += output.map.decay(
+            [
+                rates[0] * season.decomposition_rate,
+                rates[1] * season.decomposition_rate,
+                rates[2] * season.decomposition_rate,
+            ],
+            self.params.decay_mode.to_array(),
+            &self.params.regrowth,
+            self.params.growth_rate * season.growth_rate,
+            delta,
+        );
+        output.map.diffuse(
+            self.params.diffusion_rate.to_array(),
+            delta,
+            self.diffusion_steps,
         );
     }
 
-    fn shuffle(&mut self, output: &mut State) {
-        let shuffle = |settings: &Group, group: &mut Vec<Individual>, idx: usize| -> Option<Individual> {
-            let len = group.len();
-            if len >= settings.budget as usize || group[idx].energy < settings.reproduction_min {
+    fn shuffle(&mut self, output: &mut State, immigration: &mut Immigration, lineage: &mut Lineage,
+               delta: Duration) {
+        let (boundary, plane) = (self.params.boundary, self.params.plane_size());
+        let conservation = self.params.energy_conservation;
+        let shuffle = |species: usize, settings: &Group, group: &mut Vec<Individual>, idx: usize,
+                       size: &mut usize, ledger: &mut EnergyLedger, turnover: &mut Turnover,
+                       lineage: &mut Lineage| -> Option<Individual> {
+            if !fertile(settings, &group[idx]) {
                 return None;
             }
-            let partner_idx = {
-                let mut chosen = idx;
-                for j in 0..len {
-                    if (group[j].energy > settings.reproduction_min && group[j].energy > group[chosen].energy) || chosen == idx {
-                        chosen = j;
+            let partner_idx = choose_partner(settings, group, idx, boundary, plane)?;
+
+            // once the species is at its budget, the newborn either takes the place of another
+            // individual or is not born at all
+            if *size >= settings.budget as usize {
+                match choose_victim(settings.budget_policy, group, idx, partner_idx) {
+                    Some(victim) => {
+                        ledger.expired += group[victim].energy.max(0.0);
+                        group[victim].energy = -1.0;
+                        turnover.replaced += 1;
+                    }
+                    None => {
+                        turnover.rejected += 1;
+                        return None;
                     }
                 }
-                if chosen == idx {
-                    return None;
-                }
-                chosen
-            };
+            } else {
+                *size += 1;
+            }
+
             let (me, partner) = borrow_two_mut(group, idx, partner_idx);
 
             me.energy -= settings.reproduction_cost;
             partner.energy -= settings.reproduction_cost;
+            me.cooldown = settings.reproduction_cooldown;
+            partner.cooldown = settings.reproduction_cooldown;
+            me.offspring += 1;
+            partner.offspring += 1;
 
+            let energy = if conservation {
+                2.0 * settings.reproduction_cost * settings.reproduction_efficiency
+            } else {
+                settings.offspring_energy
+            };
+            ledger.invested += 2.0 * settings.reproduction_cost;
+            ledger.endowed += energy;
+
+            let around_mother = |radius: f32| {
+                let angle = crate::random::random::<f32>() * 2.0 * std::f32::consts::PI;
+                let radius = crate::random::random::<f32>().sqrt() * radius;
+                boundary.bound([
+                    me.position[0] + radius * angle.cos(),
+                    me.position[1] + radius * angle.sin()
+                ], plane)
+            };
+            let position = if settings.offspring_placement == OffspringPlacement::Mother {
+                // born somewhere around the initiating parent, the mother
+                around_mother(settings.scatter_radius)
+            } else if settings.local_mating {
+                around_mother(settings.offspring_jitter)
+            } else {
+                // halfway along the shortest way between the parents
+                let offset = boundary.offset(me.position, partner.position, plane);
+                boundary.bound([me.position[0] + offset[0] / 2.0, me.position[1] + offset[1] / 2.0], plane)
+            };
             let mut child = Individual {
-                position: [(me.position[0] + partner.position[0]) / 2.0, (me.position[1] + partner.position[1]) / 2.0],
+                position,
                 velocity: [(me.velocity[0] + partner.velocity[0]) / 2.0, (me.velocity[1] + partner.velocity[1]) / 2.0],
-                energy: settings.offspring_energy,
-                weights: Default::default(),
+                energy,
+                age: 0.0,
+                handling: 0.0,
+                cooldown: 0.0,
+                gestation: settings.gestation,
+                signature: Default::default(),
+                id: 0,
+                parents: [0, 0],
+                generation: 0,
+                birth_step: 0,
+                offspring: 0,
+                gathered: 0.0,
+                weights: [[0.0; NN_INPUTS]; NN_OUTPUTS],
                 biases: Default::default(),
             };
+            for i in 0..me.signature.len() {
+                let mutation = (crate::random::random::<f32>() * 2.0 - 1.0) * settings.signature_mutation;
+                child.signature[i] =
+                    ((me.signature[i] + partner.signature[i]) / 2.0 + mutation).clamp(0.0, 1.0);
+            }
             for i in 0..me.weights.len() {
                 for j in 0..me.weights[0].len() {
                     child.weights[i][j] =
@@ -360,47 +818,126 @@ impl State {
             for i in 0..me.biases.len() {
                 child.biases[i] = (me.biases[i] + partner.biases[i]) / 2.0;
             }
+            lineage.conceive(species, &mut child, me, partner);
             Some(child)
         };
 
-        {
-            let herb = &self.params.herbivores;
-            group_step_index(&mut self.herbivores, &mut output.herbivores,
-                             |v, idx| shuffle(herb, v, idx));
-        }
-        {
-            let pred = &self.params.predators;
-            group_step_index(&mut self.carnivores, &mut output.carnivores,
-                             |v, idx| shuffle(pred, v, idx));
+        output.ledger = self.ledger;
+        output.turnover.clone_from(&self.turnover);
+        for (species, settings) in self.params.species.iter().enumerate() {
+            let ledger = &mut output.ledger;
+            let turnover = &mut output.turnover[species];
+            let mut size = self.populations[species].iter().filter(|i| i.energy >= 0.0).count();
+            group_step_index(&mut self.populations[species], &mut output.populations[species],
+                             |v, idx| shuffle(species, settings, v, idx, &mut size, ledger, turnover,
+                                              &mut *lineage));
+
+            // whoever was flagged as dead, be it during the step or by the shuffle, is gone now
+            for i in self.populations[species].iter().filter(|i| i.energy < 0.0) {
+                lineage.death(species, i);
+            }
+
+            // immigrants arrive once the newborns have taken up their room
+            let population = &mut output.populations[species];
+            let alive = population.iter().filter(|i| i.energy > 0.0).count();
+            let room = (settings.budget as usize).saturating_sub(population.len());
+            let mut immigrants = immigration.immigrants(species, alive, room, delta.as_secs_f32());
+            lineage.arrive(species, &mut immigrants);
+            turnover.immigrants += immigrants.len() as u64;
+            population.extend(immigrants);
         }
+        lineage.advance();
     }
 
     fn individual_pos(&self, i: &Individual) -> (u32, u32) {
+        self.cell_of(i.position)
+    }
+
+    /// Cell of the map the given position falls in, clamped to the map.
+    fn cell_of(&self, position: [f32; 2]) -> (u32, u32) {
+        let x = position[0] / self.params.plane_width * self.params.horizontal_granularity as f32;
+        let y = position[1] / self.params.plane_height * self.params.vertical_granularity as f32;
         (
-            (i.position[0] / self.params.plane_width).floor() as u32,
-            (i.position[1] / self.params.plane_height).floor() as u32,
+            x.floor().clamp(0.0, (self.params.horizontal_granularity - 1) as f32) as u32,
+            y.floor().clamp(0.0, (self.params.vertical_granularity - 1) as f32) as u32,
         )
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Debug)]
 pub struct World {
     state: State,
     temp_state: State,
+    /// Simulated time, in seconds, since the start of the simulation.
+    time: f32,
+    immigration: Immigration,
+    /// Lineage of the individuals of all species.
+    lineage: Lineage,
 }
 
 impl World {
-    pub fn new(params: &Simulation) -> Self {
-        let state = State::new(params);
+    pub fn new(params: &Simulation, terrain: &Terrain, immigration: Immigration, mut lineage: Lineage) -> Self {
+        let state = State::new(params, terrain);
+        lineage.found(&state.populations);
         World {
             state: state.clone(),
             temp_state: state,
+            time: 0.0,
+            immigration,
+            lineage,
         }
     }
 
     pub fn step(&mut self, delta: Duration) {
-        self.state.step(&mut self.temp_state, delta);
-        self.temp_state.shuffle(&mut self.state);
+        // individuals left with no energy at all by the last step are not carried over by this one
+        for (species, population) in self.state.populations.iter().enumerate() {
+            for i in population.iter().filter(|i| i.energy <= 0.0) {
+                self.lineage.death(species, i);
+            }
+        }
+
+        let season = self.state.params.schedules.at(self.time);
+        self.state.grid.fill(&self.state.populations);
+        self.state.step(&mut self.temp_state, delta, season);
+        self.temp_state.shuffle(&mut self.state, &mut self.immigration, &mut self.lineage, delta);
+        self.time += delta.as_secs_f32();
+    }
+
+    /// Takes the given scripted action on the current state of the world.
+    pub fn apply(&mut self, action: &Action) {
+        match action {
+            Action::WipeGrass { region } => self.state.map.wipe_grass(*region),
+            Action::Set { parameter, value } => {
+                for state in [&mut self.state, &mut self.temp_state].iter_mut() {
+                    state.params.set(*parameter, *value);
+                    state.diffusion_steps = state.params.diffusion_steps();
+                }
+            }
+            Action::Export { species, .. } => {
+                let population = self.state.populations[*species].iter()
+                    .filter(|i| i.energy > 0.0)
+                    .copied()
+                    .collect::<Vec<_>>();
+                match action.export(&population) {
+                    Ok(count) => info!("exported {} genomes", count),
+                    Err(what) => warn!("could not export genomes: {}", what),
+                }
+            }
+            _ => if let Some(species) = action.species() {
+                let budget = self.state.params.species[species].budget as usize;
+                let population = &mut self.state.populations[species];
+                let before = population.clone();
+                population.retain(|i| i.energy > 0.0);
+                action.populate(population, budget);
+                self.lineage.reconcile(species, &before, population);
+            }
+        }
+    }
+
+    pub fn statistics(&self) -> Statistics {
+        let names = self.state.params.species.iter().map(|group| group.name.as_str());
+        Statistics::new(names.zip(&self.state.populations), &self.state.turnover, self.state.ledger,
+                        &self.state.params.diversity)
     }
 
     pub fn current_state(&self) -> &State {
@@ -412,34 +949,112 @@ impl World {
     }
 }
 
-fn group_step<F: FnMut(&mut Individual) -> ()>(src: &Vec<Individual>, dest: &mut Vec<Individual>, mut f: F) {
+/// Copies the individuals still alive from one population over to another.
+fn group_step(src: &Vec<Individual>, dest: &mut Vec<Individual>) {
     dest.clear();
     src.iter()
-        .filter_map(|i| {
-            if i.energy <= 0.0 {
-                return None;
-            }
-            let mut i = *i;
-            f(&mut i);
-            Some(i)
-        })
-        .for_each(|i| dest.push(i));
+        .filter(|i| i.energy > 0.0)
+        .for_each(|i| dest.push(*i));
 }
 
+/// Copies the individuals still alive from one population over to another, followed by the
+/// children the given function has them give birth to. The function may flag any individual of
+/// the source population as dead by giving it a negative energy, including ones that it was
+/// already called for.
 fn group_step_index<F: FnMut(&mut Vec<Individual>, usize) -> Option<Individual>>(src: &mut Vec<Individual>, dest: &mut Vec<Individual>, mut f: F) {
     dest.clear();
     let initial_len = src.len();
+    let mut children = Vec::new();
     for i in 0..initial_len {
-        //remove individuals killed by predators
+        //skip individuals killed by hunters or replaced by newborns
         if src[i].energy < 0.0 {
             continue;
         }
         if let Some(child) = f(src, i) {
-            dest.push(child);
+            children.push(child);
         }
-        dest.push(src[i]);
         debug_assert_eq!(src.len(), initial_len, "Source vector was resized! The function must only modify elements, not add");
     }
+    dest.extend(src.iter().filter(|i| i.energy >= 0.0));
+    dest.append(&mut children);
+}
+
+/// Chooses the individual a newborn of the individuals at `idx` and `partner` takes the place of
+/// in a group at its budget, following the given policy, returning `None` if the birth should
+/// not happen. The parents and individuals already flagged as dead are never chosen.
+fn choose_victim(policy: BudgetPolicy, group: &[Individual], idx: usize, partner: usize) -> Option<usize> {
+    let spared = |j: usize| j == idx || j == partner || group[j].energy < 0.0;
+    let candidates = || (0..group.len()).filter(|&j| !spared(j));
+
+    match policy {
+        BudgetPolicy::Reject => None,
+        BudgetPolicy::ReplaceLowestEnergy => candidates()
+            .min_by(|&a, &b| group[a].energy.total_cmp(&group[b].energy)),
+        BudgetPolicy::ReplaceOldest => candidates()
+            .max_by(|&a, &b| group[a].age.total_cmp(&group[b].age)),
+        BudgetPolicy::ReplaceRandom => {
+            let candidates = candidates().collect::<Vec<_>>();
+            crate::random::with(|rng| candidates.choose(rng).copied())
+        }
+    }
+}
+
+/// Whether the given individual of a group may reproduce, that is, whether it has been born, is
+/// old enough, has enough energy and is done cooling down from its last reproduction.
+fn fertile(settings: &Group, individual: &Individual) -> bool {
+    individual.born()
+        && individual.energy >= settings.reproduction_min
+        && individual.age >= settings.reproduction_age
+        && individual.cooldown <= 0.0
+}
+
+/// Chooses a mate for the individual at `idx` using the strategy set for the group, returning
+/// `None` if no eligible partner could be found. Distances are measured following the given
+/// boundary of a plane of the given size.
+fn choose_partner(settings: &Group, group: &[Individual], idx: usize, boundary: Boundary, size: [f32; 2])
+                  -> Option<usize> {
+    let distance = |j: usize| boundary.distance(group[idx].position, group[j].position, size);
+    let eligible = |j: usize| {
+        j != idx
+            && fertile(settings, &group[j])
+            && (!settings.local_mating || distance(j) <= settings.mating_radius)
+    };
+    let fittest = |candidates: &mut dyn Iterator<Item=usize>| {
+        candidates
+            .filter(|&j| eligible(j))
+            .max_by(|&a, &b| group[a].energy.total_cmp(&group[b].energy))
+    };
+
+    match settings.mate_selection {
+        MateSelection::GlobalBest => fittest(&mut (0..group.len())),
+        MateSelection::Nearest => (0..group.len())
+            .filter(|&j| eligible(j) && distance(j) <= settings.view_radius)
+            .min_by(|&a, &b| distance(a).total_cmp(&distance(b))),
+        MateSelection::Tournament => {
+            // candidates are only ever drawn out of the eligible individuals, so that every
+            // tournament is as big as it can be
+            let candidates = (0..group.len()).filter(|&j| eligible(j)).collect::<Vec<_>>();
+            crate::random::with(|rng| {
+                fittest(&mut (0..settings.tournament_size).filter_map(|_| candidates.choose(rng).copied()))
+            })
+        }
+        MateSelection::Roulette => {
+            let total: f32 = (0..group.len())
+                .filter(|&j| eligible(j))
+                .map(|j| group[j].energy)
+                .sum();
+            let mut target = crate::random::random::<f32>() * total;
+            let mut chosen = None;
+            for j in (0..group.len()).filter(|&j| eligible(j)) {
+                chosen = Some(j);
+                target -= group[j].energy;
+                if target <= 0.0 {
+                    break;
+                }
+            }
+            chosen
+        }
+    }
 }
 
 #[inline(always)]
@@ -452,3 +1067,259 @@ fn borrow_two_mut<T>(vec: &mut Vec<T>, left: usize, right: usize) -> (&mut T, &m
         (&mut *ptr.add(left), &mut *ptr.add(right))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::genome::Genome;
+    use crate::settings::Preferences;
+
+    const PLANE: [f32; 2] = [100.0, 100.0];
+
+    /// Individual with a blank genome, born and ready to reproduce as far as its age goes.
+    fn individual(position: [f32; 2], energy: f32) -> Individual {
+        Genome {
+            signature: [0.0; 3],
+            weights: vec![vec![0.0; NN_INPUTS]; NN_OUTPUTS],
+            biases: [0.0; NN_OUTPUTS],
+        }.individual(position, energy)
+    }
+
+    /// Group of the default settings choosing its mates with the given strategy, in which every
+    /// individual with at least half of its energy left is fertile.
+    fn group(mate_selection: MateSelection) -> Group {
+        let mut group = Preferences::default().simulation.species[0].clone();
+        group.mate_selection = mate_selection;
+        group.reproduction_min = 0.5;
+        group.reproduction_age = 0.0;
+        group.local_mating = false;
+        group
+    }
+
+    const STRATEGIES: [MateSelection; 4] = [
+        MateSelection::GlobalBest,
+        MateSelection::Nearest,
+        MateSelection::Tournament,
+        MateSelection::Roulette,
+    ];
+
+    #[test]
+    fn global_best_picks_the_fittest() {
+        let settings = group(MateSelection::GlobalBest);
+        let population = [
+            individual([10.0, 10.0], 0.9),
+            individual([20.0, 20.0], 0.6),
+            individual([30.0, 30.0], 0.8),
+            individual([40.0, 40.0], 0.3),
+        ];
+
+        assert_eq!(choose_partner(&settings, &population, 1, Boundary::Torus, PLANE), Some(0));
+        assert_eq!(choose_partner(&settings, &population, 0, Boundary::Torus, PLANE), Some(2));
+    }
+
+    #[test]
+    fn nearest_picks_the_closest_in_view() {
+        let mut settings = group(MateSelection::Nearest);
+        settings.view_radius = 10.0;
+        let population = [
+            individual([50.0, 50.0], 0.6),
+            individual([58.0, 50.0], 0.9),
+            individual([50.0, 53.0], 0.6),
+            individual([51.0, 50.0], 0.3),
+            individual([50.0, 70.0], 1.0),
+        ];
+
+        assert_eq!(choose_partner(&settings, &population, 0, Boundary::Torus, PLANE), Some(2));
+        assert_eq!(choose_partner(&settings, &population, 4, Boundary::Torus, PLANE), None);
+    }
+
+    #[test]
+    fn nearest_follows_the_boundary() {
+        let mut settings = group(MateSelection::Nearest);
+        settings.view_radius = 10.0;
+        let population = [
+            individual([1.0, 50.0], 0.6),
+            individual([8.0, 50.0], 0.6),
+            individual([99.0, 50.0], 0.6),
+        ];
+
+        assert_eq!(choose_partner(&settings, &population, 0, Boundary::Torus, PLANE), Some(2));
+        assert_eq!(choose_partner(&settings, &population, 0, Boundary::Clamp, PLANE), Some(1));
+    }
+
+    #[test]
+    fn tournament_only_draws_eligible_individuals() {
+        let settings = group(MateSelection::Tournament);
+        let population = [
+            individual([10.0, 10.0], 0.6),
+            individual([20.0, 20.0], 0.1),
+            individual([30.0, 30.0], 0.2),
+            individual([40.0, 40.0], 0.7),
+            individual([50.0, 50.0], 0.3),
+        ];
+
+        crate::random::seed(28);
+        for _ in 0..100 {
+            assert_eq!(choose_partner(&settings, &population, 0, Boundary::Torus, PLANE), Some(3));
+        }
+    }
+
+    #[test]
+    fn roulette_only_picks_eligible_individuals() {
+        let settings = group(MateSelection::Roulette);
+        let population = [
+            individual([10.0, 10.0], 0.6),
+            individual([20.0, 20.0], 0.9),
+            individual([30.0, 30.0], 0.2),
+            individual([40.0, 40.0], 0.7),
+        ];
+
+        crate::random::seed(28);
+        for _ in 0..100 {
+            let partner = choose_partner(&settings, &population, 0, Boundary::Torus, PLANE);
+            assert!(partner == Some(1) || partner == Some(3), "unexpected partner: {:?}", partner);
+        }
+    }
+
+    #[test]
+    fn nobody_eligible() {
+        let mut population = [
+            individual([10.0, 10.0], 0.6),
+            individual([20.0, 20.0], 0.4),
+            individual([30.0, 30.0], 0.9),
+            individual([40.0, 40.0], 0.9),
+        ];
+        // neither born yet nor done cooling down
+        population[2].gestation = 1.0;
+        population[3].cooldown = 1.0;
+
+        for &strategy in STRATEGIES.iter() {
+            let settings = group(strategy);
+            assert_eq!(choose_partner(&settings, &population, 0, Boundary::Torus, PLANE), None,
+                       "{:?} found a partner", strategy);
+        }
+    }
+
+    #[test]
+    fn local_mating_leaves_out_distant_individuals() {
+        let population = [
+            individual([50.0, 50.0], 0.6),
+            individual([80.0, 50.0], 1.0),
+            individual([53.0, 50.0], 0.6),
+        ];
+
+        crate::random::seed(28);
+        for &strategy in STRATEGIES.iter() {
+            let mut settings = group(strategy);
+            settings.view_radius = 10.0;
+            settings.local_mating = true;
+            settings.mating_radius = 5.0;
+            assert_eq!(choose_partner(&settings, &population, 0, Boundary::Torus, PLANE), Some(2),
+                       "{:?} picked a distant partner", strategy);
+        }
+    }
+
+    /// Group of individuals 0 and 1 mating, next to a dead individual and two that could make
+    /// room for their newborn, the first of which is the youngest and has the most energy left.
+    fn budget_group() -> Vec<Individual> {
+        let mut population = vec![
+            individual([10.0, 10.0], 0.1),
+            individual([20.0, 20.0], 0.2),
+            individual([30.0, 30.0], -1.0),
+            individual([40.0, 40.0], 0.5),
+            individual([50.0, 50.0], 0.3),
+        ];
+        for (i, age) in [9.0, 8.0, 10.0, 3.0, 5.0].iter().enumerate() {
+            population[i].age = *age;
+        }
+        population
+    }
+
+    const POLICIES: [BudgetPolicy; 4] = [
+        BudgetPolicy::Reject,
+        BudgetPolicy::ReplaceLowestEnergy,
+        BudgetPolicy::ReplaceOldest,
+        BudgetPolicy::ReplaceRandom,
+    ];
+
+    #[test]
+    fn reject_replaces_nobody() {
+        assert_eq!(choose_victim(BudgetPolicy::Reject, &budget_group(), 0, 1), None);
+    }
+
+    #[test]
+    fn replace_lowest_energy_spares_the_parents_and_the_dead() {
+        assert_eq!(choose_victim(BudgetPolicy::ReplaceLowestEnergy, &budget_group(), 0, 1), Some(4));
+    }
+
+    #[test]
+    fn replace_oldest_spares_the_parents_and_the_dead() {
+        assert_eq!(choose_victim(BudgetPolicy::ReplaceOldest, &budget_group(), 0, 1), Some(4));
+    }
+
+    #[test]
+    fn replace_random_spares_the_parents_and_the_dead() {
+        let population = budget_group();
+
+        crate::random::seed(40);
+        for _ in 0..100 {
+            let victim = choose_victim(BudgetPolicy::ReplaceRandom, &population, 0, 1);
+            assert!(victim == Some(3) || victim == Some(4), "unexpected victim: {:?}", victim);
+        }
+    }
+
+    #[test]
+    fn nobody_to_replace() {
+        let mut population = budget_group();
+        population.truncate(3);
+
+        for &policy in POLICIES.iter() {
+            assert_eq!(choose_victim(policy, &population, 0, 1), None, "{:?} found a victim", policy);
+        }
+    }
+
+    /// World of a single species at its budget, in which every individual is ready to reproduce.
+    fn world_at_budget(policy: BudgetPolicy) -> World {
+        let mut simulation = Preferences::default().simulation;
+        simulation.species.truncate(1);
+        let group = &mut simulation.species[0];
+        group.individuals = 16;
+        group.budget = 16;
+        group.budget_policy = policy;
+        group.reproduction_min = 0.5;
+        group.reproduction_age = 0.0;
+        group.mate_selection = MateSelection::GlobalBest;
+        group.local_mating = false;
+
+        let mut world = World::new(&simulation, &Terrain::open(&simulation),
+                                   Immigration::closed(&simulation), Lineage::unlogged(&simulation));
+        for individual in &mut world.current_state_mut().populations[0] {
+            individual.energy = 1.0;
+        }
+        world
+    }
+
+    #[test]
+    fn reject_counts_rejected_births() {
+        crate::random::seed(40);
+        let mut world = world_at_budget(BudgetPolicy::Reject);
+        world.step(Duration::from_millis(10));
+
+        let state = world.current_state();
+        assert_eq!(state.populations[0].len(), 16);
+        assert_eq!(state.turnover[0].replaced, 0);
+        assert_eq!(state.turnover[0].rejected, 16);
+    }
+
+    #[test]
+    fn replace_counts_replaced_individuals() {
+        crate::random::seed(40);
+        let mut world = world_at_budget(BudgetPolicy::ReplaceLowestEnergy);
+        world.step(Duration::from_millis(10));
+
+        let state = world.current_state();
+        assert_eq!(state.populations[0].len(), 16);
+        assert!(state.turnover[0].replaced > 0);
+        assert!(state.turnover[0].replaced + state.turnover[0].rejected <= 16);
+    }
+}
@@ -4,7 +4,12 @@ use wgpu::{ShaderModule, PipelineLayout, ComputePipeline, ShaderModuleSource, De
 use crate::flipbook::Producer;
 use std::time::Duration;
 use wgpu::util::{DeviceExt, BufferInitDescriptor};
-use crate::settings::Preferences;
+use crate::settings::{Preferences, Simulation};
+use crate::events::Action;
+use crate::immigration::Immigration;
+use crate::statistics::{Statistics, Turnover};
+use crate::lineage::Lineage;
+use crate::flipbook::Frame;
 
 /** An instance of the compute pipeline. */
 struct Pipeline {
@@ -20,6 +25,7 @@ impl Pipeline {
 	pub fn new(
 		device:   &Device,
 		params:   &ComputeParameters,
+		grid:     &SpatialGrid,
 		flipbook: &Producer,
 		shader:   ShaderModuleSource<'static>) -> Self {
 
@@ -29,7 +35,8 @@ impl Pipeline {
 				label: Some("Evo/Pipeline/Layout"),
 				bind_group_layouts: &[
 					flipbook.layout(),
-					&params.layout
+					&params.layout,
+					&grid.layout
 				],
 				push_constant_ranges: &[]
 			});
@@ -53,18 +60,25 @@ impl Pipeline {
 
 /** Parameters passed to the compute functions. */
 struct ComputeParameters {
-	/** Storage buffer backing the parameter data. */
+	/** Uniform buffer backing the parameter data shared by all species. */
 	pub buffer: Buffer,
+	/** Storage buffer backing the parameter data of every species. */
+	pub species: Buffer,
+	/** Uniform buffers holding the index of the species each dispatch runs
+	 * for, one for every species. */
+	pub dispatch: Vec<Buffer>,
 	/** Layout of the binding group for these parameters. */
 	pub layout: BindGroupLayout,
-	/** Binding group for these parameters. */
-	pub bind: BindGroup
+	/** Binding groups for these parameters, one for every species. Dispatches
+	 * that do not run for a given species may use any of them. */
+	pub binds: Vec<BindGroup>
 }
 impl ComputeParameters {
 	/** Creates a new compute parameter set. */
 	pub fn new(
 		device: &Device,
-		params: crate::dataset::ComputeParameters) -> Self {
+		params: crate::dataset::ComputeParameters,
+		species: &[crate::dataset::SpeciesParameters]) -> Self {
 
 		let mut buffer = Vec::with_capacity(4);
 		params.bytes(&mut buffer);
@@ -74,6 +88,34 @@ impl ComputeParameters {
 				contents: &buffer[..],
 				usage: BufferUsage::UNIFORM | BufferUsage::COPY_DST
 			});
+
+		let mut species_buffer = Vec::new();
+		for params in species {
+			params.bytes(&mut species_buffer);
+		}
+		let species_buffer = device.create_buffer_init(
+			&BufferInitDescriptor {
+				label: Some("Evo/ComputeParameters/SpeciesBuffer"),
+				contents: &species_buffer[..],
+				usage: BufferUsage::STORAGE | BufferUsage::COPY_DST
+			});
+
+		let dispatch = (0..species.len() as u32)
+			.map(|index| {
+				/* Uniform buffers are padded out to a full vec4. */
+				let mut contents = Vec::with_capacity(16);
+				contents.extend_from_slice(&index.to_ne_bytes());
+				contents.resize(16, 0);
+
+				device.create_buffer_init(
+					&BufferInitDescriptor {
+						label: Some("Evo/ComputeParameters/DispatchBuffer"),
+						contents: &contents[..],
+						usage: BufferUsage::UNIFORM
+					})
+			})
+			.collect::<Vec<_>>();
+
 		let layout = device.create_bind_group_layout(
 			&BindGroupLayoutDescriptor {
 				label: Some("Evo/ComputeParameters/Layout"),
@@ -86,33 +128,182 @@ impl ComputeParameters {
 							min_binding_size: None
 						},
 						count: None
+					},
+					BindGroupLayoutEntry {
+						binding: 1,
+						visibility: ShaderStage::COMPUTE,
+						ty: BindingType::StorageBuffer {
+							dynamic: false,
+							min_binding_size: None,
+							readonly: true
+						},
+						count: None
+					},
+					BindGroupLayoutEntry {
+						binding: 2,
+						visibility: ShaderStage::COMPUTE,
+						ty: BindingType::UniformBuffer {
+							dynamic: false,
+							min_binding_size: None
+						},
+						count: None
 					}
 				]
 			});
+		let binds = dispatch.iter()
+			.map(|dispatch| device.create_bind_group(
+				&BindGroupDescriptor {
+					label: Some("Evo/ComputeParameters/BindGroup"),
+					layout: &layout,
+					entries: &[
+						BindGroupEntry {
+							binding: 0,
+							resource: BindingResource::Buffer(buffer.slice(..))
+						},
+						BindGroupEntry {
+							binding: 1,
+							resource: BindingResource::Buffer(species_buffer.slice(..))
+						},
+						BindGroupEntry {
+							binding: 2,
+							resource: BindingResource::Buffer(dispatch.slice(..))
+						}
+					]
+				}))
+			.collect();
+
+		Self {
+			buffer,
+			species: species_buffer,
+			dispatch,
+			layout,
+			binds
+		}
+	}
+
+	/** Update the data in the buffer with the given data. */
+	pub fn update(&mut self, queue: &Queue, params: crate::dataset::ComputeParameters) {
+		let mut buffer = Vec::with_capacity(4);
+		params.bytes(&mut buffer);
+		queue.write_buffer(&self.buffer, 0, &buffer[..]);
+	}
+}
+
+/** Spatial grid the individuals alive are sorted into at the start of every
+ * iteration, along with the grass grazed off the field during the iteration,
+ * as described in `Grid.glslh`. */
+struct SpatialGrid {
+	/** Storage buffer holding the number of individuals in every cell. */
+	pub counts: Buffer,
+	/** Storage buffer holding the individuals in every cell. */
+	pub entries: Buffer,
+	/** Storage buffer holding the positions of the individuals in every
+	 * cell. */
+	pub positions: Buffer,
+	/** Storage buffer holding the grass grazed off every cell of the
+	 * field. */
+	pub grazed: Buffer,
+	/** Number of cells in the grid. */
+	pub cells: usize,
+	/** Number of cells in the field. */
+	pub field: usize,
+	/** Layout of the binding group for the grid. */
+	pub layout: BindGroupLayout,
+	/** Binding group for the grid. */
+	pub bind: BindGroup
+}
+impl SpatialGrid {
+	/** Creates the spatial grid for the given simulation settings. */
+	pub fn new(device: &Device, simulation: &Simulation) -> Self {
+		let [width, height] = simulation.grid.size(
+			simulation.plane_width,
+			simulation.plane_height);
+		let cells = width as usize * height as usize;
+		let capacity = simulation.grid.cell_capacity.max(1) as usize;
+		let field = simulation.horizontal_granularity as usize
+			* simulation.vertical_granularity as usize;
+
+		let counts = device.create_buffer_init(
+			&BufferInitDescriptor {
+				label: Some("Evo/SpatialGrid/Counts"),
+				contents: &vec![0; cells * 4][..],
+				usage: BufferUsage::STORAGE | BufferUsage::COPY_DST
+			});
+		let entries = device.create_buffer_init(
+			&BufferInitDescriptor {
+				label: Some("Evo/SpatialGrid/Entries"),
+				contents: &vec![0; cells * capacity * 8][..],
+				usage: BufferUsage::STORAGE
+			});
+		let positions = device.create_buffer_init(
+			&BufferInitDescriptor {
+				label: Some("Evo/SpatialGrid/Positions"),
+				contents: &vec![0; cells * capacity * 8][..],
+				usage: BufferUsage::STORAGE
+			});
+		let grazed = device.create_buffer_init(
+			&BufferInitDescriptor {
+				label: Some("Evo/SpatialGrid/Grazed"),
+				contents: &vec![0; field * 4][..],
+				usage: BufferUsage::STORAGE | BufferUsage::COPY_DST
+			});
+
+		let entry = |binding| BindGroupLayoutEntry {
+			binding,
+			visibility: ShaderStage::COMPUTE,
+			ty: BindingType::StorageBuffer {
+				dynamic: false,
+				min_binding_size: None,
+				readonly: false
+			},
+			count: None
+		};
+		let layout = device.create_bind_group_layout(
+			&BindGroupLayoutDescriptor {
+				label: Some("Evo/SpatialGrid/Layout"),
+				entries: &[entry(0), entry(1), entry(2), entry(3)]
+			});
 		let bind = device.create_bind_group(
 			&BindGroupDescriptor {
-				label: Some("Evo/ComputeParameters/BindGroup"),
+				label: Some("Evo/SpatialGrid/BindGroup"),
 				layout: &layout,
 				entries: &[
 					BindGroupEntry {
 						binding: 0,
-						resource: BindingResource::Buffer(buffer.slice(..))
+						resource: BindingResource::Buffer(counts.slice(..))
+					},
+					BindGroupEntry {
+						binding: 1,
+						resource: BindingResource::Buffer(entries.slice(..))
+					},
+					BindGroupEntry {
+						binding: 2,
+						resource: BindingResource::Buffer(grazed.slice(..))
+					},
+					BindGroupEntry {
+						binding: 3,
+						resource: BindingResource::Buffer(positions.slice(..))
 					}
 				]
 			});
 
 		Self {
-			buffer,
+			counts,
+			entries,
+			positions,
+			grazed,
+			cells,
+			field,
 			layout,
 			bind
 		}
 	}
 
-	/** Update the data in the buffer with the given data. */
-	pub fn update(&mut self, queue: &Queue, params: crate::dataset::ComputeParameters) {
-		let mut buffer = Vec::with_capacity(4);
-		params.bytes(&mut buffer);
-		queue.write_buffer(&self.buffer, 0, &buffer[..]);
+	/** Empties out all of the cells of the grid and forgets about all of the
+	 * grass grazed. */
+	pub fn clear(&self, queue: &Queue) {
+		queue.write_buffer(&self.counts, 0, &vec![0; self.cells * 4][..]);
+		queue.write_buffer(&self.grazed, 0, &vec![0; self.field * 4][..]);
 	}
 }
 
@@ -120,108 +311,227 @@ pub struct Evo<A> {
 	state: A,
 	base_params: crate::dataset::ComputeParameters,
 	params: ComputeParameters,
+	grid: SpatialGrid,
+	/** Simulation settings the base parameters are built from, as changed
+	 * by any scripted events. */
+	simulation: Simulation,
+	/** Region the grass is to be wiped from before the next iteration. */
+	wipe: Option<[f32; 4]>,
+	/** Immigration of individuals into every species. */
+	immigration: Immigration,
+	/** Lineage of the individuals of all species. */
+	lineage: Lineage,
+	/** Simulated time, in seconds, since the start of the simulation. */
+	time: f32,
 	flipbook: Producer,
 
-	pre_run_herbivores: Pipeline,
-	simulate_herbivores: Pipeline,
-	shuffle_herbivores: Pipeline,
-
-	pre_run_predators: Pipeline,
-	simulate_predators: Pipeline,
-	shuffle_predators: Pipeline,
+	/** Names of all species, in the order they were declared in. */
+	names: Vec<String>,
 
+	pre_run: Pipeline,
+	fill_grid: Pipeline,
+	simulate: Pipeline,
+	shuffle: Pipeline,
 	update_plane: Pipeline,
+	diffuse: Pipeline,
+	settle: Pipeline,
 }
 impl<A> Evo<A>
 	where A: Borrow<State> {
 
 	/** Creates a new instance of the evolution driver. */
-	pub fn new(state: A, flipbook: Producer, prefs: &Preferences) -> Self {
+	pub fn new(
+		state: A,
+		flipbook: Producer,
+		prefs: &Preferences,
+		immigration: Immigration,
+		lineage: Lineage) -> Self {
 		let device = state.borrow().device();
 
-		let base_params = crate::dataset::ComputeParameters {
-			delta: 0.0,
-			growth_rate: prefs.simulation.growth_rate,
-			decomposition_rate: prefs.simulation.decomposition_rate,
-			herbivore_view_radius: prefs.simulation.herbivores.view_radius,
-			predator_view_radius: prefs.simulation.predators.view_radius,
-			herbivore_max_speed: prefs.simulation.herbivores.max_speed,
-			predator_max_speed: prefs.simulation.predators.max_speed,
-			herbivore_reproduction_cost: prefs.simulation.herbivores.reproduction_cost,
-			predator_reproduction_cost: prefs.simulation.predators.reproduction_cost,
-			herbivore_reproduction_min: prefs.simulation.herbivores.reproduction_min,
-			predator_reproduction_min: prefs.simulation.predators.reproduction_min,
-			herbivore_offspring_energy: prefs.simulation.herbivores.offspring_energy,
-			predator_offspring_energy: prefs.simulation.predators.offspring_energy,
-			herbivore_penalty: [
-				prefs.simulation.herbivores.metabolism_min,
-				prefs.simulation.herbivores.metabolism_max,
-			],
-			predator_penalty: [
-				prefs.simulation.predators.metabolism_min,
-				prefs.simulation.predators.metabolism_max,
-			],
-			simulation: [
-				prefs.simulation.plane_width,
-				prefs.simulation.plane_height
-			]
-		};
+		let base_params = base_params(&prefs.simulation);
+		let species = flipbook.slices()
+			.iter()
+			.enumerate()
+			.map(|(index, slice)| crate::dataset::SpeciesParameters::new(
+				&prefs.simulation,
+				index,
+				slice.start))
+			.collect::<Vec<_>>();
 		let params = ComputeParameters::new(
 			device,
-			base_params);
-
-		let simulate_herbivores = crate::shaders::compute::herbivores::simulate();
-		let simulate_herbivores = Pipeline::new(device, &params, &flipbook, simulate_herbivores);
+			base_params,
+			&species);
 
-		let shuffle_herbivores = crate::shaders::compute::herbivores::shuffle();
-		let shuffle_herbivores = Pipeline::new(device, &params, &flipbook, shuffle_herbivores);
+		let grid = SpatialGrid::new(device, &prefs.simulation);
 
-		let simulate_predators = crate::shaders::compute::predators::simulate();
-		let simulate_predators = Pipeline::new(device, &params, &flipbook, simulate_predators);
+		let fill_grid = crate::shaders::compute::species::fill_grid();
+		let fill_grid = Pipeline::new(device, &params, &grid, &flipbook, fill_grid);
 
-		let shuffle_predators = crate::shaders::compute::predators::shuffle();
-		let shuffle_predators = Pipeline::new(device, &params, &flipbook, shuffle_predators);
+		let simulate = crate::shaders::compute::species::simulate();
+		let simulate = Pipeline::new(device, &params, &grid, &flipbook, simulate);
 
-		let pre_run_herbivores = crate::shaders::compute::herbivores::pre_run();
-		let pre_run_herbivores = Pipeline::new(device, &params, &flipbook, pre_run_herbivores);
+		let shuffle = crate::shaders::compute::species::shuffle();
+		let shuffle = Pipeline::new(device, &params, &grid, &flipbook, shuffle);
 
-		let pre_run_predators = crate::shaders::compute::predators::pre_run();
-		let pre_run_predators = Pipeline::new(device, &params, &flipbook, pre_run_predators);
+		let pre_run = crate::shaders::compute::pre_run();
+		let pre_run = Pipeline::new(device, &params, &grid, &flipbook, pre_run);
 
 		let update_plane = crate::shaders::compute::update_plane();
-		let update_plane = Pipeline::new(device, &params, &flipbook, update_plane);
+		let update_plane = Pipeline::new(device, &params, &grid, &flipbook, update_plane);
+
+		let diffuse = crate::shaders::compute::diffuse();
+		let diffuse = Pipeline::new(device, &params, &grid, &flipbook, diffuse);
+
+		let settle = crate::shaders::compute::settle();
+		let settle = Pipeline::new(device, &params, &grid, &flipbook, settle);
 
 		Self {
 			state,
 			base_params,
 			params,
+			grid,
+			simulation: prefs.simulation.clone(),
+			wipe: None,
+			immigration,
+			lineage,
+			time: 0.0,
 			flipbook,
-			simulate_herbivores,
-			shuffle_herbivores,
-			pre_run_predators,
-			simulate_predators,
-			shuffle_predators,
-			pre_run_herbivores,
-			update_plane
+			names: prefs.simulation.species
+				.iter()
+				.map(|group| group.name.clone())
+				.collect(),
+			pre_run,
+			fill_grid,
+			simulate,
+			shuffle,
+			update_plane,
+			diffuse,
+			settle
 		}
 	}
 
+	/** Takes the given scripted action on the current state of the
+	 * simulation. Grass gets wiped at the start of the next iteration. */
+	pub async fn apply(&mut self, action: &Action) {
+		match action {
+			Action::WipeGrass { region } => self.wipe = Some(*region),
+			Action::Set { parameter, value } => {
+				self.simulation.set(*parameter, *value);
+				self.base_params = base_params(&self.simulation);
+			},
+			Action::Export { species, .. } => {
+				let mut population = self.flipbook.frame().populations().await.swap_remove(*species);
+				population.retain(|i| i.energy > 0.0);
+
+				match action.export(&population) {
+					Ok(count) => info!("exported {} genomes", count),
+					Err(what) => warn!("could not export genomes: {}", what)
+				}
+			},
+			_ => if let Some(species) = action.species() {
+				let budget = self.simulation.species[species].budget as usize;
+				let mut frame = self.flipbook.frame();
+				settle(&mut self.lineage, &frame).await;
+
+				let before = frame.populations().await.swap_remove(species);
+				let mut population = before.clone();
+				population.retain(|i| i.energy > 0.0);
+				action.populate(&mut population, budget);
+				self.lineage.reconcile(species, &before, &mut population);
+
+				frame.write_population(species, &population).await;
+			}
+		}
+	}
+
+	/** Gather the statistics of the populations as they currently are. */
+	pub async fn statistics(&mut self) -> Statistics {
+		let frame = self.flipbook.frame();
+		let populations = frame.populations().await;
+		let back_channel = frame.back_channel().await;
+		let turnover = self.immigration.arrived()
+			.into_iter()
+			.zip(back_channel.rejected.iter().zip(&back_channel.replaced))
+			.map(|(immigrants, (&rejected, &replaced))| Turnover {
+				immigrants,
+				rejected: u64::from(rejected),
+				replaced: u64::from(replaced)
+			})
+			.collect::<Vec<_>>();
+
+		Statistics::new(
+			self.names.iter().map(String::as_str).zip(&populations),
+			&turnover,
+			frame.ledger().await,
+			&self.simulation.diversity)
+	}
+
 	/** Run an iteration of the evolution algorithm. */
 	pub async fn iterate(&mut self, delta: Duration) {
 		let device = self.state.borrow().device();
 		let queue = self.state.borrow().queue();
-		let frame = self.flipbook.frame();
+		let mut frame = self.flipbook.frame();
+
+		/* Account for the births and deaths of the last iteration. */
+		settle(&mut self.lineage, &frame).await;
+
+		/* Make the room freed up by deaths available to births again. */
+		let mut moved = false;
+		for species in 0..self.names.len() {
+			moved |= frame.compact_population(species).await;
+		}
+
+		/* Bring in the immigrants that arrived since the last shuffle. */
+		let mut ranges = frame.ranges().await;
+		let mut arrived = moved;
+		for (species, range) in ranges.iter().enumerate() {
+			let alive = range.len();
+			let room = (self.simulation.species[species].budget as usize).saturating_sub(alive);
+
+			let mut immigrants = self.immigration.immigrants(
+				species,
+				alive,
+				room,
+				delta.as_secs_f32());
+			if !immigrants.is_empty() {
+				self.lineage.arrive(species, &mut immigrants);
+
+				frame.append_population(species, &immigrants).await;
+				arrived = true;
+			}
+		}
+		if arrived {
+			ranges = frame.ranges().await;
+		}
 
-		/* Update the compute parameters. */
+		/* Update the compute parameters, following their schedules. These go
+		 * after the immigrants, which take identifiers of their own. */
+		let season = self.simulation.schedules.at(self.time);
+		let decomposition = self.base_params.decomposition;
 		self.params.update(
 			queue,
 			crate::dataset::ComputeParameters {
 				delta: delta.as_secs_f32(),
+				seed: rand::random(),
+				growth_rate: self.base_params.growth_rate * season.growth_rate,
+				decomposition: [
+					decomposition[0] * season.decomposition_rate,
+					decomposition[1] * season.decomposition_rate,
+					decomposition[2] * season.decomposition_rate
+				],
+				metabolism: season.metabolism,
+				temperature: season.temperature,
+				wipe_grass: self.wipe.is_some() as u32,
+				wipe_region: self.wipe.take().unwrap_or_default(),
+				next_id: self.lineage.next_id(),
+				step: self.lineage.step(),
+				lineage: self.lineage.logging() as u32,
 				..self.base_params
 			});
+		self.time += delta.as_secs_f32();
 
-		let herbivores = frame.herbivores().await.end;
-		let predators = frame.predators().await.end;
+		frame.clear_ledger();
+		self.grid.clear(queue);
 
 
 		let mut encoder = device.create_command_encoder(
@@ -230,67 +540,161 @@ impl<A> Evo<A>
 			});
 		let mut pass = encoder.begin_compute_pass();
 
-		/* Do the herbivore run. */
-		pass.set_pipeline(&self.pre_run_herbivores.pipeline);
+		/* Clear the contributions of all individuals to the plane. */
+		pass.set_pipeline(&self.pre_run.pipeline);
 		pass.set_bind_group(0, frame.bind_group(), &[]);
-		pass.set_bind_group(1, &self.params.bind, &[]);
+		pass.set_bind_group(1, &self.params.binds[0], &[]);
+		pass.set_bind_group(2, &self.grid.bind, &[]);
 		pass.dispatch(
 			frame.plane_width(),
 			frame.plane_height(),
-			herbivores);
-
-		pass.set_pipeline(&self.simulate_herbivores.pipeline);
-		pass.set_bind_group(0, frame.bind_group(), &[]);
-		pass.set_bind_group(1, &self.params.bind, &[]);
-		pass.dispatch(
-			herbivores,
-			1,
-			1);
-
-		/* Do the predator run. */
-		pass.set_pipeline(&self.pre_run_predators.pipeline);
-		pass.set_bind_group(0, frame.bind_group(), &[]);
-		pass.set_bind_group(1, &self.params.bind, &[]);
-		pass.dispatch(
-			frame.plane_width(),
-			frame.plane_height(),
-			predators);
+			frame.budget());
+
+		/* Sort the individuals of every species into the spatial grid. */
+		for (range, bind) in ranges.iter().zip(&self.params.binds) {
+			pass.set_pipeline(&self.fill_grid.pipeline);
+			pass.set_bind_group(0, frame.bind_group(), &[]);
+			pass.set_bind_group(1, bind, &[]);
+			pass.set_bind_group(2, &self.grid.bind, &[]);
+			pass.dispatch(
+				range.end,
+				1,
+				1);
+		}
 
-		pass.set_pipeline(&self.simulate_predators.pipeline);
-		pass.set_bind_group(0, frame.bind_group(), &[]);
-		pass.set_bind_group(1, &self.params.bind, &[]);
-		pass.dispatch(
-			predators,
-			1,
-			1);
+		/* Do the run of every species, in order. */
+		for (range, bind) in ranges.iter().zip(&self.params.binds) {
+			pass.set_pipeline(&self.simulate.pipeline);
+			pass.set_bind_group(0, frame.bind_group(), &[]);
+			pass.set_bind_group(1, bind, &[]);
+			pass.set_bind_group(2, &self.grid.bind, &[]);
+			pass.dispatch(
+				range.end,
+				1,
+				1);
+		}
 
 		/* Perform all of the required population shuffles. */
-		pass.set_pipeline(&self.shuffle_herbivores.pipeline);
-		pass.set_bind_group(0, frame.bind_group(), &[]);
-		pass.set_bind_group(1, &self.params.bind, &[]);
-		pass.dispatch(
-			herbivores,
-			1,
-			1);
-
-		pass.set_pipeline(&self.shuffle_predators.pipeline);
-		pass.set_bind_group(0, frame.bind_group(), &[]);
-		pass.set_bind_group(1, &self.params.bind, &[]);
-		pass.dispatch(
-			predators,
-			1,
-			1);
+		for (range, bind) in ranges.iter().zip(&self.params.binds) {
+			pass.set_pipeline(&self.shuffle.pipeline);
+			pass.set_bind_group(0, frame.bind_group(), &[]);
+			pass.set_bind_group(1, bind, &[]);
+			pass.set_bind_group(2, &self.grid.bind, &[]);
+			pass.dispatch(
+				range.end,
+				1,
+				1);
+		}
 
 		/* Weave the results and update the plane. */
 		pass.set_pipeline(&self.update_plane.pipeline);
 		pass.set_bind_group(0, frame.bind_group(), &[]);
-		pass.set_bind_group(1, &self.params.bind, &[]);
+		pass.set_bind_group(1, &self.params.binds[0], &[]);
+		pass.set_bind_group(2, &self.grid.bind, &[]);
 		pass.dispatch(
 			frame.plane_width(),
 			frame.plane_height(),
 			1);
 
+		/* Spread the chemicals out, one stable step at a time. */
+		if self.base_params.diffusion.iter().any(|rate| *rate > 0.0) {
+			for _ in 0..self.base_params.diffusion_steps {
+				pass.set_pipeline(&self.diffuse.pipeline);
+				pass.set_bind_group(0, frame.bind_group(), &[]);
+				pass.set_bind_group(1, &self.params.binds[0], &[]);
+				pass.set_bind_group(2, &self.grid.bind, &[]);
+				pass.dispatch(
+					frame.plane_width(),
+					frame.plane_height(),
+					1);
+
+				pass.set_pipeline(&self.settle.pipeline);
+				pass.set_bind_group(0, frame.bind_group(), &[]);
+				pass.set_bind_group(1, &self.params.binds[0], &[]);
+				pass.set_bind_group(2, &self.grid.bind, &[]);
+				pass.dispatch(
+					frame.plane_width(),
+					frame.plane_height(),
+					1);
+			}
+		}
+
 		std::mem::drop(pass);
 		queue.submit(std::iter::once(encoder.finish()));
+
+		self.lineage.advance();
+	}
+}
+
+/** Take down the births and deaths recorded by the last iteration to run on
+ * the given frame into the given lineage, and clear them out. The identifiers
+ * handed out to the newborns are set aside, so that they are never given out
+ * again, whether the births were recorded or not. */
+async fn settle(lineage: &mut Lineage, frame: &Frame<'_>) {
+	if !lineage.founded() {
+		lineage.found(&frame.populations().await);
+	}
+
+	let (births, count, records) = frame.lineage().await;
+	lineage.reserve(u64::from(births));
+	for record in &records {
+		lineage.record(record);
+	}
+	if count as usize > records.len() {
+		warn!("{} births and deaths did not fit in the lineage buffer and were not logged",
+			count as usize - records.len());
+	}
+
+	frame.clear_lineage();
+}
+
+/** Parameters shared by all species for the given simulation settings, before
+ * any of the parameters that change on every iteration are filled in. */
+fn base_params(simulation: &Simulation) -> crate::dataset::ComputeParameters {
+	let decay_mode = simulation.decay_mode.to_array();
+	crate::dataset::ComputeParameters {
+		delta: 0.0,
+		growth_rate: simulation.growth_rate,
+		seed: 0,
+		energy_conservation: simulation.energy_conservation as u32,
+		simulation: [
+			simulation.plane_width,
+			simulation.plane_height
+		],
+		species: simulation.species.len() as u32,
+		wall_proximity: simulation.terrain.wall_proximity_input as u32,
+		diffusion_steps: simulation.diffusion_steps(),
+		regrowth_model: simulation.regrowth.model as u32,
+		regrowth_seed: simulation.regrowth.seed_density,
+		regrowth_spread: simulation.regrowth.spread,
+		decomposition: simulation.decomposition_rate.to_array(),
+		decay_mode: [
+			decay_mode[0] as u32,
+			decay_mode[1] as u32,
+			decay_mode[2] as u32
+		],
+		diffusion: simulation.diffusion_rate.to_array(),
+		regrowth_delay: simulation.regrowth.delay,
+		metabolism: 1.0,
+		wipe_grass: 0,
+		wipe_region: [0.0; 4],
+		grid_cell_size: simulation.grid.cell_size,
+		grid_capacity: simulation.grid.cell_capacity,
+		grid_size: simulation.grid.size(
+			simulation.plane_width,
+			simulation.plane_height),
+		crowding: simulation.crowding.enabled as u32,
+		separation_radius: simulation.crowding.separation_radius,
+		separation_force: simulation.crowding.separation_force,
+		boundary: simulation.boundary as u32,
+		temperature_range: [
+			simulation.temperature.low,
+			simulation.temperature.high
+		],
+		temperature: 1.0,
+		temperature_input: simulation.temperature.input as u32,
+		next_id: 0,
+		step: 0,
+		lineage: 0
 	}
 }
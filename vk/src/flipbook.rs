@@ -1,12 +1,14 @@
-use wgpu::{BindGroupLayout, Buffer, Texture, BufferUsage, BindGroup, BindGroupLayoutDescriptor, BindGroupLayoutEntry, ShaderStage, BindingType, TextureViewDimension, TextureFormat, Device, TextureDescriptor, Extent3d, TextureDimension, TextureUsage, BindGroupDescriptor, BindGroupEntry, BindingResource, TextureViewDescriptor, TextureAspect, MapMode, Queue, TextureCopyView, Origin3d, TextureDataLayout, CommandBuffer, CommandEncoderDescriptor};
+use wgpu::{BindGroupLayout, Buffer, Texture, TextureView, BufferUsage, BindGroup, BindGroupLayoutDescriptor, BindGroupLayoutEntry, ShaderStage, BindingType, TextureViewDimension, TextureFormat, Device, TextureDescriptor, Extent3d, TextureDimension, TextureUsage, BindGroupDescriptor, BindGroupEntry, BindingResource, TextureViewDescriptor, TextureAspect, MapMode, Queue, TextureCopyView, Origin3d, TextureDataLayout, CommandBuffer, CommandEncoderDescriptor};
 use crate::state::State;
 use std::borrow::Borrow;
 use crate::settings::Preferences;
 use std::sync::{Mutex, Arc};
 use std::time::Instant;
 use wgpu::util::{DeviceExt, BufferInitDescriptor};
-use crate::dataset::BackChannel;
+use crate::dataset::{BackChannel, Individual, LineageRecord};
 use std::ops::{Range, RangeBounds, Bound};
+use crate::statistics::EnergyLedger;
+use crate::terrain::Terrain;
 
 /** Creates a new flipbook dataset channel, creating all the required backing
  * storage and binding descriptors, modeled and initialized after the parameters
@@ -28,7 +30,7 @@ use std::ops::{Range, RangeBounds, Bound};
  * previously established property that snapshots will always contain the most
  * recently produced copy of the dataset.
  */
-pub fn channel(state: Arc<State>, prefs: &Preferences) -> (Producer, Consumer) {
+pub fn channel(state: Arc<State>, prefs: &Preferences, terrain: &Terrain) -> (Producer, Consumer) {
 
 	let device = state.device();
 	let layout = device.create_bind_group_layout(
@@ -46,29 +48,29 @@ pub fn channel(state: Arc<State>, prefs: &Preferences) -> (Producer, Consumer) {
 					},
 					count: None
 				},
-				/* Herbivore simulation planes. */
+				/* Individual simulation planes. */
 				BindGroupLayoutEntry {
 					binding: 1,
 					visibility: ShaderStage::COMPUTE | ShaderStage::VERTEX,
 					ty: BindingType::StorageTexture {
-						dimension: TextureViewDimension::D2Array,
+						dimension: TextureViewDimension::D3,
 						format: TextureFormat::Rgba32Float,
 						readonly: false
 					},
 					count: None
 				},
-				/* Predator simulation planes. */
+				/* Population of all species. */
 				BindGroupLayoutEntry {
 					binding: 2,
 					visibility: ShaderStage::COMPUTE | ShaderStage::VERTEX,
-					ty: BindingType::StorageTexture {
-						dimension: TextureViewDimension::D2Array,
-						format: TextureFormat::Rgba32Float,
+					ty: BindingType::StorageBuffer {
+						dynamic: false,
+						min_binding_size: None,
 						readonly: false
 					},
 					count: None
 				},
-				/* Herbivore group. */
+				/* Back channel. */
 				BindGroupLayoutEntry {
 					binding: 3,
 					visibility: ShaderStage::COMPUTE | ShaderStage::VERTEX,
@@ -79,21 +81,54 @@ pub fn channel(state: Arc<State>, prefs: &Preferences) -> (Producer, Consumer) {
 					},
 					count: None
 				},
-				/* Predator group. */
+				/* Terrain. */
 				BindGroupLayoutEntry {
 					binding: 4,
 					visibility: ShaderStage::COMPUTE | ShaderStage::VERTEX,
-					ty: BindingType::StorageBuffer {
-						dynamic: false,
-						min_binding_size: None,
-						readonly: false
+					ty: BindingType::StorageTexture {
+						dimension: TextureViewDimension::D2,
+						format: TextureFormat::Rgba32Float,
+						readonly: true
 					},
 					count: None
 				},
-				/* Back channel. */
+				/* Scratch plane. */
 				BindGroupLayoutEntry {
 					binding: 5,
+					visibility: ShaderStage::COMPUTE,
+					ty: BindingType::StorageTexture {
+						dimension: TextureViewDimension::D2,
+						format: TextureFormat::Rgba32Float,
+						readonly: false
+					},
+					count: None
+				},
+				/* Regrowth timers. */
+				BindGroupLayoutEntry {
+					binding: 6,
+					visibility: ShaderStage::COMPUTE,
+					ty: BindingType::StorageTexture {
+						dimension: TextureViewDimension::D2,
+						format: TextureFormat::Rgba32Float,
+						readonly: false
+					},
+					count: None
+				},
+				/* Temperature. */
+				BindGroupLayoutEntry {
+					binding: 7,
 					visibility: ShaderStage::COMPUTE | ShaderStage::VERTEX,
+					ty: BindingType::StorageTexture {
+						dimension: TextureViewDimension::D2,
+						format: TextureFormat::R32Float,
+						readonly: true
+					},
+					count: None
+				},
+				/* Lineage. */
+				BindGroupLayoutEntry {
+					binding: 8,
+					visibility: ShaderStage::COMPUTE,
 					ty: BindingType::StorageBuffer {
 						dynamic: false,
 						min_binding_size: None,
@@ -104,12 +139,173 @@ pub fn channel(state: Arc<State>, prefs: &Preferences) -> (Producer, Consumer) {
 			]
 		});
 
+	/* Grass starts off the same way in all of the bundles. */
+	let grass = terrain.seed_grass(&prefs.simulation.regrowth);
+
+	/* The temperature of every cell only ever changes following its schedule,
+	 * which the shaders apply on top of it, so it is shared by all of the
+	 * bundles. */
+	let temperature = {
+		let texture = device.create_texture(
+			&TextureDescriptor {
+				label: Some("Flipbook/Dataset/TemperatureTexture"),
+				size: Extent3d {
+					width: terrain.width(),
+					height: terrain.height(),
+					depth: 1
+				},
+				mip_level_count: 1,
+				sample_count: 1,
+				dimension: TextureDimension::D2,
+				format: TextureFormat::R32Float,
+				usage: TextureUsage::STORAGE | TextureUsage::COPY_DST
+			});
+		state.queue().write_texture(
+			TextureCopyView {
+				texture: &texture,
+				mip_level: 0,
+				origin: Origin3d::ZERO
+			},
+			&terrain.temperature_texels()[..],
+			TextureDataLayout {
+				offset: 0,
+				bytes_per_row: 4 * terrain.width(),
+				rows_per_image: terrain.height()
+			},
+			Extent3d {
+				width: terrain.width(),
+				height: terrain.height(),
+				depth: 1
+			});
+		let view = texture.create_view(
+			&TextureViewDescriptor {
+				label: Some("Flipbook/Dataset/TemperatureTextureView"),
+				format: Some(TextureFormat::R32Float),
+				dimension: Some(TextureViewDimension::D2),
+				aspect: TextureAspect::All,
+				base_mip_level: 0,
+				level_count: None,
+				base_array_layer: 0,
+				array_layer_count: None
+			});
+
+		(texture, view)
+	};
+
+	/* The terrain never changes, so it is shared by all of the bundles. */
+	let terrain = {
+		let texture = device.create_texture(
+			&TextureDescriptor {
+				label: Some("Flipbook/Dataset/TerrainTexture"),
+				size: Extent3d {
+					width: terrain.width(),
+					height: terrain.height(),
+					depth: 1
+				},
+				mip_level_count: 1,
+				sample_count: 1,
+				dimension: TextureDimension::D2,
+				format: TextureFormat::Rgba32Float,
+				usage: TextureUsage::STORAGE | TextureUsage::COPY_DST
+			});
+		state.queue().write_texture(
+			TextureCopyView {
+				texture: &texture,
+				mip_level: 0,
+				origin: Origin3d::ZERO
+			},
+			&terrain.texels()[..],
+			TextureDataLayout {
+				offset: 0,
+				bytes_per_row: 16 * terrain.width(),
+				rows_per_image: terrain.height()
+			},
+			Extent3d {
+				width: terrain.width(),
+				height: terrain.height(),
+				depth: 1
+			});
+		let view = texture.create_view(
+			&TextureViewDescriptor {
+				label: Some("Flipbook/Dataset/TerrainTextureView"),
+				format: Some(TextureFormat::Rgba32Float),
+				dimension: Some(TextureViewDimension::D2),
+				aspect: TextureAspect::All,
+				base_mip_level: 0,
+				level_count: None,
+				base_array_layer: 0,
+				array_layer_count: None
+			});
+
+		(texture, view)
+	};
+
+	/* Intermediate results never outlive an iteration, so the scratch plane
+	 * is shared by all of the bundles, as well. */
+	let scratch = {
+		let texture = device.create_texture(
+			&TextureDescriptor {
+				label: Some("Flipbook/Dataset/ScratchTexture"),
+				size: Extent3d {
+					width: prefs.simulation.horizontal_granularity,
+					height: prefs.simulation.vertical_granularity,
+					depth: 1
+				},
+				mip_level_count: 1,
+				sample_count: 1,
+				dimension: TextureDimension::D2,
+				format: TextureFormat::Rgba32Float,
+				usage: TextureUsage::STORAGE
+			});
+		let view = texture.create_view(
+			&TextureViewDescriptor {
+				label: Some("Flipbook/Dataset/ScratchTextureView"),
+				format: Some(TextureFormat::Rgba32Float),
+				dimension: Some(TextureViewDimension::D2),
+				aspect: TextureAspect::All,
+				base_mip_level: 0,
+				level_count: None,
+				base_array_layer: 0,
+				array_layer_count: None
+			});
+
+		(texture, view)
+	};
+
+	/* Births and deaths are read back and cleared after every iteration, so
+	 * the lineage buffer is shared by all of the bundles, too. Every individual
+	 * may at most be born, give birth to an individual taking the place of
+	 * another one and die over a single iteration, and records are only ever
+	 * written when there is a lineage log. */
+	let lineage = {
+		let budget = slices(prefs).last().map(|slice| slice.end).unwrap_or(0);
+		let capacity = if prefs.simulation.lineage_log.is_some() {
+			3 * budget as usize
+		} else {
+			1
+		};
+
+		device.create_buffer_init(
+			&BufferInitDescriptor {
+				label: Some("Flipbook/Dataset/LineageBuffer"),
+				contents: &vec![0; LineageRecord::RECORDS_OFFSET
+					+ capacity * LineageRecord::BYTE_SIZE][..],
+				usage: BufferUsage::STORAGE | BufferUsage::MAP_READ
+					| BufferUsage::COPY_DST
+			})
+	};
+
 	let bundles = {
 		let mut iter = BundleFactory::new(
 			device,
 			state.queue(),
 			&layout,
-			prefs);
+			&terrain.1,
+			&temperature.1,
+			&scratch.1,
+			&lineage,
+			prefs,
+			&grass);
 		[
 			iter.next().unwrap(),
 			iter.next().unwrap(),
@@ -131,7 +327,11 @@ pub fn channel(state: Arc<State>, prefs: &Preferences) -> (Producer, Consumer) {
 		state,
 		bundles,
 		index,
-		layout
+		layout,
+		terrain,
+		temperature,
+		scratch,
+		lineage
 	});
 
 	(
@@ -151,61 +351,66 @@ struct Index {
 /** Flipbook storage bundle. */
 #[derive(Debug)]
 struct Bundle {
-	/** Handle to the underlying herbivore storage buffer. Along with the number
+	/** Handle to the underlying population storage buffer, holding the
+	 * individuals of all species, one after the other. Along with the number
 	 * of individuals allocated and the size of the data, in bytes. */
-	herbivores: (Buffer, u32, u64),
-	/** Handle to the underlying predator storage buffer. Along with the number
-	 * of individuals allocated and the size of the data, in bytes. */
-	predators: (Buffer, u32, u64),
+	population: (Buffer, u32, u64),
+	/** Slice of the population buffer owned by every species, in the order
+	 * the species were declared in. */
+	slices: Vec<Range<u32>>,
 	/** Handle to the host back-channeling buffer. Along with the size of the
 	 * data, in bytes. */
 	back_channel: (Buffer, u64),
 	/** Handle to the simulation plane storage. */
 	plane: (Texture, u32, u32),
-	/** Handle to the herbivore simulation planes. */
-	herbivore_planes: (Texture, u32, u32, u32),
-	/** Handle to the herbivore simulation planes. */
-	predator_planes: (Texture, u32, u32, u32),
+	/** Handle to the individual simulation planes, with one layer for every
+	 * individual in the population buffer. */
+	planes: (Texture, u32, u32, u32),
+	/** Handle to the regrowth timers of the simulation plane, which is just
+	 * as big as the plane itself. */
+	regrowth: Texture,
 	/** Bind group for the resources in this bundle. */
 	bind: BindGroup
 }
 impl Bundle {
 	/** Create a new bundle on the given device with the given preferences and
 	 * the given initial buffer data. */
-	fn new_with_populations<A, B>(
+	fn new_with_population<A>(
 		device: &Device,
 		queue:  &Queue,
 		layout: &BindGroupLayout,
+		terrain: &TextureView,
+		temperature: &TextureView,
+		scratch: &TextureView,
+		lineage: &Buffer,
 		prefs: &Preferences,
-		herbivores: A,
-		predators: B) -> Self
-		where A: AsRef<[u8]>,
-			  B: AsRef<[u8]> {
-
+		grass: &[f32],
+		population: A) -> Self
+		where A: AsRef<[u8]> {
 
-		let herbivores_len = herbivores.as_ref().len();
-		let predators_len  = herbivores.as_ref().len();
-
-		let herbivores = device.create_buffer_init(
-			&BufferInitDescriptor {
-				label: Some("Flipbook/Dataset/HerbivoreBuffer"),
-				contents: &herbivores.as_ref()[..],
-				usage: BufferUsage::STORAGE | BufferUsage::COPY_SRC
-					| BufferUsage::COPY_DST
-			});
+		let slices = slices(prefs);
+		let budget = slices.last().map(|slice| slice.end).unwrap_or(0);
 
-		let predators = device.create_buffer_init(
+		let population_len = population.as_ref().len();
+		let population = device.create_buffer_init(
 			&BufferInitDescriptor {
-				label: Some("Flipbook/Dataset/PredatorBuffer"),
-				contents: &predators.as_ref()[..],
+				label: Some("Flipbook/Dataset/PopulationBuffer"),
+				contents: &population.as_ref()[..],
 				usage: BufferUsage::STORAGE | BufferUsage::COPY_SRC
 					| BufferUsage::COPY_DST
+					| BufferUsage::MAP_READ
 			});
 
-		let mut back_channel_buf = Vec::with_capacity(16);
+		let mut back_channel_buf = Vec::with_capacity(
+			BackChannel::byte_size(slices.len()));
 		let back_channel = BackChannel {
-			herbivores: 0..prefs.simulation.herbivores.individuals,
-			predators: 0..prefs.simulation.predators.individuals
+			ledger: Default::default(),
+			ranges: slices.iter()
+				.zip(&prefs.simulation.species)
+				.map(|(slice, group)| slice.start..slice.start + group.individuals)
+				.collect(),
+			rejected: vec![0; slices.len()],
+			replaced: vec![0; slices.len()]
 		};
 		let back_channel_len = back_channel.bytes(&mut back_channel_buf);
 
@@ -245,13 +450,13 @@ impl Bundle {
 				array_layer_count: None
 			});
 
-		let herbivore_planes = device.create_texture(
+		let planes = device.create_texture(
 			&TextureDescriptor {
-				label: Some("Flipbook/Dataset/HerbivoresPlanes"),
+				label: Some("Flipbook/Dataset/IndividualPlanes"),
 				size: Extent3d {
 					width: prefs.simulation.horizontal_granularity,
 					height: prefs.simulation.vertical_granularity,
-					depth: prefs.simulation.herbivores.budget
+					depth: budget
 				},
 				mip_level_count: 1,
 				sample_count: 1,
@@ -260,9 +465,9 @@ impl Bundle {
 				usage: TextureUsage::STORAGE | TextureUsage::COPY_SRC
 					| TextureUsage::COPY_DST
 			});
-		let herbivore_planes_view = herbivore_planes.create_view(
+		let planes_view = planes.create_view(
 			&TextureViewDescriptor {
-				label: Some("Flipbook/Dataset/HerbivorePlanesView"),
+				label: Some("Flipbook/Dataset/IndividualPlanesView"),
 				format: Some(TextureFormat::Rgba32Float),
 				dimension: Some(TextureViewDimension::D3),
 				aspect: TextureAspect::All,
@@ -272,26 +477,26 @@ impl Bundle {
 				array_layer_count: None
 			});
 
-		let predator_planes = device.create_texture(
+		let regrowth = device.create_texture(
 			&TextureDescriptor {
-				label: Some("Flipbook/Dataset/PredatorPlanes"),
+				label: Some("Flipbook/Dataset/RegrowthTexture"),
 				size: Extent3d {
 					width: prefs.simulation.horizontal_granularity,
 					height: prefs.simulation.vertical_granularity,
-					depth: prefs.simulation.predators.budget
+					depth: 1
 				},
 				mip_level_count: 1,
 				sample_count: 1,
-				dimension: TextureDimension::D3,
+				dimension: TextureDimension::D2,
 				format: TextureFormat::Rgba32Float,
 				usage: TextureUsage::STORAGE | TextureUsage::COPY_SRC
 					| TextureUsage::COPY_DST
 			});
-		let predator_planes_view = predator_planes.create_view(
+		let regrowth_view = regrowth.create_view(
 			&TextureViewDescriptor {
-				label: Some("Flipbook/Dataset/PredatorPlanesView"),
+				label: Some("Flipbook/Dataset/RegrowthTextureView"),
 				format: Some(TextureFormat::Rgba32Float),
-				dimension: Some(TextureViewDimension::D3),
+				dimension: Some(TextureViewDimension::D2),
 				aspect: TextureAspect::All,
 				base_mip_level: 0,
 				level_count: None,
@@ -313,7 +518,32 @@ impl Bundle {
 				source[3] = w;
 			});
 
-		/* Clear the main plane texture. */
+		/* Clear the regrowth timers, so grass may grow right away. */
+		queue.write_texture(
+			TextureCopyView {
+				texture: &regrowth,
+				mip_level: 0,
+				origin: Origin3d { x: 0, y: 0, z: 0 }
+			},
+			&clear[..],
+			TextureDataLayout {
+				offset: 0,
+				bytes_per_row: 16 * prefs.simulation.horizontal_granularity,
+				rows_per_image: prefs.simulation.vertical_granularity
+			},
+			Extent3d {
+				width: prefs.simulation.horizontal_granularity,
+				height: prefs.simulation.vertical_granularity,
+				depth: 1
+			});
+
+		/* Clear the main plane texture, save for the grass it starts off
+		 * with. */
+		clear.chunks_exact_mut(16)
+			.zip(grass)
+			.for_each(|(texel, grass)| {
+				texel[12..].copy_from_slice(&grass.to_ne_bytes());
+			});
 		queue.write_texture(
 			TextureCopyView {
 				texture: &plane,
@@ -343,38 +573,46 @@ impl Bundle {
 					},
 					BindGroupEntry {
 						binding: 1,
-						resource: BindingResource::TextureView(&herbivore_planes_view)
+						resource: BindingResource::TextureView(&planes_view)
 					},
 					BindGroupEntry {
 						binding: 2,
-						resource: BindingResource::TextureView(&predator_planes_view)
+						resource: BindingResource::Buffer(population.slice(..))
 					},
 					BindGroupEntry {
 						binding: 3,
-						resource: BindingResource::Buffer(herbivores.slice(..))
+						resource: BindingResource::Buffer(back_channel.slice(..))
 					},
 					BindGroupEntry {
 						binding: 4,
-						resource: BindingResource::Buffer(predators.slice(..))
+						resource: BindingResource::TextureView(terrain)
 					},
 					BindGroupEntry {
 						binding: 5,
-						resource: BindingResource::Buffer(back_channel.slice(..))
+						resource: BindingResource::TextureView(scratch)
+					},
+					BindGroupEntry {
+						binding: 6,
+						resource: BindingResource::TextureView(&regrowth_view)
+					},
+					BindGroupEntry {
+						binding: 7,
+						resource: BindingResource::TextureView(temperature)
+					},
+					BindGroupEntry {
+						binding: 8,
+						resource: BindingResource::Buffer(lineage.slice(..))
 					}
 				]
 			});
 
 		Self {
-			herbivores: (
-				herbivores,
-				prefs.simulation.herbivores.budget,
-				herbivores_len as u64
-			),
-			predators: (
-				predators,
-				prefs.simulation.predators.budget,
-				predators_len as u64
+			population: (
+				population,
+				budget,
+				population_len as u64
 			),
+			slices,
 			back_channel: (
 				back_channel,
 				back_channel_len as u64
@@ -384,18 +622,13 @@ impl Bundle {
 				prefs.simulation.horizontal_granularity,
 				prefs.simulation.vertical_granularity
 			),
-			herbivore_planes: (
-				herbivore_planes,
-				prefs.simulation.horizontal_granularity,
-				prefs.simulation.vertical_granularity,
-				prefs.simulation.herbivores.budget
-			),
-			predator_planes: (
-				predator_planes,
+			planes: (
+				planes,
 				prefs.simulation.horizontal_granularity,
 				prefs.simulation.vertical_granularity,
-				prefs.simulation.predators.budget
+				budget
 			),
+			regrowth,
 			bind,
 		}
 	}
@@ -409,13 +642,40 @@ impl Bundle {
 				.expect("could not map back channel for reading");
 			let mapped = slice.get_mapped_range();
 
-			BackChannel::from_bytes(&*mapped)
+			BackChannel::from_bytes(&*mapped, self.slices.len())
 		};
 
 		self.back_channel.0.unmap();
 		channel
 	}
 
+	/** Zero out the energy ledger in the back channel buffer. */
+	pub fn clear_ledger(&self, queue: &Queue) {
+		let zero = [0_u8; EnergyLedger::ENTRIES * 8];
+		queue.write_buffer(
+			&self.back_channel.0,
+			BackChannel::LEDGER_OFFSET as u64,
+			&zero[..]);
+	}
+
+	/** Read the individuals in the given range back from the population
+	 * storage buffer. */
+	pub async fn read_population(&self, range: Range<u32>) -> Vec<Individual> {
+		let buffer = &self.population.0;
+		let population = {
+			let slice = buffer.slice(..);
+			slice.map_async(MapMode::Read)
+				.await
+				.expect("could not map population buffer for reading");
+			let mapped = slice.get_mapped_range();
+
+			crate::dataset::population_from_bytes(&*mapped, range)
+		};
+
+		buffer.unmap();
+		population
+	}
+
 	/** Write to the given data to the back channel buffer. */
 	pub async fn write_back_channel(&self, data: BackChannel) -> usize {
 		let written = {
@@ -426,7 +686,8 @@ impl Bundle {
 				.expect("could not map back channel for writing");
 			let mut mapped = slice.get_mapped_range_mut();
 
-			let mut buf = Vec::with_capacity(16);
+			let mut buf = Vec::with_capacity(
+				BackChannel::byte_size(data.ranges.len()));
 			data.bytes(&mut buf);
 
 			let target = &mut *mapped;
@@ -440,15 +701,34 @@ impl Bundle {
 	}
 }
 
+/** Slices of the population buffer owned by every species in the given
+ * preferences, in the order the species were declared in. Every species owns
+ * as many individuals as its budget. */
+fn slices(prefs: &Preferences) -> Vec<Range<u32>> {
+	prefs.simulation.species
+		.iter()
+		.scan(0, |base, group| {
+			let slice = *base..*base + group.budget;
+			*base = slice.end;
+
+			Some(slice)
+		})
+		.collect()
+}
+
 /** An iterator that yields any number of identical bundles. */
 struct BundleFactory<'a> {
 	device: &'a Device,
 	queue:  &'a Queue,
 	layout: &'a BindGroupLayout,
+	terrain: &'a TextureView,
+	temperature: &'a TextureView,
+	scratch: &'a TextureView,
+	lineage: &'a Buffer,
 	prefs:  &'a Preferences,
+	grass:  &'a [f32],
 
-	herbivores: Vec<u8>,
-	predators:  Vec<u8>,
+	population: Vec<u8>,
 }
 impl<'a> BundleFactory<'a> {
 	/** Create a new iterator with the given parameters. */
@@ -456,29 +736,42 @@ impl<'a> BundleFactory<'a> {
 		device: &'a Device,
 		queue:  &'a Queue,
 		layout: &'a BindGroupLayout,
-		prefs: &'a Preferences) -> Self {
+		terrain: &'a TextureView,
+		temperature: &'a TextureView,
+		scratch: &'a TextureView,
+		lineage: &'a Buffer,
+		prefs: &'a Preferences,
+		grass: &'a [f32]) -> Self {
 
 		use crate::dataset;
 		Self {
 			device,
 			queue,
 			layout,
+			terrain,
+			temperature,
+			scratch,
+			lineage,
 			prefs,
-			herbivores: dataset::population_bytes(&prefs.simulation.herbivores),
-			predators:  dataset::population_bytes(&prefs.simulation.predators),
+			grass,
+			population: dataset::populations_bytes(&prefs.simulation),
 		}
 	}
 }
 impl<'a> Iterator for BundleFactory<'a> {
 	type Item = Bundle;
 	fn next(&mut self) -> Option<Self::Item> {
-		Some(Bundle::new_with_populations(
+		Some(Bundle::new_with_population(
 				self.device,
 				self.queue,
 				self.layout,
+				self.terrain,
+				self.temperature,
+				self.scratch,
+				self.lineage,
 				self.prefs,
-				&self.herbivores,
-				&self.predators))
+				self.grass,
+				&self.population))
 	}
 }
 
@@ -492,6 +785,14 @@ struct Flipbook {
 	index: Mutex<Index>,
 	/** Layout for the binding groups of the bundles. */
 	layout: BindGroupLayout,
+	/** Terrain texture shared by all bundles, along with its view. */
+	terrain: (Texture, TextureView),
+	/** Temperature texture shared by all bundles, along with its view. */
+	temperature: (Texture, TextureView),
+	/** Scratch plane texture shared by all bundles, along with its view. */
+	scratch: (Texture, TextureView),
+	/** Lineage buffer shared by all bundles. */
+	lineage: Buffer,
 }
 impl Flipbook {
 	/** Copies the data from the bundle at the first index to the bundle at the
@@ -519,33 +820,18 @@ impl Flipbook {
 		let j = target;
 
 		encoder.copy_buffer_to_buffer(
-			&bundles[i].herbivores.0,
-			0,
-			&bundles[j].herbivores.0,
-			0,
-			if bundles[i].herbivores.2 != bundles[j].herbivores.2 {
-				panic!("both herbivore bundles must have been the same \
-						size, but, instead, we got: bundles[{}].herbivores.2 \
-						({}) != bundles[{}].herbivores.2 ({})",
-					i, bundles[i].herbivores.2,
-					j, bundles[j].herbivores.2)
-			} else {
-				bundles[i].herbivores.2
-			});
-
-		encoder.copy_buffer_to_buffer(
-			&bundles[i].predators.0,
+			&bundles[i].population.0,
 			0,
-			&bundles[j].predators.0,
+			&bundles[j].population.0,
 			0,
-			if bundles[i].predators.2 != bundles[j].predators.2 {
-				panic!("both predator bundles must have been the same \
-						size, but, instead, we got: bundles[{}].predators.2 \
-						({}) != bundles[{}].predators.2 ({})",
-					i, bundles[i].predators.2,
-					j, bundles[j].predators.2)
+			if bundles[i].population.2 != bundles[j].population.2 {
+				panic!("both population bundles must have been the same \
+						size, but, instead, we got: bundles[{}].population.2 \
+						({}) != bundles[{}].population.2 ({})",
+					i, bundles[i].population.2,
+					j, bundles[j].population.2)
 			} else {
-				bundles[i].predators.2
+				bundles[i].population.2
 			});
 
 		encoder.copy_buffer_to_buffer(
@@ -600,90 +886,65 @@ impl Flipbook {
 
 		encoder.copy_texture_to_texture(
 			TextureCopyView {
-				texture: &bundles[i].herbivore_planes.0,
+				texture: &bundles[i].planes.0,
 				mip_level: 0,
 				origin: Origin3d::ZERO
 			},
 			TextureCopyView {
-				texture: &bundles[j].herbivore_planes.0,
+				texture: &bundles[j].planes.0,
 				mip_level: 0,
 				origin: Origin3d::ZERO
 			},
 			Extent3d {
-				width: if bundles[i].herbivore_planes.1 != bundles[j].herbivore_planes.1 {
-					panic!("both herbivore plane bundles must have been \
+				width: if bundles[i].planes.1 != bundles[j].planes.1 {
+					panic!("both individual plane bundles must have been \
 							the same width, but, instead, we got: \
-							bundles[{}].herbivore_planes.1 ({}) != \
-							bundles[{}].herbivore_planes.1 ({})",
-						i, bundles[i].herbivore_planes.1,
-						j, bundles[j].herbivore_planes.1)
+							bundles[{}].planes.1 ({}) != \
+							bundles[{}].planes.1 ({})",
+						i, bundles[i].planes.1,
+						j, bundles[j].planes.1)
 				} else {
-					bundles[i].herbivore_planes.1
+					bundles[i].planes.1
 				},
-				height: if bundles[i].herbivore_planes.2 != bundles[j].herbivore_planes.2 {
-					panic!("both herbivore plane bundles must have been \
+				height: if bundles[i].planes.2 != bundles[j].planes.2 {
+					panic!("both individual plane bundles must have been \
 							the same height, but, instead, we got: \
-							bundles[{}].herbivore_planes.2 ({}) != \
-							bundles[{}].herbivore_planes.2 ({})",
-						i, bundles[i].herbivore_planes.2,
-						j, bundles[j].herbivore_planes.2)
+							bundles[{}].planes.2 ({}) != \
+							bundles[{}].planes.2 ({})",
+						i, bundles[i].planes.2,
+						j, bundles[j].planes.2)
 				} else {
-					bundles[i].herbivore_planes.2
+					bundles[i].planes.2
 				},
-				depth: if bundles[i].herbivore_planes.3 != bundles[j].herbivore_planes.3 {
-					panic!("both herbivore plane bundles must have been \
+				depth: if bundles[i].planes.3 != bundles[j].planes.3 {
+					panic!("both individual plane bundles must have been \
 							the same depth, but, instead, we got: \
-							bundles[{}].herbivore_planes.3 ({}) != \
-							bundles[{}].herbivore_planes.3 ({})",
-						i, bundles[i].herbivore_planes.3,
-						j, bundles[j].herbivore_planes.3)
+							bundles[{}].planes.3 ({}) != \
+							bundles[{}].planes.3 ({})",
+						i, bundles[i].planes.3,
+						j, bundles[j].planes.3)
 				} else {
-					bundles[i].herbivore_planes.3
+					bundles[i].planes.3
 				}
 			});
 
+		/* The regrowth timers are always as big as the plane, whose size
+		 * was already checked above. */
 		encoder.copy_texture_to_texture(
 			TextureCopyView {
-				texture: &bundles[i].predator_planes.0,
+				texture: &bundles[i].regrowth,
 				mip_level: 0,
 				origin: Origin3d::ZERO
 			},
 			TextureCopyView {
-				texture: &bundles[j].predator_planes.0,
+				texture: &bundles[j].regrowth,
 				mip_level: 0,
 				origin: Origin3d::ZERO
 			},
 			Extent3d {
-				width: if bundles[i].predator_planes.1 != bundles[j].predator_planes.1 {
-					panic!("both predator plane bundles must have been \
-							the same width, but, instead, we got: \
-							bundles[{}].predator_planes.1 ({}) != \
-							bundles[{}].predator_planes.1 ({})",
-						i, bundles[i].predator_planes.1,
-						j, bundles[j].predator_planes.1)
-				} else {
-					bundles[i].predator_planes.1
-				},
-				height: if bundles[i].predator_planes.2 != bundles[j].predator_planes.2 {
-					panic!("both predator plane bundles must have been \
-							the same height, but, instead, we got: \
-							bundles[{}].predator_planes.2 ({}) != \
-							bundles[{}].predator_planes.2 ({})",
-						i, bundles[i].predator_planes.2,
-						j, bundles[j].predator_planes.2)
-				} else {
-					bundles[i].predator_planes.2
-				},
-				depth: if bundles[i].predator_planes.3 != bundles[j].predator_planes.3 {
-					panic!("both predator plane bundles must have been \
-							the same depth, but, instead, we got: \
-							bundles[{}].predator_planes.3 ({}) != \
-							bundles[{}].predator_planes.3 ({})",
-						i, bundles[i].predator_planes.3,
-						j, bundles[j].predator_planes.3)
-				} else {
-					bundles[i].predator_planes.3
-				}
+				width: bundles[i].plane.1,
+				height: bundles[i].plane.2,
+				depth: 1
 			});
 
 		self.state.queue()
@@ -738,16 +999,10 @@ impl Consumer {
 		&self.book.layout
 	}
 
-	/** The budget for individuals in the herbivore group in each of the
-	 * snapshots obtained from this consumer. */
-	pub fn herbivore_budget(&self) -> u32 {
-		self.book.bundles[0].herbivores.1
-	}
-
-	/** The budget for individuals in the predator group in each of the
-	 * snapshots obtained from this consumer. */
-	pub fn predator_budget(&self) -> u32 {
-		self.book.bundles[0].predators.1
+	/** The slice of the population buffer owned by every species in each of
+	 * the snapshots obtained from this consumer. */
+	pub fn slices(&self) -> &[Range<u32>] {
+		&self.book.bundles[0].slices
 	}
 }
 
@@ -781,20 +1036,13 @@ impl<'a> Snapshot<'a> {
 		self.data().plane.2
 	}
 
-	/** Range of individuals currently alive in the herbivore group. */
-	pub async fn herbivores(&self) -> Range<u32> {
+	/** Range of individuals currently alive in every species, as indices into
+	 * the whole population buffer. */
+	pub async fn ranges(&self) -> Vec<Range<u32>> {
 		self.data()
 			.read_back_channel()
 			.await
-			.herbivores
-	}
-
-	/** Range of individuals currently alive in the predator group. */
-	pub async fn predators(&self) -> Range<u32> {
-		self.data()
-			.read_back_channel()
-			.await
-			.predators
+			.ranges
 	}
 }
 
@@ -834,16 +1082,10 @@ impl Producer {
 		&self.book.layout
 	}
 
-	/** The budget for individuals in the herbivore group in each of the frames
-	 * obtained from this producer. */
-	pub fn herbivore_budget(&self) -> u32 {
-		self.book.bundles[0].herbivores.1
-	}
-
-	/** The budget for individuals in the predator group in each of the frames
-	 * obtained from this producer. */
-	pub fn predator_budget(&self) -> u32 {
-		self.book.bundles[0].predators.1
+	/** The slice of the population buffer owned by every species in each of
+	 * the frames obtained from this producer. */
+	pub fn slices(&self) -> &[Range<u32>] {
+		&self.book.bundles[0].slices
 	}
 }
 
@@ -877,75 +1119,157 @@ impl<'a> Frame<'a> {
 	}
 
 	/** Read the back current back channel. */
-	async fn back_channel(&self) -> BackChannel {
+	pub async fn back_channel(&self) -> BackChannel {
 		self.data()
 			.read_back_channel()
 			.await
 	}
 
-	/** Range of individuals currently alive in the herbivore group. */
-	pub async fn herbivores(&self) -> Range<u32> {
+	/** Total number of individuals in the population buffer, across all
+	 * species. */
+	pub fn budget(&self) -> u32 {
+		self.data().population.1
+	}
+
+	/** Range of individuals currently alive in every species, as indices into
+	 * the whole population buffer. */
+	pub async fn ranges(&self) -> Vec<Range<u32>> {
 		self.back_channel()
 			.await
-			.herbivores
+			.ranges
 	}
 
-	/** Range of individuals currently alive in the predator group. */
-	pub async fn predators(&self) -> Range<u32> {
+	/** Energy ledger of the last iteration that ran on this frame. */
+	pub async fn ledger(&self) -> EnergyLedger {
 		self.back_channel()
 			.await
-			.predators
+			.ledger
 	}
 
-	/** Set the range of individuals currently alive in the herbivore group.
-	 *
-	 * # Panics
-	 * This function will panic if the given bounds fall outside the budget
-	 * range of the herbivores or if the lower bound is greater than the upper
-	 * bound. */
-	pub async fn set_herbivores<R>(&mut self, range: R)
-		where R: RangeBounds<u32> {
+	/** Zero out the energy ledger, to be filled in by the next iteration. */
+	pub fn clear_ledger(&self) {
+		self.data().clear_ledger(self.root.book.state.queue())
+	}
 
-		let budget = self.data().herbivores.1;
-		let lower = match range.start_bound() {
-			Bound::Unbounded => 0,
-			Bound::Excluded(val) => val.saturating_add(1),
-			Bound::Included(val) => *val
-		};
-		let upper = match range.end_bound() {
-			Bound::Unbounded => budget,
-			Bound::Excluded(val) => *val,
-			Bound::Included(val) => val.saturating_add(1)
+	/** Number of individuals born during the last iteration, along with the
+	 * number of births and deaths recorded over it and the records that fit in
+	 * the lineage buffer, as described in `Lineage.glslh`. */
+	pub async fn lineage(&self) -> (u32, u32, Vec<LineageRecord>) {
+		let buffer = &self.root.book.lineage;
+		let lineage = {
+			let slice = buffer.slice(..);
+			slice.map_async(MapMode::Read)
+				.await
+				.expect("could not map lineage buffer for reading");
+			let mapped = slice.get_mapped_range();
+
+			crate::dataset::lineage_from_bytes(&*mapped)
 		};
 
-		if lower > upper {
-			panic!("lower bound > upper bound: {} > {}", lower, upper);
+		buffer.unmap();
+		lineage
+	}
+
+	/** Zero out the births and the records of the lineage buffer, to be
+	 * filled in by the next iteration. */
+	pub fn clear_lineage(&self) {
+		self.root.book.state.queue().write_buffer(
+			&self.root.book.lineage,
+			0,
+			&[0; LineageRecord::RECORDS_OFFSET][..]);
+	}
+
+	/** Copy of the individuals currently alive in every species. */
+	pub async fn populations(&self) -> Vec<Vec<Individual>> {
+		let mut populations = Vec::new();
+		for range in self.ranges().await {
+			populations.push(self.data().read_population(range).await);
 		}
-		if upper > budget {
-			panic!("upper bound > budget: {} > {}", upper, budget);
+
+		populations
+	}
+
+	/** Replace the individuals currently alive in the given species with the
+	 * given ones, packed at the start of the slice owned by the species.
+	 * Individuals past the budget of the species are left out. */
+	pub async fn write_population(&mut self, species: usize, population: &[Individual]) {
+		let slice = self.data().slices[species].clone();
+		let count = population.len().min((slice.end - slice.start) as usize);
+
+		let mut data = Vec::with_capacity(count * Individual::BYTE_SIZE);
+		for individual in &population[..count] {
+			individual.bytes(&mut data);
 		}
-		if lower > budget {
-			panic!("lower bound > budget: {} > {}", lower, budget);
+		self.root.book.state.queue().write_buffer(
+			&self.data().population.0,
+			(slice.start as usize * Individual::BYTE_SIZE) as u64,
+			&data[..]);
+
+		self.set_range(species, 0..count as u32).await;
+	}
+
+	/** Pack the individuals currently alive in the given species at the start
+	 * of the slice owned by the species, if they have drifted all the way up
+	 * to its end. Deaths free up room at the start of the range of a species,
+	 * while births take it up at the end, so a species may otherwise be found
+	 * at its budget with fewer individuals than it allows for. Returns whether
+	 * the individuals were moved. */
+	pub async fn compact_population(&mut self, species: usize) -> bool {
+		let slice = self.data().slices[species].clone();
+		let range = self.ranges().await[species].clone();
+		if range.end < slice.end || range.start == slice.start {
+			return false
 		}
 
-		let mut back_channel = self.back_channel().await;
-		back_channel.herbivores = lower..upper;
+		let population = self.data().read_population(range).await;
+		self.write_population(species, &population).await;
 
-		self.data()
-			.write_back_channel(back_channel)
-			.await;
+		true
+	}
+
+	/** Add the given individuals to those currently alive in the given
+	 * species, right after them, if there is room for them there, or packing
+	 * all of them at the start of the slice owned by the species, otherwise.
+	 * Individuals past the budget of the species are left out. */
+	pub async fn append_population(&mut self, species: usize, individuals: &[Individual]) {
+		let slice = self.data().slices[species].clone();
+		let range = self.ranges().await[species].clone();
+
+		if ((slice.end - range.end) as usize) < individuals.len() {
+			let mut population = self.data().read_population(range).await;
+			population.extend_from_slice(individuals);
+
+			self.write_population(species, &population).await;
+			return
+		}
+
+		let mut data = Vec::with_capacity(individuals.len() * Individual::BYTE_SIZE);
+		for individual in individuals {
+			individual.bytes(&mut data);
+		}
+		self.root.book.state.queue().write_buffer(
+			&self.data().population.0,
+			(range.end as usize * Individual::BYTE_SIZE) as u64,
+			&data[..]);
+
+		let lower = range.start - slice.start;
+		let upper = range.end - slice.start + individuals.len() as u32;
+		self.set_range(species, lower..upper).await;
 	}
 
-	/** Set the range of individuals currently alive in the predator group.
+	/** Set the range of individuals currently alive in the given species. The
+	 * range is relative to the slice of the population buffer owned by the
+	 * species.
 	 *
 	 * # Panics
-	 * This function will panic if the given bounds fall outside the budget
-	 * range of the predators or if the lower bound is greater than the upper
-	 * bound. */
-	pub async fn set_predators<R>(&mut self, range: R)
+	 * This function will panic if the species does not exist, if the given
+	 * bounds fall outside the budget range of the species or if the lower
+	 * bound is greater than the upper bound. */
+	pub async fn set_range<R>(&mut self, species: usize, range: R)
 		where R: RangeBounds<u32> {
 
-		let budget = self.data().predators.1;
+		let slice = self.data().slices[species].clone();
+		let budget = slice.end - slice.start;
 		let lower = match range.start_bound() {
 			Bound::Unbounded => 0,
 			Bound::Excluded(val) => val.saturating_add(1),
@@ -968,7 +1292,7 @@ impl<'a> Frame<'a> {
 		}
 
 		let mut back_channel = self.back_channel().await;
-		back_channel.predators = lower..upper;
+		back_channel.ranges[species] = slice.start + lower..slice.start + upper;
 
 		self.data()
 			.write_back_channel(back_channel)
@@ -0,0 +1,233 @@
+use std::error::Error;
+use std::fs::File;
+use std::io::{Read, Write};
+use serde::{Serialize, Deserialize};
+use crate::dataset::{Individual, NN_INPUTS, NN_OUTPUTS};
+use crate::settings::Group;
+
+/** Version of the genome file format written and understood by this build.
+ * Files of any other version are rejected. */
+pub const GENOME_FILE_VERSION: u32 = 4;
+
+/** Heritable traits of an individual, as stored in genome files. */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct Genome {
+	/** Chemical composition of the pheromones of the individual, in red,
+	 * green and blue order. */
+	pub signature: [f32; 3],
+	/** Weight matrix of the neural network of the individual, as a row of
+	 * [`NN_INPUTS`] weights for every one of its [`NN_OUTPUTS`] outputs. */
+	pub weights: Vec<Vec<f32>>,
+	/** Output biases of the neural network of the individual. */
+	pub biases: [f32; NN_OUTPUTS],
+}
+impl Genome {
+	/** Creates a newborn individual with this genome at the given position,
+	 * with the given amount of energy. */
+	pub fn individual(&self, position: [f32; 2], energy: f32) -> Individual {
+		let mut weights = [[0.0; NN_INPUTS]; NN_OUTPUTS];
+		for (row, genes) in weights.iter_mut().zip(&self.weights) {
+			row.copy_from_slice(genes);
+		}
+
+		Individual {
+			position,
+			velocity: [0.0, 0.0],
+			energy,
+			age: 0.0,
+			handling: 0.0,
+			cooldown: 0.0,
+			gestation: 0.0,
+			id: 0,
+			parents: [0, 0],
+			generation: 0,
+			birth_step: 0,
+			offspring: 0,
+			gathered: 0.0,
+			signature: self.signature,
+			weights,
+			biases: self.biases
+		}
+	}
+
+	/** Gives the given individual this genome, with every weight and bias
+	 * deviating from it by at most the given amount. */
+	pub fn imprint(&self, individual: &mut Individual, mutation: f32) {
+		let mutate = |gene: f32| if mutation > 0.0 {
+			gene + (crate::random::random::<f32>() * 2.0 - 1.0) * mutation
+		} else {
+			gene
+		};
+
+		individual.signature = self.signature;
+		for (row, genes) in individual.weights.iter_mut().zip(&self.weights) {
+			for (weight, gene) in row.iter_mut().zip(genes) {
+				*weight = mutate(*gene);
+			}
+		}
+		for (bias, gene) in individual.biases.iter_mut().zip(&self.biases) {
+			*bias = mutate(*gene);
+		}
+	}
+}
+impl From<&Individual> for Genome {
+	fn from(individual: &Individual) -> Self {
+		Self {
+			signature: individual.signature,
+			weights: individual.weights.iter()
+				.map(|row| row.to_vec())
+				.collect(),
+			biases: individual.biases
+		}
+	}
+}
+
+/** Contents of a genome file, a TOML file listing any number of genomes. */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct GenomeFile {
+	/** Version of the format of the file. */
+	pub version: u32,
+	/** Genomes in the file. */
+	pub genomes: Vec<Genome>,
+}
+impl GenomeFile {
+	/** Genome file of the current version holding the given genomes. */
+	pub fn new(genomes: Vec<Genome>) -> Self {
+		Self {
+			version: GENOME_FILE_VERSION,
+			genomes
+		}
+	}
+
+	/** Writes this file out to the given path, replacing whatever was
+	 * there. */
+	pub fn save(&self, path: &str) -> Result<(), Box<dyn Error>> {
+		let data = toml::to_string(self)
+			.map_err(|what| format!("could not serialize genome file {}: {}", path, what))?;
+		File::create(path)
+			.map_err(|what| format!("could not create genome file {}: {}", path, what))?
+			.write_all(data.as_bytes())?;
+
+		Ok(())
+	}
+
+	/** Loads the genomes in the file at the given path. */
+	pub fn load(path: &str) -> Result<Self, Box<dyn Error>> {
+		let mut data = String::new();
+		File::open(path)
+			.map_err(|what| format!("could not open genome file {}: {}", path, what))?
+			.read_to_string(&mut data)?;
+
+		let file: Self = toml::from_str(&data)
+			.map_err(|what| format!("could not parse genome file {}: {}", path, what))?;
+		if file.version != GENOME_FILE_VERSION {
+			return Err(format!("genome file {} is of version {}, but only \
+				version {} is supported", path, file.version, GENOME_FILE_VERSION).into())
+		}
+		if file.genomes.is_empty() {
+			return Err(format!("genome file {} holds no genomes", path).into())
+		}
+		for (index, genome) in file.genomes.iter().enumerate() {
+			if genome.weights.len() != NN_OUTPUTS
+				|| genome.weights.iter().any(|row| row.len() != NN_INPUTS) {
+
+				return Err(format!("genome {} in genome file {} does not have \
+					{} rows of {} weights", index, path, NN_OUTPUTS, NN_INPUTS).into())
+			}
+		}
+
+		Ok(file)
+	}
+}
+
+/** Seeds the first individuals of the given population with the genomes in
+ * the genome file of the given group, as set by its seed settings, up to the
+ * number of individuals the group starts off with. Groups without a genome
+ * file are left as they are. */
+pub fn seed(group: &Group, population: &mut [Individual]) -> Result<(), Box<dyn Error>> {
+	let path = match &group.seed.archive {
+		Some(path) => path,
+		None => return Ok(())
+	};
+	let genomes = GenomeFile::load(path)?.genomes;
+
+	let count = (group.individuals as usize).min(population.len());
+	let count = if group.seed.replicate {
+		count
+	} else {
+		count.min(genomes.len())
+	};
+	for (index, individual) in population[..count].iter_mut().enumerate() {
+		let mutation = if index < genomes.len() {
+			0.0
+		} else {
+			group.seed.mutation
+		};
+		genomes[index % genomes.len()].imprint(individual, mutation);
+	}
+
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/** Path to a temporary file of the given name, unique to this process. */
+	fn temporary(name: &str) -> String {
+		std::env::temp_dir()
+			.join(format!("genome-{}-{}.toml", std::process::id(), name))
+			.to_string_lossy()
+			.into_owned()
+	}
+
+	/** Genome with a different value in every one of its genes. */
+	fn genome(seed: f32) -> Genome {
+		Genome {
+			signature: [seed, seed + 0.25, seed + 0.5],
+			weights: (0..NN_OUTPUTS)
+				.map(|i| (0..NN_INPUTS)
+					.map(|j| seed + (i * NN_INPUTS + j) as f32 / 7.0)
+					.collect())
+				.collect(),
+			biases: [seed, -seed, seed * 2.0, seed / 3.0, 0.0]
+		}
+	}
+
+	#[test]
+	fn save_then_load() {
+		let path = temporary("round-trip");
+		let file = GenomeFile::new(vec![genome(0.125), genome(-3.5)]);
+		file.save(&path).unwrap();
+		let loaded = GenomeFile::load(&path);
+		std::fs::remove_file(&path).unwrap();
+
+		assert_eq!(loaded.unwrap(), file);
+	}
+
+	#[test]
+	fn version_mismatch() {
+		let path = temporary("version");
+		let mut file = GenomeFile::new(vec![genome(1.0)]);
+		file.version = GENOME_FILE_VERSION + 1;
+		file.save(&path).unwrap();
+		let loaded = GenomeFile::load(&path);
+		std::fs::remove_file(&path).unwrap();
+
+		let what = loaded.unwrap_err().to_string();
+		assert!(what.contains("version"), "unexpected error: {}", what);
+	}
+
+	#[test]
+	fn empty_file() {
+		let path = temporary("empty");
+		GenomeFile::new(Vec::new()).save(&path).unwrap();
+		let loaded = GenomeFile::load(&path);
+		std::fs::remove_file(&path).unwrap();
+
+		let what = loaded.unwrap_err().to_string();
+		assert!(what.contains("no genomes"), "unexpected error: {}", what);
+	}
+}
@@ -0,0 +1,82 @@
+use crate::dataset::Individual;
+use crate::settings::{Simulation, Boundary};
+
+/** Spatial grid the individuals alive are sorted into at the start of every
+ * step of the CPU backend, such that the ones around any given point can be
+ * found without going over all of them. This is the counterpart to the grid
+ * described in `Grid.glslh`, except that its cells hold any number of
+ * individuals. */
+#[derive(Debug, Clone)]
+pub struct Grid {
+	/** Width and height of every cell, in simulation board units. */
+	cell_size: f32,
+	/** Number of cells across the grid. */
+	width: u32,
+	/** Number of cells down the grid. */
+	height: u32,
+	/** Individuals in every cell, in row order, as the index of their species
+	 * and their index into its population. */
+	cells: Vec<Vec<(usize, usize)>>,
+	/** What happens at the edges of the plane, which the grid wraps around
+	 * under the torus boundary. */
+	boundary: Boundary,
+}
+impl Grid {
+	/** Creates an empty grid over the plane of the given simulation
+	 * settings. */
+	pub fn new(simulation: &Simulation) -> Self {
+		let [width, height] = simulation.grid.size(
+			simulation.plane_width,
+			simulation.plane_height);
+
+		Self {
+			cell_size: simulation.grid.cell_size.max(f32::EPSILON),
+			width,
+			height,
+			cells: vec![Vec::new(); width as usize * height as usize],
+			boundary: simulation.boundary
+		}
+	}
+
+	/** Sorts the individuals alive and born in the given populations into
+	 * the grid, replacing the ones that were in it. */
+	pub fn fill(&mut self, populations: &[Vec<Individual>]) {
+		self.cells.iter_mut().for_each(Vec::clear);
+		for (species, population) in populations.iter().enumerate() {
+			for (index, individual) in population.iter().enumerate() {
+				if individual.energy <= 0.0 || !individual.born() {
+					continue
+				}
+
+				let (x, y) = self.cell(individual.position);
+				self.cells[(y * self.width + x) as usize].push((species, index));
+			}
+		}
+	}
+
+	/** Cell the given position falls in, clamped to the grid. */
+	pub fn cell(&self, position: [f32; 2]) -> (u32, u32) {
+		let x = (position[0] / self.cell_size).floor();
+		let y = (position[1] / self.cell_size).floor();
+		(
+			x.clamp(0.0, (self.width - 1) as f32) as u32,
+			y.clamp(0.0, (self.height - 1) as f32) as u32
+		)
+	}
+
+	/** Individuals in all of the cells overlapping the box between the given
+	 * lowest and highest corners, as the index of their species and their
+	 * index into its population. Under the torus boundary, the box wraps
+	 * around the edges of the plane. */
+	pub fn within(&self, low: [f32; 2], high: [f32; 2]) -> impl Iterator<Item = (usize, usize)> + '_ {
+		let index = |position: f32| (position / self.cell_size).floor() as i64;
+		let columns = self.boundary.span(index(low[0]), index(high[0]), self.width);
+		let rows = self.boundary.span(index(low[1]), index(high[1]), self.height);
+
+		rows.filter_map(move |y| self.boundary.cell(y, self.height))
+			.flat_map(move |y| columns.clone()
+				.filter_map(move |x| self.boundary.cell(x, self.width))
+				.map(move |x| (y * self.width + x) as usize))
+			.flat_map(move |cell| self.cells[cell].iter().copied())
+	}
+}
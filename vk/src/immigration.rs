@@ -0,0 +1,284 @@
+use std::error::Error;
+use rand::Rng;
+use crate::dataset::{Individual, NN_INPUTS, NN_OUTPUTS};
+use crate::genome::{Genome, GenomeFile};
+use crate::settings::{Simulation, Group, ImmigrationPolicy, ImmigrantSource};
+
+/** Inflow of immigrants into a single species. */
+#[derive(Debug, Clone)]
+struct Inflow {
+	/** Policy by which individuals immigrate. */
+	policy: ImmigrationPolicy,
+	/** Population the species is kept at under the minimum population
+	 * policy. */
+	minimum: usize,
+	/** Number of immigrants arriving every second under the fixed rate
+	 * policy. */
+	rate: f32,
+	/** Genomes immigrants are drawn from, or none for random genomes. */
+	genomes: Option<Vec<Genome>>,
+	/** Left, right, top and bottom bounds of the region immigrants arrive
+	 * in. */
+	region: [f32; 4],
+	/** Signature random immigrants are given. */
+	signature: [f32; 3],
+	/** Energy immigrants start off with. */
+	energy: f32,
+	/** Fraction of an immigrant owed to the species by the fixed rate policy
+	 * that has not arrived yet. */
+	owed: f32,
+	/** Number of immigrants that arrived since the start of the simulation. */
+	arrived: u64,
+}
+impl Inflow {
+	/** Creates the inflow of immigrants into the given group. */
+	fn new(simulation: &Simulation, group: &Group) -> Result<Self, Box<dyn Error>> {
+		let settings = &group.immigration;
+		let genomes = match settings.source {
+			ImmigrantSource::Random => None,
+			ImmigrantSource::Archive if settings.policy == ImmigrationPolicy::None => None,
+			ImmigrantSource::Archive => Some(GenomeFile::load(&settings.archive)
+				.map_err(|what| format!("could not load the immigrants of \
+					species {}: {}", group.name, what))?
+				.genomes)
+		};
+
+		let region = settings.region.unwrap_or(group.spawn_range);
+		let max_x = simulation.plane_width - 0.01;
+		let max_y = simulation.plane_height - 0.01;
+
+		Ok(Self {
+			policy: settings.policy,
+			minimum: settings.minimum.min(group.budget) as usize,
+			rate: settings.rate,
+			genomes,
+			region: [
+				f32::min(region[0], region[1]).clamp(0.0, max_x),
+				f32::max(region[0], region[1]).clamp(0.0, max_x),
+				f32::min(region[2], region[3]).clamp(0.0, max_y),
+				f32::max(region[2], region[3]).clamp(0.0, max_y)
+			],
+			signature: [
+				group.signature.red,
+				group.signature.green,
+				group.signature.blue
+			],
+			energy: group.offspring_energy,
+			owed: 0.0,
+			arrived: 0
+		})
+	}
+
+	/** Creates a single immigrant at a random position within the region. */
+	fn immigrant(&self) -> Individual {
+		let (position, genome) = crate::random::with(|rng| {
+			let [l, r, t, b] = self.region;
+			let position = [
+				l + (r - l) * rng.gen::<f32>(),
+				t + (b - t) * rng.gen::<f32>()
+			];
+
+			let genome = match &self.genomes {
+				Some(genomes) => genomes[rng.gen_range(0..genomes.len())].clone(),
+				None => Genome {
+					signature: self.signature,
+					weights: (0..NN_OUTPUTS)
+						.map(|_| (0..NN_INPUTS).map(|_| rng.gen()).collect())
+						.collect(),
+					biases: rng.gen()
+				}
+			};
+
+			(position, genome)
+		});
+
+		genome.individual(position, self.energy)
+	}
+}
+
+/** Immigration of individuals from outside of the simulation into every
+ * species, following the immigration settings of their groups. Both backends
+ * bring immigrants in as part of the shuffle at the end of every step, and
+ * immigrants are never counted as births. */
+#[derive(Debug, Clone)]
+pub struct Immigration {
+	inflows: Vec<Inflow>,
+}
+impl Immigration {
+	/** Creates the immigration of the species in the given simulation
+	 * settings, loading any archives immigrants are drawn from. */
+	pub fn new(simulation: &Simulation) -> Result<Self, Box<dyn Error>> {
+		Ok(Self {
+			inflows: simulation.species.iter()
+				.map(|group| Inflow::new(simulation, group))
+				.collect::<Result<_, _>>()?
+		})
+	}
+
+	/** Immigration where no individuals ever immigrate into any of the
+	 * species in the given simulation settings. */
+	pub fn closed(simulation: &Simulation) -> Self {
+		let mut simulation = simulation.clone();
+		for group in &mut simulation.species {
+			group.immigration = Default::default();
+		}
+
+		Self::new(&simulation).expect("closed immigration never loads archives")
+	}
+
+	/** Immigrants arriving into the given species over a step covering the
+	 * given time, in seconds, given how many of its individuals are alive and
+	 * how many more it has room for. */
+	pub fn immigrants(&mut self, species: usize, alive: usize, room: usize, delta: f32) -> Vec<Individual> {
+		let inflow = &mut self.inflows[species];
+		let count = match inflow.policy {
+			ImmigrationPolicy::None => 0,
+			ImmigrationPolicy::MinimumPopulation => inflow.minimum.saturating_sub(alive),
+			ImmigrationPolicy::FixedRate => {
+				inflow.owed += inflow.rate * delta;
+				let count = inflow.owed.floor();
+				inflow.owed -= count;
+
+				count as usize
+			}
+		}.min(room);
+
+		inflow.arrived += count as u64;
+		(0..count).map(|_| inflow.immigrant()).collect()
+	}
+
+	/** Number of immigrants that arrived into every species since the start
+	 * of the simulation, in the order the species were declared in. */
+	pub fn arrived(&self) -> Vec<u64> {
+		self.inflows.iter()
+			.map(|inflow| inflow.arrived)
+			.collect()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::settings::{Preferences, Immigration as Settings};
+
+	/** Immigration into a single species following the given settings. */
+	fn immigration(settings: Settings) -> (Simulation, Immigration) {
+		let mut simulation = Preferences::default().simulation;
+		simulation.species.truncate(1);
+		simulation.species[0].immigration = settings;
+
+		let immigration = Immigration::new(&simulation).unwrap();
+		(simulation, immigration)
+	}
+
+	#[test]
+	fn no_immigration() {
+		let (_, mut immigration) = immigration(Default::default());
+
+		assert!(immigration.immigrants(0, 0, 100, 1.0).is_empty());
+		assert_eq!(immigration.arrived(), vec![0]);
+	}
+
+	#[test]
+	fn minimum_population() {
+		let (_, mut immigration) = immigration(Settings {
+			policy: ImmigrationPolicy::MinimumPopulation,
+			minimum: 10,
+			..Default::default()
+		});
+
+		assert_eq!(immigration.immigrants(0, 4, 100, 1.0).len(), 6);
+		assert_eq!(immigration.immigrants(0, 12, 100, 1.0).len(), 0);
+		/* Never more than there is room for. */
+		assert_eq!(immigration.immigrants(0, 0, 3, 1.0).len(), 3);
+		assert_eq!(immigration.arrived(), vec![9]);
+	}
+
+	#[test]
+	fn minimum_population_within_budget() {
+		let mut simulation = Preferences::default().simulation;
+		simulation.species.truncate(1);
+		simulation.species[0].budget = 5;
+		simulation.species[0].immigration = Settings {
+			policy: ImmigrationPolicy::MinimumPopulation,
+			minimum: 10,
+			..Default::default()
+		};
+		let mut immigration = Immigration::new(&simulation).unwrap();
+
+		assert_eq!(immigration.immigrants(0, 0, 100, 1.0).len(), 5);
+	}
+
+	#[test]
+	fn fixed_rate() {
+		let (_, mut immigration) = immigration(Settings {
+			policy: ImmigrationPolicy::FixedRate,
+			rate: 1.0,
+			..Default::default()
+		});
+
+		/* Fractions of an immigrant add up over the steps. */
+		for _ in 0..3 {
+			assert!(immigration.immigrants(0, 0, 100, 0.25).is_empty());
+		}
+		assert_eq!(immigration.immigrants(0, 0, 100, 0.25).len(), 1);
+		assert_eq!(immigration.immigrants(0, 1000, 100, 2.5).len(), 2);
+		assert_eq!(immigration.immigrants(0, 1000, 100, 0.5).len(), 1);
+		assert_eq!(immigration.arrived(), vec![4]);
+	}
+
+	#[test]
+	fn random_immigrants() {
+		let (simulation, mut immigration) = immigration(Settings {
+			policy: ImmigrationPolicy::MinimumPopulation,
+			minimum: 50,
+			region: Some([20.0, 10.0, 30.0, 40.0]),
+			..Default::default()
+		});
+		let group = &simulation.species[0];
+
+		crate::random::seed(39);
+		let immigrants = immigration.immigrants(0, 0, 100, 1.0);
+		assert_eq!(immigrants.len(), 50);
+		for immigrant in immigrants {
+			let [x, y] = immigrant.position;
+			assert!(x >= 10.0 && x <= 20.0 && y >= 30.0 && y <= 40.0,
+				"immigrant at {:?} out of its region", immigrant.position);
+			assert_eq!(immigrant.energy, group.offspring_energy);
+			assert_eq!(immigrant.signature,
+				[group.signature.red, group.signature.green, group.signature.blue]);
+		}
+	}
+
+	#[test]
+	fn archived_immigrants() {
+		let path = std::env::temp_dir()
+			.join(format!("immigration-{}-archive.toml", std::process::id()))
+			.to_string_lossy()
+			.into_owned();
+		let genome = Genome {
+			signature: [0.25, 0.5, 0.75],
+			weights: vec![vec![0.5; NN_INPUTS]; NN_OUTPUTS],
+			biases: [1.0; NN_OUTPUTS]
+		};
+		GenomeFile::new(vec![genome.clone()]).save(&path).unwrap();
+
+		let mut simulation = Preferences::default().simulation;
+		simulation.species.truncate(1);
+		simulation.species[0].immigration = Settings {
+			policy: ImmigrationPolicy::MinimumPopulation,
+			minimum: 3,
+			source: ImmigrantSource::Archive,
+			archive: path.clone(),
+			..Default::default()
+		};
+		let loaded = Immigration::new(&simulation);
+		std::fs::remove_file(&path).unwrap();
+
+		let immigrants = loaded.unwrap().immigrants(0, 0, 100, 1.0);
+		assert_eq!(immigrants.len(), 3);
+		for immigrant in immigrants {
+			assert_eq!(Genome::from(&immigrant), genome);
+		}
+	}
+}
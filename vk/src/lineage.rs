@@ -0,0 +1,344 @@
+use std::collections::HashSet;
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use crate::dataset::{Individual, LineageEvent, LineageRecord};
+use crate::settings::Simulation;
+
+/** Header line of the lineage log, which is a CSV file with one line for every
+ * birth and every death. */
+const HEADER: &str = "Step,Event,Species,Id,Mother,Father,Generation,Age";
+
+/** Lineage of the individuals of all species. Hands out the identifiers of
+ * individuals, keeps count of the steps simulated and writes every birth and
+ * every death out to the lineage log, if there is one. Both backends keep one
+ * of these, and give it every individual that joins or leaves the simulation.
+ *
+ * Individuals that did not have any parents, that is, the individuals the
+ * species start off with, immigrants and injected individuals, are born with
+ * no parents, and are of generation zero. */
+#[derive(Debug)]
+pub struct Lineage {
+	/** Names of all species, in the order they were declared in. */
+	names: Vec<String>,
+	/** Identifier handed out to the next individual. */
+	next: u64,
+	/** Number of steps simulated so far. */
+	step: u64,
+	/** Whether the births of the individuals the species start off with have
+	 * been logged. */
+	founded: bool,
+	/** Lineage log, if there is one. */
+	log: Option<BufWriter<File>>,
+}
+impl Lineage {
+	/** Starts keeping track of the lineage of the individuals in the given
+	 * simulation settings, creating the lineage log, if one was set. */
+	pub fn new(simulation: &Simulation) -> Result<Self, Box<dyn Error>> {
+		let mut lineage = Self::unlogged(simulation);
+		if let Some(path) = &simulation.lineage_log {
+			let file = File::create(path)
+				.map_err(|what| format!("could not create lineage log {}: {}", path, what))?;
+
+			let mut log = BufWriter::new(file);
+			writeln!(log, "{}", HEADER)?;
+			lineage.log = Some(log);
+		}
+
+		Ok(lineage)
+	}
+
+	/** Keeps track of the lineage of the individuals in the given simulation
+	 * settings without writing any of it out. */
+	pub fn unlogged(simulation: &Simulation) -> Self {
+		/* Individuals the species start off with take the identifiers up to
+		 * the sum of the budgets. See `dataset::populations()`. */
+		let founders = simulation.species.iter()
+			.map(|group| u64::from(group.budget))
+			.sum::<u64>();
+
+		Self {
+			names: simulation.species.iter()
+				.map(|group| group.name.clone())
+				.collect(),
+			next: founders + 1,
+			step: 0,
+			founded: false,
+			log: None
+		}
+	}
+
+	/** Whether births and deaths are being written out to a lineage log. */
+	pub fn logging(&self) -> bool {
+		self.log.is_some()
+	}
+
+	/** Number of steps simulated so far. */
+	pub fn step(&self) -> u64 {
+		self.step
+	}
+
+	/** Identifier the next individual will be given. */
+	pub fn next_id(&self) -> u64 {
+		self.next
+	}
+
+	/** Whether the births of the individuals the species start off with have
+	 * been logged yet. */
+	pub fn founded(&self) -> bool {
+		self.founded
+	}
+
+	/** Moves on to the next step, flushing out everything logged over the
+	 * last one, since the simulation may be stopped at any point. */
+	pub fn advance(&mut self) {
+		self.step += 1;
+		if let Some(Err(what)) = self.log.as_mut().map(Write::flush) {
+			warn!("could not write to the lineage log, closing it: {}", what);
+			self.log = None;
+		}
+	}
+
+	/** Sets the given number of identifiers aside, returning the first of
+	 * them. The GPU backend hands them out to the newborns of an iteration by
+	 * itself. */
+	pub fn reserve(&mut self, count: u64) -> u64 {
+		let first = self.next;
+		self.next += count;
+
+		first
+	}
+
+	/** Logs the births of the individuals every species starts off with, in
+	 * the order the species were declared in. */
+	pub fn found<P: AsRef<[Individual]>>(&mut self, populations: &[P]) {
+		for (species, population) in populations.iter().enumerate() {
+			for founder in population.as_ref() {
+				self.birth(species, founder);
+			}
+		}
+		self.founded = true;
+	}
+
+	/** Gives the given newborn of the given parents its identity and logs its
+	 * birth into the species at the given index. */
+	pub fn conceive(&mut self, species: usize, child: &mut Individual, mother: &Individual, father: &Individual) {
+		child.id = self.reserve(1);
+		child.parents = [mother.id, father.id];
+		child.generation = mother.generation.max(father.generation) + 1;
+		child.birth_step = self.step;
+
+		self.birth(species, child);
+	}
+
+	/** Gives the given individuals, which came into the species at the given
+	 * index from outside of the simulation, an identity of their own, with no
+	 * parents, and logs their births. */
+	pub fn arrive(&mut self, species: usize, individuals: &mut [Individual]) {
+		for individual in individuals {
+			individual.id = self.reserve(1);
+			individual.parents = [0, 0];
+			individual.generation = 0;
+			individual.birth_step = self.step;
+
+			self.birth(species, individual);
+		}
+	}
+
+	/** Logs the changes made to the population of the species at the given
+	 * index by a scripted event, which turned it from the first given one into
+	 * the second. Individuals that were left out of it died, and individuals
+	 * that have no identity yet arrived into it. */
+	pub fn reconcile(&mut self, species: usize, before: &[Individual], after: &mut [Individual]) {
+		let kept = after.iter()
+			.map(|individual| individual.id)
+			.filter(|&id| id != 0)
+			.collect::<HashSet<_>>();
+		for individual in before.iter().filter(|individual| !kept.contains(&individual.id)) {
+			self.death(species, individual);
+		}
+
+		for individual in after.iter_mut().filter(|individual| individual.id == 0) {
+			self.arrive(species, std::slice::from_mut(individual));
+		}
+	}
+
+	/** Logs the birth of the given individual into the species at the given
+	 * index. */
+	pub fn birth(&mut self, species: usize, individual: &Individual) {
+		let record = LineageRecord::new(LineageEvent::Birth, species, individual, self.step);
+		self.record(&record);
+	}
+
+	/** Logs the death of the given individual of the species at the given
+	 * index. */
+	pub fn death(&mut self, species: usize, individual: &Individual) {
+		let record = LineageRecord::new(LineageEvent::Death, species, individual, self.step);
+		self.record(&record);
+	}
+
+	/** Writes the given record out to the lineage log, if there is one. The
+	 * log gets closed if it could not be written to. */
+	pub fn record(&mut self, record: &LineageRecord) {
+		let log = match &mut self.log {
+			Some(log) => log,
+			None => return
+		};
+
+		let event = match record.event {
+			LineageEvent::Birth => "Birth",
+			LineageEvent::Death => "Death"
+		};
+		let species = self.names.get(record.species as usize)
+			.map(String::as_str)
+			.unwrap_or("?");
+		if let Err(what) = writeln!(log, "{},{},{},{},{},{},{},{}",
+			record.step,
+			event,
+			species,
+			record.id,
+			record.parents[0],
+			record.parents[1],
+			record.generation,
+			record.age) {
+
+			warn!("could not write to the lineage log, closing it: {}", what);
+			self.log = None;
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::dataset::{NN_INPUTS, NN_OUTPUTS};
+	use crate::genome::Genome;
+	use crate::settings::Preferences;
+
+	/** Individual of the given identity, with a blank genome. */
+	fn individual(id: u64, generation: u32) -> Individual {
+		let mut individual = Genome {
+			signature: [0.0; 3],
+			weights: vec![vec![0.0; NN_INPUTS]; NN_OUTPUTS],
+			biases: [0.0; NN_OUTPUTS]
+		}.individual([0.0, 0.0], 1.0);
+		individual.id = id;
+		individual.generation = generation;
+
+		individual
+	}
+
+	#[test]
+	fn identifiers_follow_the_founders() {
+		let simulation = Preferences::default().simulation;
+		let lineage = Lineage::unlogged(&simulation);
+		let founders = crate::dataset::populations(&simulation);
+
+		let ids = founders.iter()
+			.flatten()
+			.map(|individual| individual.id)
+			.collect::<HashSet<_>>();
+		assert_eq!(ids.len(), founders.iter().map(Vec::len).sum::<usize>());
+		assert!(ids.iter().all(|&id| id != 0 && id < lineage.next_id()));
+	}
+
+	#[test]
+	fn reserve_sets_identifiers_aside() {
+		let mut lineage = Lineage::unlogged(&Preferences::default().simulation);
+		let first = lineage.next_id();
+
+		assert_eq!(lineage.reserve(10), first);
+		assert_eq!(lineage.next_id(), first + 10);
+		assert_eq!(lineage.reserve(0), first + 10);
+	}
+
+	#[test]
+	fn conceive_links_the_parents() {
+		let mut lineage = Lineage::unlogged(&Preferences::default().simulation);
+		lineage.advance();
+		lineage.advance();
+
+		let (mother, father) = (individual(5, 2), individual(7, 4));
+		let first = lineage.next_id();
+		let mut child = individual(0, 0);
+		lineage.conceive(0, &mut child, &mother, &father);
+
+		assert_eq!(child.id, first);
+		assert_eq!(child.parents, [5, 7]);
+		assert_eq!(child.generation, 5);
+		assert_eq!(child.birth_step, 2);
+
+		let mut sibling = individual(0, 0);
+		lineage.conceive(0, &mut sibling, &father, &mother);
+		assert_eq!(sibling.id, first + 1);
+		assert_eq!(sibling.parents, [7, 5]);
+	}
+
+	#[test]
+	fn arrivals_have_no_parents() {
+		let mut lineage = Lineage::unlogged(&Preferences::default().simulation);
+		lineage.advance();
+
+		let first = lineage.next_id();
+		let mut arrivals = vec![individual(0, 3), individual(0, 0)];
+		arrivals[0].parents = [1, 2];
+		lineage.arrive(1, &mut arrivals);
+
+		for (i, arrival) in arrivals.iter().enumerate() {
+			assert_eq!(arrival.id, first + i as u64);
+			assert_eq!(arrival.parents, [0, 0]);
+			assert_eq!(arrival.generation, 0);
+			assert_eq!(arrival.birth_step, 1);
+		}
+	}
+
+	#[test]
+	fn reconcile_identifies_newcomers() {
+		let mut lineage = Lineage::unlogged(&Preferences::default().simulation);
+		let before = vec![individual(1, 0), individual(2, 0)];
+		let mut after = vec![individual(2, 0), individual(0, 0)];
+
+		let first = lineage.next_id();
+		lineage.reconcile(0, &before, &mut after);
+
+		assert_eq!(after[0].id, 2);
+		assert_eq!(after[1].id, first);
+		assert_eq!(lineage.next_id(), first + 1);
+	}
+
+	#[test]
+	fn births_and_deaths_are_logged() {
+		let path = std::env::temp_dir()
+			.join(format!("lineage-{}.csv", std::process::id()))
+			.to_string_lossy()
+			.into_owned();
+		let mut simulation = Preferences::default().simulation;
+		simulation.lineage_log = Some(path.clone());
+
+		let mut lineage = Lineage::new(&simulation).unwrap();
+		let (mother, father) = (individual(1, 0), individual(2, 0));
+		lineage.found(&[vec![mother, father], vec![]]);
+		assert!(lineage.founded());
+		lineage.advance();
+
+		let mut child = individual(0, 0);
+		lineage.conceive(0, &mut child, &mother, &father);
+		let mut dead = mother;
+		dead.age = 2.5;
+		lineage.death(0, &dead);
+		lineage.advance();
+		assert_eq!(lineage.step(), 2);
+
+		let log = std::fs::read_to_string(&path);
+		drop(lineage);
+		std::fs::remove_file(&path).unwrap();
+
+		assert_eq!(log.unwrap().lines().collect::<Vec<_>>(), vec![
+			HEADER,
+			"0,Birth,Herbivores,1,0,0,0,0",
+			"0,Birth,Herbivores,2,0,0,0,0",
+			format!("1,Birth,Herbivores,{},1,2,1,0", child.id).as_str(),
+			"1,Death,Herbivores,1,0,0,0,2.5",
+		]);
+	}
+}
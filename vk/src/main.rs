@@ -18,6 +18,10 @@ use log::LevelFilter;
 use std::time::Duration;
 use crate::evolve::wgpu::Evo;
 use crate::evolve::cpu::World;
+use crate::lineage::Lineage;
+use crate::terrain::Terrain;
+use crate::events::Timeline;
+use crate::immigration::Immigration;
 
 mod display;
 mod shaders;
@@ -27,6 +31,15 @@ mod flipbook;
 mod dataset;
 mod models;
 mod evolve;
+mod statistics;
+mod terrain;
+mod genome;
+mod events;
+mod immigration;
+mod grid;
+mod lineage;
+mod random;
+mod training;
 
 /** Backend driver to be used for evolution. */
 enum Backend {
@@ -46,6 +59,14 @@ fn main() {
 			Default::default()
 		});
 
+	/* Train offline instead, if told to, without ever opening a window. */
+	if prefs.training.enabled {
+		if let Err(what) = training::train(&prefs) {
+			error!("training failed: {}", what);
+		}
+		return
+	}
+
 	let window_size = PhysicalSize {
 		width:  prefs.window.width,
 		height: prefs.window.height
@@ -64,7 +85,13 @@ fn main() {
 		}, &prefs)).expect("could not initialize state");
 	let state = Arc::new(state);
 
-	let (producer, consumer) = flipbook::channel(state.clone(), &prefs);
+	let terrain = Terrain::load(&prefs.simulation)
+		.unwrap_or_else(|what| {
+			warn!("could not load terrain, falling back to an open plane: {}", what);
+			Terrain::open(&prefs.simulation)
+		});
+
+	let (producer, consumer) = flipbook::channel(state.clone(), &prefs, &terrain);
 	let mut display = Display::new(
 		state.clone(),
 		surface,
@@ -76,17 +103,37 @@ fn main() {
 		.build()
 		.unwrap();
 
+	let immigration = Immigration::new(&prefs.simulation)
+		.unwrap_or_else(|what| {
+			warn!("could not load the immigrants, closing off all species: {}", what);
+			Immigration::closed(&prefs.simulation)
+		});
+
+	let lineage = Lineage::new(&prefs.simulation)
+		.unwrap_or_else(|what| {
+			warn!("could not create the lineage log, running without one: {}", what);
+			Lineage::unlogged(&prefs.simulation)
+		});
+
 	let mut backend = if prefs.simulation.mode == SimulationMode::Gpu {
 		let evo = Evo::new(
 			state.clone(),
 			producer,
-			&prefs);
+			&prefs,
+			immigration,
+			lineage);
 		Backend::Gpu(evo)
 	} else {
-		let world = World::new(&prefs.simulation);
+		let world = World::new(&prefs.simulation, &terrain, immigration, lineage);
 		Backend::Cpu(world)
 	};
 
+	let mut timeline = Timeline::new(&prefs.simulation)
+		.unwrap_or_else(|what| {
+			warn!("could not load the scripted events, running without any: {}", what);
+			Default::default()
+		});
+
 	/* Keep a thread taking care of polling the device. */
 	std::thread::spawn(move || loop {
 		state.device().poll(Maintain::Wait);
@@ -107,11 +154,25 @@ fn main() {
 		}
 
 		let mut time = Instant::now();
+		let mut report = Instant::now();
 		loop {
 			let now = Instant::now();
 			let delta = now.duration_since(time);
 			time = now;
 
+			/* Report the statistics of the populations, if it is time. */
+			let interval = prefs.simulation.statistics_interval;
+			if interval > 0.0
+				&& report.elapsed() >= Duration::from_secs_f32(interval) {
+
+				report = now;
+				let statistics = match backend {
+					Backend::Gpu(ref mut driver) => driver.statistics().await,
+					Backend::Cpu(ref driver) => driver.statistics()
+				};
+				info!("{}", statistics);
+			}
+
 			/* Dilate and clamp. */
 			let delta_dil = delta.as_nanos() as f64;
 			let delta_dil = delta_dil * f64::from(prefs.simulation.time_dilation);
@@ -136,11 +197,19 @@ fn main() {
 			};
 
 
-			/* Simulate. */
+			/* Run the scripted events that are due, then simulate. */
+			for action in timeline.due() {
+				info!("scripted event: {}", action);
+				match backend {
+					Backend::Gpu(ref mut driver) => driver.apply(&action).await,
+					Backend::Cpu(ref mut driver) => driver.apply(&action)
+				}
+			}
 			match backend {
 				Backend::Gpu(ref mut driver) => driver.iterate(delta).await,
 				Backend::Cpu(ref mut driver) => driver.step(delta)
 			}
+			timeline.advance(delta);
 		}
 	});
 
@@ -0,0 +1,33 @@
+use std::cell::RefCell;
+use rand::{Rng, SeedableRng};
+use rand::distributions::{Distribution, Standard};
+use rand::rngs::StdRng;
+
+thread_local! {
+	/** Generator every random number drawn on the host for the simulation
+	 * comes from. Every thread has its own, seeded from the entropy of the
+	 * system unless told otherwise. */
+	static GENERATOR: RefCell<StdRng> = RefCell::new(StdRng::from_entropy());
+}
+
+/** Reseeds the generator of the current thread with the given seed, such that
+ * everything drawn from it afterwards comes out the same every time it is
+ * given the same seed. */
+pub fn seed(seed: u64) {
+	GENERATOR.with(|generator| *generator.borrow_mut() = StdRng::seed_from_u64(seed))
+}
+
+/** Draws a random value from the generator of the current thread. */
+pub fn random<T>() -> T
+	where Standard: Distribution<T> {
+
+	with(|generator| generator.gen())
+}
+
+/** Runs the given function with the generator of the current thread. The
+ * function may not draw any random values through this module itself. */
+pub fn with<F, R>(f: F) -> R
+	where F: FnOnce(&mut StdRng) -> R {
+
+	GENERATOR.with(|generator| f(&mut generator.borrow_mut()))
+}
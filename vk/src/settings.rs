@@ -15,18 +15,483 @@ pub struct Pheromone {
 	pub blue: f32
 }
 
+/** A value given separately for each of the chemicals in the pheromones. */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct Chemicals<T = f32> {
+	/** Value for the red chemical. */
+	pub red: T,
+	/** Value for the green chemical. */
+	pub green: T,
+	/** Value for the blue chemical. */
+	pub blue: T
+}
+impl<T: Copy> Chemicals<T> {
+	/** The values for all chemicals, in red, green and blue order. */
+	pub fn to_array(&self) -> [T; 3] {
+		[self.red, self.green, self.blue]
+	}
+}
+
+/** Ways in which the chemicals on the field decompose over time. */
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub enum DecayMode {
+	/** A fixed amount of the chemical decomposes every second, such that any
+	 * amount of it is gone after a fixed time. */
+	Linear = 0,
+	/** A fixed fraction of what is left of the chemical decomposes every
+	 * second, such that it lingers on for longer the less of it there is. */
+	Exponential = 1,
+}
+impl DecayMode {
+	/** Decomposes the given amount of a chemical at the given rate over the
+	 * given time, in seconds, returning what is left of it. */
+	pub fn decay(self, value: f32, rate: f32, delta: f32) -> f32 {
+		match self {
+			DecayMode::Linear => (value - rate * delta).max(0.0),
+			DecayMode::Exponential => value * (-rate * delta).exp()
+		}
+	}
+}
+impl Default for DecayMode {
+	fn default() -> Self {
+		DecayMode::Linear
+	}
+}
+
+/** Models for the regrowth of the grass on the field. */
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub enum RegrowthModel {
+	/** Grass grows by a fixed amount every second. */
+	Linear = 0,
+	/** Grass grows in proportion to how much of it is already there, and
+	 * slows down as it gets close to its maximum. */
+	Logistic = 1,
+	/** Grass starts off in a few patches and only grows where there already
+	 * is grass, either in the cell itself or in its neighbours, such that the
+	 * patches spread out over time. */
+	Patches = 2,
+	/** Grass grows by a fixed amount every second, but only once some time
+	 * has passed since it was last grazed. */
+	Delayed = 3,
+}
+
+/** Settings controlling how the grass on the field grows back. */
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct Regrowth {
+	/** Model followed by the regrowth of the grass. */
+	pub model: RegrowthModel,
+	/** Density grass grows back from under the logistic model, even in cells
+	 * that have been grazed down to nothing. */
+	pub seed_density: f32,
+	/** Number of patches of grass the field starts off with under the patches
+	 * model. */
+	pub patches: u32,
+	/** Radius of the patches of grass the field starts off with under the
+	 * patches model, in simulation board units. */
+	pub patch_radius: f32,
+	/** How strongly the grass around a cell seeds it under the patches model,
+	 * as a fraction of the average density of grass in its neighbours. */
+	pub spread: f32,
+	/** Time, in seconds of simulated time, during which grass does not grow
+	 * back after being grazed under the delayed model. */
+	pub delay: f32,
+}
+impl Regrowth {
+	/** Grows the given density of grass back over the given time, in seconds,
+	 * at the given rate per second, returning the new density. This needs the
+	 * average density of grass in the neighbouring cells, as well as the time
+	 * left before the grass may grow back after it was last grazed, which
+	 * gets counted down. `UpdatePlane.glsl` grows grass back the same way. */
+	pub fn regrow(&self, grass: f32, neighbours: f32, delay: &mut f32, rate: f32, delta: f32) -> f32 {
+		let growth = match self.model {
+			RegrowthModel::Linear => rate * delta,
+			RegrowthModel::Logistic =>
+				rate * grass.max(self.seed_density) * (1.0 - grass) * delta,
+			RegrowthModel::Patches =>
+				rate * grass.max(neighbours * self.spread) * (1.0 - grass) * delta,
+			RegrowthModel::Delayed => {
+				/* Only grow for the part of the step after the delay. */
+				let waited = delta.min(*delay);
+				*delay -= waited;
+
+				rate * (delta - waited)
+			}
+		};
+
+		(grass + growth).clamp(0.0, 1.0)
+	}
+}
+impl Default for Regrowth {
+	fn default() -> Self {
+		Self {
+			model: RegrowthModel::Linear,
+			seed_density: 0.01,
+			patches: 16,
+			patch_radius: 5.0,
+			spread: 0.5,
+			delay: 10.0
+		}
+	}
+}
+
+/** Shapes of the schedules environment parameters may follow over time. */
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub enum ScheduleKind {
+	/** The parameter oscillates around its base value, like the seasons. */
+	Sine,
+	/** The parameter is interpolated linearly between keyframes. */
+	Keyframes,
+	/** The parameter jumps from one keyframe to the next, holding the value
+	 * of the last keyframe that was reached. */
+	Step,
+}
+
+/** Schedule an environment parameter follows over simulated time, given as a
+ * factor its base value in the settings gets scaled by. */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct Schedule {
+	/** Shape of the schedule. */
+	pub kind: ScheduleKind,
+	/** Length, in seconds of simulated time, of one cycle of the schedule.
+	 * Keyframe and step schedules only repeat when this is greater than
+	 * zero, and hold their last value otherwise. */
+	pub period: f32,
+	/** How far the factor of a sine schedule swings away from one. */
+	pub amplitude: f32,
+	/** Phase of a sine schedule at the start of the simulation, in cycles. */
+	pub phase: f32,
+	/** Keyframes of the keyframe and step schedules, as pairs of a time, in
+	 * seconds of simulated time, and the factor at that time, in order of
+	 * time. */
+	pub keyframes: Vec<[f32; 2]>,
+}
+impl Schedule {
+	/** Factor the base value of the parameter gets scaled by at the given
+	 * time, in seconds of simulated time. Factors are never negative. */
+	pub fn factor(&self, time: f32) -> f32 {
+		let time = if self.period > 0.0 {
+			time.rem_euclid(self.period)
+		} else {
+			time
+		};
+
+		let factor = match self.kind {
+			ScheduleKind::Sine => if self.period > 0.0 {
+				let angle = (time / self.period + self.phase) * 2.0 * std::f32::consts::PI;
+				1.0 + self.amplitude * angle.sin()
+			} else {
+				1.0
+			},
+			ScheduleKind::Keyframes | ScheduleKind::Step => {
+				let next = self.keyframes.iter().position(|[at, _]| *at > time);
+				match (self.keyframes.last(), next) {
+					(None, _) => 1.0,
+					(Some(&[_, last]), None) => last,
+					(_, Some(0)) => self.keyframes[0][1],
+					(_, Some(next)) => {
+						let [from, a] = self.keyframes[next - 1];
+						let [to, b] = self.keyframes[next];
+
+						if self.kind == ScheduleKind::Step {
+							a
+						} else {
+							a + (b - a) * (time - from) / (to - from)
+						}
+					}
+				}
+			}
+		};
+
+		factor.max(0.0)
+	}
+}
+impl Default for Schedule {
+	fn default() -> Self {
+		Self {
+			kind: ScheduleKind::Sine,
+			period: 0.0,
+			amplitude: 0.0,
+			phase: 0.0,
+			keyframes: Vec::new()
+		}
+	}
+}
+
+/** Schedules of the environment parameters that change over time. Parameters
+ * without a schedule keep their base value forever. */
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct Schedules {
+	/** Schedule of the growth rate of the grass. */
+	pub growth_rate: Option<Schedule>,
+	/** Schedule of the decomposition rates of all of the chemicals. */
+	pub decomposition_rate: Option<Schedule>,
+	/** Schedule of the metabolism of the individuals of all species. */
+	pub metabolism: Option<Schedule>,
+	/** Schedule of the temperature all over the plane. */
+	pub temperature: Option<Schedule>,
+}
+impl Schedules {
+	/** Factors all of the scheduled parameters get scaled by at the given
+	 * time, in seconds of simulated time. */
+	pub fn at(&self, time: f32) -> Season {
+		let factor = |schedule: &Option<Schedule>| schedule.as_ref()
+			.map(|schedule| schedule.factor(time))
+			.unwrap_or(1.0);
+
+		Season {
+			growth_rate: factor(&self.growth_rate),
+			decomposition_rate: factor(&self.decomposition_rate),
+			metabolism: factor(&self.metabolism),
+			temperature: factor(&self.temperature)
+		}
+	}
+}
+
+/** Factors the base values of the scheduled parameters are scaled by at some
+ * point in time. */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Season {
+	/** Factor of the growth rate of the grass. */
+	pub growth_rate: f32,
+	/** Factor of the decomposition rates of the chemicals. */
+	pub decomposition_rate: f32,
+	/** Factor of the metabolism of the individuals. */
+	pub metabolism: f32,
+	/** Factor of the temperature of the plane. */
+	pub temperature: f32,
+}
+
+/** Kinds of events that may be scripted to happen during a simulation. */
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub enum EventKind {
+	/** Kill a fraction of the individuals of a species, picked at random,
+	 * optionally only out of those within a region. */
+	Cull,
+	/** Add individuals to a species, with genomes drawn at random from a
+	 * genome file, optionally within a region rather than the spawn range of
+	 * the species. */
+	Inject,
+	/** Remove all of the grass within a region. */
+	WipeGrass,
+	/** Change the base value of an environment parameter. */
+	Set,
+	/** Write the genomes of the individuals of a species out to a genome
+	 * file, optionally only those within a region, and optionally only the
+	 * best of them. */
+	Export,
+}
+
+/** Orders in which exported individuals are ranked, from the best down. */
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub enum Ranking {
+	/** Most energy first. */
+	Energy,
+	/** Oldest first. */
+	Age,
+	/** Highest generation first. */
+	Generation,
+}
+
+/** Environment parameters that may be changed by scripted events. */
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub enum Parameter {
+	/** Growth rate of the grass. */
+	GrowthRate,
+	/** Decomposition rates of all of the chemicals. */
+	DecompositionRate,
+	/** Diffusion rates of all of the chemicals. */
+	DiffusionRate,
+}
+
+/** An event scripted to happen at some point during the simulation. Which of
+ * the fields are used depends on the kind of the event. */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct Event {
+	/** Time, in seconds of simulated time, at which the event happens. */
+	pub at: f32,
+	/** Step of the simulation at which the event happens. When given, this
+	 * is used instead of the time. */
+	pub at_step: Option<u64>,
+	/** What happens. */
+	pub kind: EventKind,
+	/** Name of the species culled, injected into or exported. */
+	pub species: String,
+	/** Fraction of the individuals culled, between zero and one. */
+	pub fraction: f32,
+	/** Region the event is limited to, in the same format as the spawn range
+	 * of a species. Culls and exports without a region affect the whole plane
+	 * and injections without one the spawn range of the species. */
+	pub region: Option<[f32; 4]>,
+	/** Path to the genome file injected individuals are drawn from, or
+	 * exported individuals are written to. */
+	pub genomes: String,
+	/** Number of individuals injected, or the number of the best individuals
+	 * exported. Exports of zero individuals write out all of them. */
+	pub count: u32,
+	/** Order in which exported individuals are ranked. */
+	pub rank_by: Ranking,
+	/** Parameter changed. */
+	pub parameter: Option<Parameter>,
+	/** Value the parameter is changed to. */
+	pub value: f32,
+}
+impl Default for Event {
+	fn default() -> Self {
+		Self {
+			at: 0.0,
+			at_step: None,
+			kind: EventKind::Cull,
+			species: String::new(),
+			fraction: 0.0,
+			region: None,
+			genomes: String::new(),
+			count: 0,
+			rank_by: Ranking::Energy,
+			parameter: None,
+			value: 0.0
+		}
+	}
+}
+
+/** Policies by which individuals from outside of the simulation immigrate
+ * into a group. */
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub enum ImmigrationPolicy {
+	/** No individuals ever immigrate. */
+	None,
+	/** Immigrants are brought in whenever the population falls below a
+	 * minimum, such that the group may never go extinct. */
+	MinimumPopulation,
+	/** Immigrants arrive at a fixed rate, regardless of the population. */
+	FixedRate,
+}
+
+/** Where the genomes of immigrants come from. */
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub enum ImmigrantSource {
+	/** Immigrants get fresh random genomes. */
+	Random,
+	/** Immigrants get genomes drawn at random from a genome file. */
+	Archive,
+}
+
+/** Settings controlling how individuals immigrate into a group. */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct Immigration {
+	/** Policy by which individuals immigrate. */
+	pub policy: ImmigrationPolicy,
+	/** Population the group is kept at under the minimum population
+	 * policy. */
+	pub minimum: u32,
+	/** Number of immigrants arriving every second of simulated time under
+	 * the fixed rate policy. */
+	pub rate: f32,
+	/** Where the genomes of immigrants come from. */
+	pub source: ImmigrantSource,
+	/** Path to the genome file immigrants are drawn from, when they come
+	 * from an archive. */
+	pub archive: String,
+	/** Region immigrants arrive in, in the same format as the spawn range.
+	 * Immigrants arrive within the spawn range of the group by default. */
+	pub region: Option<[f32; 4]>,
+}
+impl Default for Immigration {
+	fn default() -> Self {
+		Self {
+			policy: ImmigrationPolicy::None,
+			minimum: 0,
+			rate: 0.0,
+			source: ImmigrantSource::Random,
+			archive: String::new(),
+			region: None
+		}
+	}
+}
+
+/** Settings controlling how a group is seeded with the genomes of a genome
+ * file when the simulation starts. */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct Seed {
+	/** Path to the genome file the group is seeded from. Groups without one
+	 * start off with zero or random genomes, following `init_to_random`. */
+	pub archive: Option<String>,
+	/** Whether the genomes in the file are copied over and over until they
+	 * fill all of the individuals the group starts off with. Otherwise, the
+	 * individuals left over once every genome has been used keep the genomes
+	 * they would have had without a seed. */
+	pub replicate: bool,
+	/** Largest amount by which every weight and bias of a copy of a genome
+	 * may deviate from the one in the file. The first copy of every genome is
+	 * always exact. */
+	pub mutation: f32,
+}
+impl Default for Seed {
+	fn default() -> Self {
+		Self {
+			archive: None,
+			replicate: true,
+			mutation: 0.0
+		}
+	}
+}
+
+/** Largest fraction of the difference between a cell and its neighbours that
+ * is allowed to diffuse in a single diffusion step. Anything up to one half
+ * is numerically stable, but staying well below that keeps the chemicals from
+ * oscillating between neighbouring cells. */
+pub const MAX_DIFFUSION_FRACTION: f32 = 0.25;
+
+/** Name of the field resource that may be listed in the diet of a species,
+ * alongside the names of other species. */
+pub const GRASS: &'static str = "Grass";
+
+/** Maximum number of species allowed in a simulation. */
+pub const MAX_SPECIES: usize = 32;
+
+/** Maximum number of rays an individual may cast under ray vision. This must
+ * match `NN_RAYS_MAX` in `Dataset.glslh`. */
+pub const MAX_RAYS: usize = 8;
+
 /** Settings controlling specific groups of individuals in the simulation. */
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "PascalCase")]
 pub struct Group {
+	/** Name of this species, by which other species refer to it in their
+	 * diets. Must be unique. */
+	pub name: String,
+	/** What individuals in this group feed on. Every entry is either the name
+	 * of a species or the name of a field resource, of which only [`GRASS`]
+	 * exists. A species may not feed on itself. */
+	pub diet: Vec<String>,
 	/** Number of individuals this group will start off with. */
 	pub individuals: u32,
 	/** Maximum number of individuals this groups will allow. */
 	pub budget: u32,
+	/** What happens to births once the group has reached its budget. */
+	#[serde(default)]
+	pub budget_policy: BudgetPolicy,
 	/** Spawn area for this group. */
 	pub spawn_range: [f32; 4],
 	/** Radius of vision in simulation board units. */
 	pub view_radius: f32,
+	/** Whether individuals sense the nearest predator, prey and member of
+	 * the group within their radius of vision, along with how crowded it is,
+	 * as inputs to their networks. */
+	#[serde(default)]
+	pub neighbour_inputs: bool,
+	/** How individuals see what is around them. */
+	#[serde(default)]
+	pub vision: Vision,
+	/** Rays cast by individuals under ray vision. */
+	#[serde(default)]
+	pub rays: Rays,
 	/** Amount of energy to consume while standing still. */
 	pub metabolism_min: f32,
 	/** Amount of energy to consume while running at max speed. */
@@ -37,14 +502,530 @@ pub struct Group {
 	pub reproduction_cost: f32,
 	/** Minimum energy needed for two parents to decide to reproduce. */
 	pub reproduction_min: f32,
-	/** Energy newborns of this group group start off with. */
+	/** Energy newborns of this group group start off with. Ignored when energy
+	 * conservation is on, as newborns then get what their parents paid. */
 	pub offspring_energy: f32,
+	/** Strategy used by individuals in this group to choose their mates. */
+	#[serde(default)]
+	pub mate_selection: MateSelection,
+	/** Number of candidates drawn at random for every tournament, when the
+	 * tournament mate selection strategy is in use. */
+	#[serde(default = "Group::default_tournament_size")]
+	pub tournament_size: u32,
+	/** Whether mating is restricted to partners within the mating radius of
+	 * each other, with offspring being born around the parent that initiated
+	 * reproduction, rather than in between both parents. */
+	#[serde(default)]
+	pub local_mating: bool,
+	/** Maximum distance between two partners when local mating is on. */
+	#[serde(default = "Group::default_mating_radius")]
+	pub mating_radius: f32,
+	/** Maximum distance from the initiating parent at which offspring are
+	 * born when local mating is on. */
+	#[serde(default = "Group::default_offspring_jitter")]
+	pub offspring_jitter: f32,
+	/** Where offspring are placed when they are born. */
+	#[serde(default)]
+	pub offspring_placement: OffspringPlacement,
+	/** Maximum distance from the mother at which offspring are placed when
+	 * they are placed around her. */
+	#[serde(default)]
+	pub scatter_radius: f32,
+	/** Time, in seconds of simulated time, both parents have to wait after
+	 * having reproduced before they may reproduce again. */
+	#[serde(default)]
+	pub reproduction_cooldown: f32,
+	/** Time, in seconds of simulated time, between the moment offspring are
+	 * conceived and the moment they are born. Until then, they take up room in
+	 * the group, but do nothing and cannot be seen. A value of zero means
+	 * offspring are born right away. */
+	#[serde(default)]
+	pub gestation: f32,
+	/** Fraction of the energy taken from grass that is actually gained by
+	 * the individual when energy conservation is on. The rest is lost. */
+	#[serde(default = "Group::default_efficiency")]
+	pub feeding_efficiency: f32,
+	/** Fraction of the energy paid by both parents that is passed on to the
+	 * offspring when energy conservation is on. The rest is lost. */
+	#[serde(default = "Group::default_efficiency")]
+	pub reproduction_efficiency: f32,
+	/** Maximum distance between a hunting individual and the prey it
+	 * attacks. Only used by groups that hunt. */
+	#[serde(default = "Group::default_attack_radius")]
+	pub attack_radius: f32,
+	/** Probability an attack succeeds, in which case the prey dies. Every
+	 * hunting individual attacks at most once per step. Only used by groups
+	 * that hunt. */
+	#[serde(default = "Group::default_capture_probability")]
+	pub capture_probability: f32,
+	/** Fraction of the energy of the prey gained by the individual that
+	 * killed it. Only used by groups that hunt. */
+	#[serde(default = "Group::default_energy_transfer")]
+	pub energy_transfer: f32,
+	/** Time, in seconds of simulated time, an individual spends handling and
+	 * digesting its prey after a kill, during which it may not hunt. Only used
+	 * by groups that hunt. */
+	#[serde(default)]
+	pub handling_time: f32,
+	/** Energy below which individuals go out hunting. Only used by groups
+	 * that hunt. */
+	#[serde(default = "Group::default_hunting_threshold")]
+	pub hunting_threshold: f32,
+	/** Minimum age, in seconds of simulated time, an individual needs to have
+	 * reached before it is allowed to reproduce. A value of zero lets
+	 * individuals reproduce at any age. */
+	#[serde(default)]
+	pub reproduction_age: f32,
+	/** Maximum age, in seconds of simulated time, individuals in this group
+	 * can reach before dying of old age. A value of zero disables death by old
+	 * age altogether. */
+	#[serde(default)]
+	pub lifespan: f32,
+	/** Increase in the metabolism of an individual for every second of
+	 * simulated time it has lived, as a fraction of its base metabolism. A
+	 * value of zero means metabolism does not change with age. */
+	#[serde(default)]
+	pub senescence: f32,
+	/** Temperature at which the metabolism of individuals in this group is
+	 * the lowest. */
+	#[serde(default)]
+	pub preferred_temperature: f32,
+	/** How far from their preferred temperature individuals in this group
+	 * may be before their metabolism doubles. Metabolism grows with the
+	 * square of the distance to the preferred temperature. A value of zero
+	 * means individuals do not feel the temperature at all. */
+	#[serde(default)]
+	pub temperature_tolerance: f32,
 	/** The signature pheromone composition for this group. This will be used as
 	 * the initial value for the chemical composition in the genes of all
 	 * individuals of the group. */
 	pub signature: Pheromone,
+	/** Maximum amount by which each chemical in the signature of a newborn
+	 * may deviate from the average of the signatures of its parents. A value
+	 * of zero means signatures are passed down unchanged. */
+	#[serde(default)]
+	pub signature_mutation: f32,
 	/** Whether to initialize the other parameters to random values. */
 	pub init_to_random: bool,
+	/** How individuals from outside of the simulation immigrate into this
+	 * group. */
+	#[serde(default)]
+	pub immigration: Immigration,
+	/** Genome file this group is seeded from, if any. */
+	#[serde(default)]
+	pub seed: Seed,
+}
+impl Group {
+	/** Whether individuals in this group feed on the given species or field
+	 * resource. */
+	pub fn eats(&self, food: &str) -> bool {
+		self.diet.iter().any(|entry| entry == food)
+	}
+
+	/** Whether individuals in this group feed on other species, in which case
+	 * they are said to hunt. */
+	pub fn hunts(&self) -> bool {
+		self.diet.iter().any(|entry| entry != GRASS)
+	}
+
+	/** Factor by which the metabolism of individuals in this group is scaled
+	 * at the given temperature, the same way `ThermalStress()` does in the
+	 * shaders. */
+	pub fn thermal_stress(&self, temperature: f32) -> f32 {
+		if self.temperature_tolerance <= 0.0 {
+			return 1.0
+		}
+
+		let distance = (temperature - self.preferred_temperature) / self.temperature_tolerance;
+		1.0 + distance * distance
+	}
+
+	/** Number of candidates drawn for every tournament, when left out of the
+	 * settings. */
+	fn default_tournament_size() -> u32 {
+		4
+	}
+
+	/** Maximum distance between two partners, when left out of the
+	 * settings. */
+	fn default_mating_radius() -> f32 {
+		5.0
+	}
+
+	/** Maximum distance from the initiating parent offspring are born at,
+	 * when left out of the settings. */
+	fn default_offspring_jitter() -> f32 {
+		1.0
+	}
+
+	/** Feeding and reproduction efficiencies, when left out of the settings,
+	 * under which no energy is lost. */
+	fn default_efficiency() -> f32 {
+		1.0
+	}
+
+	/** Maximum distance between a hunter and its prey, when left out of the
+	 * settings. */
+	fn default_attack_radius() -> f32 {
+		2.0
+	}
+
+	/** Probability an attack succeeds, when left out of the settings, under
+	 * which every attack does. */
+	fn default_capture_probability() -> f32 {
+		1.0
+	}
+
+	/** Fraction of the energy of the prey gained by its hunter, when left out
+	 * of the settings. */
+	fn default_energy_transfer() -> f32 {
+		0.5
+	}
+
+	/** Energy below which individuals go out hunting, when left out of the
+	 * settings, under which they hunt until they are full. */
+	fn default_hunting_threshold() -> f32 {
+		1.0
+	}
+}
+
+/** Strategies for choosing the mate of an individual that has decided to
+ * reproduce. Only individuals eligible for reproduction, that is, individuals
+ * with enough energy and old enough, are ever considered as candidates. */
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub enum MateSelection {
+	/** Mate with the individual with the most energy in the whole group. */
+	GlobalBest = 0,
+	/** Mate with the closest individual within the radius of vision. */
+	Nearest = 1,
+	/** Mate with the individual with the most energy out of a number of
+	 * candidates drawn at random from the group. */
+	Tournament = 2,
+	/** Mate with an individual drawn at random from the group, with the
+	 * probability of each one being picked proportional to its energy. */
+	Roulette = 3,
+}
+impl Default for MateSelection {
+	fn default() -> Self {
+		MateSelection::GlobalBest
+	}
+}
+
+/** How individuals see what is around them. */
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub enum Vision {
+	/** Sense the gradients of the chemicals and of the grass all around. */
+	Gradients = 0,
+	/** Cast rays spread out over the field of view, ahead of the direction
+	 * of the last move, each sensing the first thing it hits and the field
+	 * it passes over. */
+	Rays = 1,
+}
+impl Default for Vision {
+	fn default() -> Self {
+		Vision::Gradients
+	}
+}
+
+/** Settings for the rays individuals cast under ray vision. */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct Rays {
+	/** Number of rays cast by every individual, up to [`MAX_RAYS`]. */
+	pub count: u32,
+	/** Angle, in degrees, the rays are evenly spread out over, centered on
+	 * the direction of the last move. */
+	pub field_of_view: f32,
+	/** Width of every ray, in simulation board units. Individuals are hit by
+	 * a ray when they are within half of it from its center line. */
+	pub width: f32,
+}
+impl Default for Rays {
+	fn default() -> Self {
+		Self {
+			count: 5,
+			field_of_view: 120.0,
+			width: 1.0
+		}
+	}
+}
+
+/** Settings for the spatial grid individuals are sorted into on every step,
+ * such that those around any given point can be found quickly. */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct SpatialGrid {
+	/** Width and height of every cell of the grid, in simulation board
+	 * units. */
+	pub cell_size: f32,
+	/** Maximum number of individuals every cell holds in the GPU backend.
+	 * Individuals past it are left out of the grid. */
+	pub cell_capacity: u32,
+}
+impl SpatialGrid {
+	/** Number of cells across and down the grid over the given plane. */
+	pub fn size(&self, width: f32, height: f32) -> [u32; 2] {
+		let cell = self.cell_size.max(f32::EPSILON);
+		[
+			(width / cell).ceil().max(1.0) as u32,
+			(height / cell).ceil().max(1.0) as u32
+		]
+	}
+}
+impl Default for SpatialGrid {
+	fn default() -> Self {
+		Self {
+			cell_size: 5.0,
+			cell_capacity: 32
+		}
+	}
+}
+
+/** Settings for the soft collisions between individuals. */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct Crowding {
+	/** Whether individuals that get too close to each other are pushed
+	 * apart. When this is off, individuals are points that never collide. */
+	pub enabled: bool,
+	/** Distance, in simulation board units, under which two individuals push
+	 * each other apart. */
+	pub separation_radius: f32,
+	/** Speed, in simulation board units per second, at which an individual
+	 * right on top of another one is pushed away from it. The push fades out
+	 * linearly with distance, down to nothing at the separation radius. */
+	pub separation_force: f32,
+}
+impl Default for Crowding {
+	fn default() -> Self {
+		Self {
+			enabled: false,
+			separation_radius: 1.0,
+			separation_force: 5.0
+		}
+	}
+}
+
+/** Settings for the genetic diversity metrics reported along with the
+ * population statistics. */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct Diversity {
+	/** Whether the genetic diversity of every species is measured and
+	 * reported along with the population statistics. */
+	pub enabled: bool,
+	/** Largest distance between the genomes of two individuals, measured
+	 * over their weights and biases, for them to be clustered into the same
+	 * genetic species. */
+	pub threshold: f32,
+	/** Largest number of individuals of every species, drawn at random, the
+	 * pairwise distances are measured over, which takes time that grows with
+	 * the square of this number. Zero measures every individual. */
+	pub sample_size: u32,
+}
+impl Default for Diversity {
+	fn default() -> Self {
+		Self {
+			enabled: false,
+			threshold: 1.0,
+			sample_size: 256
+		}
+	}
+}
+
+/** Ways the temperature is laid out over the plane. */
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub enum TemperatureSource {
+	/** The low temperature everywhere. */
+	Uniform,
+	/** Going from the low temperature to the high one across the plane,
+	 * along the direction given by the angle of the gradient. */
+	Gradient,
+	/** Smooth random noise between the low and the high temperatures, with
+	 * warm and cold spots about as big as the scale of the noise. */
+	Noise,
+	/** Loaded from a map, with black pixels at the low temperature and white
+	 * pixels at the high one, the same way the terrain maps are. */
+	Map,
+}
+impl Default for TemperatureSource {
+	fn default() -> Self {
+		TemperatureSource::Uniform
+	}
+}
+
+/** Settings for the temperature of the plane. The layout of the temperature
+ * over the plane never changes, but the temperature all over the plane gets
+ * scaled by its schedule, if any. */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct Temperature {
+	/** How the temperature is laid out over the plane. */
+	pub source: TemperatureSource,
+	/** Lowest temperature on the plane. */
+	pub low: f32,
+	/** Highest temperature on the plane. */
+	pub high: f32,
+	/** Direction, in degrees, in which the temperature rises under the
+	 * gradient source, clockwise from the horizontal axis, such that ninety
+	 * degrees goes from the top of the plane down to its bottom. */
+	pub angle: f32,
+	/** Size, in simulation board units, of the warm and cold spots under the
+	 * noise source. */
+	pub noise_scale: f32,
+	/** Path to the map the temperature is loaded from under the map
+	 * source. */
+	pub map: Option<String>,
+	/** Whether individuals sense the temperature under them, relative to
+	 * the lowest and highest temperatures, as an input to their networks. */
+	pub input: bool,
+}
+impl Temperature {
+	/** Temperature at the given level between the lowest and the highest
+	 * temperatures, from zero to one. */
+	pub fn at_level(&self, level: f32) -> f32 {
+		self.low + (self.high - self.low) * level
+	}
+
+	/** Level of the given temperature between the lowest and the highest
+	 * temperatures, as sensed by individuals, the same way
+	 * `TemperatureLevel()` does in the shaders. */
+	pub fn level(&self, temperature: f32) -> f32 {
+		(temperature - self.low) / (self.high - self.low).max(f32::EPSILON)
+	}
+}
+impl Default for Temperature {
+	fn default() -> Self {
+		Self {
+			source: TemperatureSource::Uniform,
+			low: 20.0,
+			high: 20.0,
+			angle: 90.0,
+			noise_scale: 20.0,
+			map: None,
+			input: false
+		}
+	}
+}
+
+/** What happens at the edges of the simulation plane. Movement, the sensing
+ * of the field and of other individuals and the spreading of the chemicals all
+ * follow the same boundary, in both modes. */
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub enum Boundary {
+	/** The plane wraps around, with its left edge joined to its right edge
+	 * and its top edge joined to its bottom edge, such that it has no edges
+	 * at all. */
+	Torus = 0,
+	/** The edges of the plane are walls. Individuals stop at them, they
+	 * block vision and no chemicals spread through them. */
+	Clamp = 1,
+	/** Same as `Clamp`, except that individuals running into an edge bounce
+	 * off of it, ending up as far back into the plane as they would have
+	 * gone past it. */
+	Reflect = 2,
+}
+impl Boundary {
+	/** Brings the given position back onto a plane of the given size, the
+	 * same way `Evo_Bound()` does in the shaders. */
+	pub fn bound(self, position: [f32; 2], size: [f32; 2]) -> [f32; 2] {
+		let bound = |p: f32, s: f32| match self {
+			Boundary::Torus => p.rem_euclid(s),
+			Boundary::Clamp => p.clamp(0.0, s),
+			/* Mirrored back every other width of the plane. */
+			Boundary::Reflect => s - (p.rem_euclid(2.0 * s) - s).abs()
+		};
+
+		[bound(position[0], size[0]), bound(position[1], size[1])]
+	}
+
+	/** Offset from one position to another on a plane of the given size.
+	 * Under the torus boundary, this is the shortest one, which may go
+	 * across the edges of the plane. Same as `Evo_Offset()`. */
+	pub fn offset(self, from: [f32; 2], to: [f32; 2], size: [f32; 2]) -> [f32; 2] {
+		let mut offset = [to[0] - from[0], to[1] - from[1]];
+		if self == Boundary::Torus {
+			for axis in 0..2 {
+				offset[axis] -= size[axis] * (offset[axis] / size[axis]).round();
+			}
+		}
+
+		offset
+	}
+
+	/** Distance between two positions on a plane of the given size, along
+	 * their offset. */
+	pub fn distance(self, from: [f32; 2], to: [f32; 2], size: [f32; 2]) -> f32 {
+		let [x, y] = self.offset(from, to, size);
+		(x * x + y * y).sqrt()
+	}
+
+	/** Range of the indices of the cells between the given lowest and
+	 * highest indices, inclusive, along an axis the given number of cells
+	 * long. These may fall off the plane under the torus boundary, in which
+	 * case they wrap around, at most once, and must be passed through
+	 * [`Boundary::cell`]. Same as `Evo_BoundSpan()`. */
+	pub fn span(self, low: i64, high: i64, count: u32) -> std::ops::RangeInclusive<i64> {
+		let count = count as i64;
+		match self {
+			Boundary::Torus => low..=high.min(low + count - 1),
+			_ => low.max(0)..=high.min(count - 1)
+		}
+	}
+
+	/** Cell at the given index along an axis the given number of cells long,
+	 * wrapped around under the torus boundary, or nothing if the index falls
+	 * off the plane under any other boundary. Same as `Evo_BoundCell()`. */
+	pub fn cell(self, index: i64, count: u32) -> Option<u32> {
+		let count = count as i64;
+		match self {
+			Boundary::Torus => Some(index.rem_euclid(count) as u32),
+			_ if index >= 0 && index < count => Some(index as u32),
+			_ => None
+		}
+	}
+}
+impl Default for Boundary {
+	fn default() -> Self {
+		Boundary::Torus
+	}
+}
+
+/** Where offspring are placed when they are conceived. The parent that
+ * initiated the reproduction is said to be the mother. */
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub enum OffspringPlacement {
+	/** Somewhere in between both parents or, when local mating is on, around
+	 * the mother, at most the offspring jitter away from her. */
+	Midpoint = 0,
+	/** Around the mother, at most the scatter radius away from her,
+	 * regardless of whether local mating is on. */
+	Mother = 1,
+}
+impl Default for OffspringPlacement {
+	fn default() -> Self {
+		OffspringPlacement::Midpoint
+	}
+}
+
+/** What happens to a birth in a group that has reached its budget. */
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub enum BudgetPolicy {
+	/** The birth does not happen, and the parents pay nothing for it. */
+	Reject = 0,
+	/** The newborn takes the place of the individual with the least energy
+	 * in the group. */
+	ReplaceLowestEnergy = 1,
+	/** The newborn takes the place of the oldest individual in the group. */
+	ReplaceOldest = 2,
+	/** The newborn takes the place of an individual drawn at random from the
+	 * group. */
+	ReplaceRandom = 3,
+}
+impl Default for BudgetPolicy {
+	fn default() -> Self {
+		BudgetPolicy::Reject
+	}
 }
 
 /** Settings controlling all the parameters for the simulation. */
@@ -64,6 +1045,9 @@ pub struct Simulation {
 	pub plane_width: f32,
 	/** Height of the simulation plane. */
 	pub plane_height: f32,
+	/** What happens at the edges of the simulation plane. */
+	#[serde(default)]
+	pub boundary: Boundary,
 
 	/** Granularity of the pheromone cells on the horizontal axis of the plane.
 	 *
@@ -83,15 +1067,210 @@ pub struct Simulation {
 	 */
 	pub vertical_granularity: u32,
 
-	/** Growth rate of the grass on the field, in units per second. */
+	/** Growth rate of the grass on the field, in units per second. Under the
+	 * logistic regrowth models, this is the intrinsic growth rate, instead. */
 	pub growth_rate: f32,
-	/** Decomposition rate of the chemicals on the field, in units per second. */
-	pub decomposition_rate: f32,
+	/** How the grass on the field grows back. */
+	#[serde(default)]
+	pub regrowth: Regrowth,
+	/** Decomposition rate of each of the chemicals on the field, per second.
+	 * Under linear decay, this is the amount of the chemical that decomposes
+	 * every second. Under exponential decay, it is the rate constant of the
+	 * decay, such that the chemical decomposes to about a third of what it
+	 * was after one second divided by the rate. */
+	pub decomposition_rate: Chemicals,
+	/** How each of the chemicals on the field decomposes. */
+	#[serde(default)]
+	pub decay_mode: Chemicals<DecayMode>,
+	/** Rate at which each of the chemicals on the field spreads out to the
+	 * neighbouring cells, as a diffusion coefficient, in simulation board
+	 * units squared per second. Zero keeps a chemical from spreading. */
+	#[serde(default)]
+	pub diffusion_rate: Chemicals,
+	/** Schedules of the environment parameters that change over simulated
+	 * time, such as with the seasons. */
+	#[serde(default)]
+	pub schedules: Schedules,
+
+	/** Whether energy is conserved as it moves between the field, prey,
+	 * parents and offspring, according to the efficiencies of each group.
+	 * When this is off, feeding and reproduction work off of fixed amounts of
+	 * energy, which may create energy out of nothing. */
+	#[serde(default)]
+	pub energy_conservation: bool,
+
+	/** Maps the terrain of the simulation plane is loaded from. When no
+	 * maps are given, the plane is open and equally fertile everywhere. */
+	#[serde(default)]
+	pub terrain: TerrainMaps,
+
+	/** Events scripted to happen during the simulation, in any order. */
+	#[serde(default)]
+	pub events: Vec<Event>,
+
+	/** Spatial grid individuals are sorted into on every step. */
+	#[serde(default)]
+	pub grid: SpatialGrid,
+
+	/** Soft collisions between individuals. */
+	#[serde(default)]
+	pub crowding: Crowding,
+
+	/** Temperature of the plane. */
+	#[serde(default)]
+	pub temperature: Temperature,
+
+	/** Parameters for every species in the simulation. Species are
+	 * simulated in the order they are declared in. */
+	pub species: Vec<Group>,
+
+	/** Interval, in seconds of real time, between two consecutive reports of
+	 * the population statistics. A value of zero disables the reports. */
+	#[serde(default)]
+	pub statistics_interval: f32,
+
+	/** Genetic diversity metrics reported along with the population
+	 * statistics. */
+	#[serde(default)]
+	pub diversity: Diversity,
+
+	/** Path to the file the births and deaths of all individuals are logged
+	 * to, if any. */
+	#[serde(default)]
+	pub lineage_log: Option<String>,
+}
+impl Simulation {
+	/** Width and height of the simulation plane. */
+	pub fn plane_size(&self) -> [f32; 2] {
+		[self.plane_width, self.plane_height]
+	}
+
+	/** Number of steps the diffusion of the chemicals is split into on every
+	 * step of the simulation, such that none of them moves more than
+	 * [`MAX_DIFFUSION_FRACTION`] of the difference between two cells, even
+	 * for steps as long as the maximum discrete time. */
+	pub fn diffusion_steps(&self) -> u32 {
+		let cell_x = self.plane_width / self.horizontal_granularity as f32;
+		let cell_y = self.plane_height / self.vertical_granularity as f32;
+		let fastest = self.diffusion_rate.to_array()
+			.iter()
+			.fold(0.0_f32, |fastest, rate| fastest.max(*rate));
+
+		let fraction = fastest * self.max_discrete_time
+			* (1.0 / (cell_x * cell_x) + 1.0 / (cell_y * cell_y));
+		(fraction / MAX_DIFFUSION_FRACTION).ceil().max(1.0) as u32
+	}
+
+	/** Changes the base value of the given environment parameter. Chemical
+	 * parameters are changed for all of the chemicals at once. */
+	pub fn set(&mut self, parameter: Parameter, value: f32) {
+		let chemicals = Chemicals {
+			red: value,
+			green: value,
+			blue: value
+		};
+
+		match parameter {
+			Parameter::GrowthRate => self.growth_rate = value,
+			Parameter::DecompositionRate => self.decomposition_rate = chemicals,
+			Parameter::DiffusionRate => self.diffusion_rate = chemicals
+		}
+	}
+
+	/** Indices of the species individuals of the given species feed on. */
+	pub fn prey_of(&self, species: usize) -> impl Iterator<Item = usize> + '_ {
+		let group = &self.species[species];
+		self.species.iter()
+			.enumerate()
+			.filter(move |(_, prey)| group.eats(&prey.name))
+			.map(|(index, _)| index)
+	}
+
+	/** Indices of the species that feed on the species at the given index. */
+	pub fn predators_of(&self, species: usize) -> impl Iterator<Item = usize> + '_ {
+		let group = &self.species[species];
+		self.species.iter()
+			.enumerate()
+			.filter(move |(_, predator)| predator.eats(&group.name))
+			.map(|(index, _)| index)
+	}
+
+	/** Checks the food web for inconsistencies, such as diets that refer to
+	 * species that do not exist. */
+	pub fn validate(&self) -> Result<(), Box<dyn Error>> {
+		if self.species.is_empty() {
+			return Err("there must be at least one species".into())
+		}
+		if self.species.len() > MAX_SPECIES {
+			return Err(format!("there may be at most {} species, but {} were \
+				declared", MAX_SPECIES, self.species.len()).into())
+		}
+
+		for (i, group) in self.species.iter().enumerate() {
+			if group.name == GRASS {
+				return Err(format!("species may not be named after the {} \
+					field resource", GRASS).into())
+			}
+			if self.species[..i].iter().any(|other| other.name == group.name) {
+				return Err(format!("species name {} is used more than once",
+					group.name).into())
+			}
+			if group.vision == Vision::Rays && !(1..=MAX_RAYS).contains(&(group.rays.count as usize)) {
+				return Err(format!("species {} must cast between 1 and {} rays",
+					group.name, MAX_RAYS).into())
+			}
+			for food in &group.diet {
+				if *food == group.name {
+					return Err(format!("species {} may not feed on itself",
+						group.name).into())
+				}
+				if *food != GRASS && !self.species.iter().any(|other| other.name == *food) {
+					return Err(format!("species {} feeds on {}, which is \
+						neither a species nor a field resource",
+						group.name, food).into())
+				}
+			}
+		}
 
-	/** Parameters for the herbivore group. */
-	pub herbivores: Group,
-	/** Parameter for the predator group. */
-	pub predators: Group,
+		Ok(())
+	}
+}
+
+/** Settings for the terrain of the simulation plane. Every map is the path
+ * to an image, in either the PNG or the PGM formats, of which only the gray
+ * levels are used. Maps get stretched over the whole plane, regardless of
+ * their size. */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct TerrainMaps {
+	/** Map of the impassable walls on the plane. Dark pixels are walls,
+	 * bright pixels are open ground. */
+	pub walls: Option<String>,
+	/** Map of how hard it is to move across the plane. Black pixels are as
+	 * easy to cross as open ground, white pixels slow individuals down by the
+	 * maximum movement cost. */
+	pub movement_cost: Option<String>,
+	/** Map of the fertility of the plane, which scales the growth rate of the
+	 * grass. Black pixels are barren, white pixels grow grass at the full
+	 * growth rate. */
+	pub fertility: Option<String>,
+	/** Factor by which the speed of individuals is divided on the cells that
+	 * are the hardest to cross. */
+	pub max_movement_cost: f32,
+	/** Whether individuals sense how close they are to the nearest wall,
+	 * relative to their radius of vision, as an input to their networks. */
+	pub wall_proximity_input: bool,
+}
+impl Default for TerrainMaps {
+	fn default() -> Self {
+		Self {
+			walls: None,
+			movement_cost: None,
+			fertility: None,
+			max_movement_cost: 4.0,
+			wall_proximity_input: false
+		}
+	}
 }
 
 /** Modes of presentation for the swapchain.
@@ -135,6 +1314,63 @@ pub struct Window {
 	pub backends: Vec<Backend>
 }
 
+/** Measures of how well an individual did over its life, by which the hall of
+ * fame of a training run is ranked. */
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub enum Fitness {
+	/** Amount of simulated time the individual lived for. */
+	Lifetime,
+	/** Number of offspring the individual had. */
+	Offspring,
+	/** Amount of energy the individual gained from feeding. */
+	Energy,
+}
+
+/** Settings for offline training, which runs the simulation on the CPU with
+ * no window, as fast as possible, keeping the best genomes it comes across. */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct Training {
+	/** Whether to train instead of running the simulation in a window. */
+	pub enabled: bool,
+	/** Number of independent episodes to run, each one starting the
+	 * simulation over from scratch. */
+	pub episodes: u32,
+	/** Largest number of steps every episode runs for. Episodes also end
+	 * once the trained species has died out. */
+	pub steps: u64,
+	/** Amount of simulated time, in seconds, every step covers. */
+	pub time_step: f32,
+	/** Seed of the first episode. Every following episode is seeded with the
+	 * seed of the one before it plus one. */
+	pub seed: u64,
+	/** Name of the species whose genomes are kept. The first species is
+	 * trained when none is given. */
+	pub species: String,
+	/** Measure by which the hall of fame is ranked. */
+	pub fitness: Fitness,
+	/** Largest number of genomes kept in the hall of fame. */
+	pub hall_of_fame: u32,
+	/** Path to the genome file the hall of fame is written to, from the best
+	 * genome down, after every episode. */
+	pub archive: String,
+}
+impl Default for Training {
+	fn default() -> Self {
+		Self {
+			enabled: false,
+			episodes: 10,
+			steps: 50000,
+			time_step: 0.02,
+			seed: 0,
+			species: String::new(),
+			fitness: Fitness::Offspring,
+			hall_of_fame: 50,
+			archive: "hall_of_fame.toml".into()
+		}
+	}
+}
+
 /** Application settings. */
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "PascalCase")]
@@ -142,7 +1378,10 @@ pub struct Preferences {
 	/** Section for the window and display settings. */
 	pub window: Window,
 	/** Section for the simulation settings. */
-	pub simulation: Simulation
+	pub simulation: Simulation,
+	/** Section for the offline training settings. */
+	#[serde(default)]
+	pub training: Training
 }
 impl Preferences {
 	/** Tries to load the application preferences from the default location. */
@@ -151,8 +1390,10 @@ impl Preferences {
 		let mut data = Vec::new();
 		file.read_to_end(&mut data)?;
 
-		toml::from_slice(&data[..])
-			.map_err(Into::into)
+		let prefs: Self = toml::from_slice(&data[..])?;
+		prefs.simulation.validate()?;
+
+		Ok(prefs)
 	}
 }
 impl Default for Preferences {
@@ -171,47 +1412,315 @@ impl Default for Preferences {
 				max_discrete_time: 0.5,
 				plane_width: 100.0,
 				plane_height: 100.0,
+				boundary: Default::default(),
 				horizontal_granularity: 100,
 				vertical_granularity: 100,
 				growth_rate: 0.1,
-				decomposition_rate: 0.1,
-				herbivores: Group {
+				regrowth: Default::default(),
+				decomposition_rate: Chemicals {
+					red: 0.1,
+					green: 0.1,
+					blue: 0.1
+				},
+				decay_mode: Default::default(),
+				diffusion_rate: Chemicals {
+					red: 1.0,
+					green: 1.0,
+					blue: 1.0
+				},
+				schedules: Default::default(),
+				energy_conservation: false,
+				terrain: Default::default(),
+				events: Vec::new(),
+				grid: Default::default(),
+				crowding: Default::default(),
+				temperature: Default::default(),
+				species: vec![Group {
+					name: "Herbivores".into(),
+					diet: vec![GRASS.into()],
 					individuals: 100,
 					budget: 1024,
+					budget_policy: BudgetPolicy::Reject,
 					spawn_range: [0.0, 100.0, 0.0, 100.0],
 					view_radius: 1.0,
+					neighbour_inputs: false,
+					vision: Vision::Gradients,
+					rays: Default::default(),
 					metabolism_min: 0.01,
 					metabolism_max: 0.05,
 					max_speed: 10.0,
 					reproduction_cost: 0.1,
 					reproduction_min: 0.5,
 					offspring_energy: 1.0,
+					mate_selection: MateSelection::GlobalBest,
+					tournament_size: 4,
+					local_mating: false,
+					mating_radius: 5.0,
+					offspring_jitter: 1.0,
+					offspring_placement: OffspringPlacement::Midpoint,
+					scatter_radius: 1.0,
+					reproduction_cooldown: 0.0,
+					gestation: 0.0,
+					feeding_efficiency: 0.8,
+					reproduction_efficiency: 0.9,
+					attack_radius: 2.0,
+					capture_probability: 1.0,
+					energy_transfer: 0.5,
+					handling_time: 0.0,
+					hunting_threshold: 1.0,
+					reproduction_age: 0.0,
+					lifespan: 0.0,
+					senescence: 0.0,
+					preferred_temperature: 20.0,
+					temperature_tolerance: 0.0,
 					signature: Pheromone {
 						red:   0.0,
 						green: 1.0,
 						blue:  1.0
 					},
-					init_to_random: true
-				},
-				predators: Group {
+					signature_mutation: 0.05,
+					init_to_random: true,
+					immigration: Default::default(),
+					seed: Default::default()
+				}, Group {
+					name: "Predators".into(),
+					diet: vec!["Herbivores".into()],
 					individuals: 10,
 					budget: 1024,
+					budget_policy: BudgetPolicy::Reject,
 					spawn_range: [0.0, 100.0, 0.0, 100.0],
 					view_radius: 1.0,
+					neighbour_inputs: false,
+					vision: Vision::Gradients,
+					rays: Default::default(),
 					metabolism_min: 0.02,
 					metabolism_max: 0.10,
 					max_speed: 10.0,
 					reproduction_cost: 0.4,
 					reproduction_min: 0.8,
 					offspring_energy: 1.0,
+					mate_selection: MateSelection::GlobalBest,
+					tournament_size: 4,
+					local_mating: false,
+					mating_radius: 5.0,
+					offspring_jitter: 1.0,
+					offspring_placement: OffspringPlacement::Midpoint,
+					scatter_radius: 1.0,
+					reproduction_cooldown: 0.0,
+					gestation: 0.0,
+					feeding_efficiency: 0.8,
+					reproduction_efficiency: 0.9,
+					attack_radius: 2.0,
+					capture_probability: 1.0,
+					energy_transfer: 0.5,
+					handling_time: 0.0,
+					hunting_threshold: 1.0,
+					reproduction_age: 0.0,
+					lifespan: 0.0,
+					senescence: 0.0,
+					preferred_temperature: 20.0,
+					temperature_tolerance: 0.0,
 					signature: Pheromone {
 						red:   1.0,
 						green: 0.0,
 						blue:  0.0
 					},
-					init_to_random: true
-				}
-			}
+					signature_mutation: 0.05,
+					init_to_random: true,
+					immigration: Default::default(),
+					seed: Default::default()
+				}],
+				statistics_interval: 5.0,
+				diversity: Default::default(),
+				lineage_log: None
+			},
+			training: Default::default()
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/** Asserts that two amounts are the same, up to rounding errors. */
+	fn assert_close(left: f32, right: f32) {
+		assert!((left - right).abs() < 1e-5, "{} is not {}", left, right);
+	}
+
+	/** Regrowth settings of the default values following the given model. */
+	fn regrowth(model: RegrowthModel) -> Regrowth {
+		Regrowth { model, ..Default::default() }
+	}
+
+	#[test]
+	fn linear_regrowth() {
+		let regrowth = regrowth(RegrowthModel::Linear);
+		let mut delay = 0.0;
+
+		assert_close(regrowth.regrow(0.2, 0.0, &mut delay, 0.1, 2.0), 0.4);
+		assert_close(regrowth.regrow(0.0, 1.0, &mut delay, 0.1, 2.0), 0.2);
+		assert_close(regrowth.regrow(0.9, 0.0, &mut delay, 0.1, 2.0), 1.0);
+	}
+
+	#[test]
+	fn logistic_regrowth() {
+		let regrowth = regrowth(RegrowthModel::Logistic);
+		let mut delay = 0.0;
+
+		/* Grazed down cells grow back from the seed density. */
+		assert_close(regrowth.regrow(0.0, 0.0, &mut delay, 1.0, 1.0), regrowth.seed_density);
+		assert_close(regrowth.regrow(0.5, 0.0, &mut delay, 1.0, 1.0), 0.75);
+		assert_close(regrowth.regrow(1.0, 0.0, &mut delay, 1.0, 1.0), 1.0);
+	}
+
+	#[test]
+	fn patches_regrowth() {
+		let regrowth = regrowth(RegrowthModel::Patches);
+		let mut delay = 0.0;
+
+		/* Cells with no grass in or around them stay bare. */
+		assert_close(regrowth.regrow(0.0, 0.0, &mut delay, 1.0, 1.0), 0.0);
+		assert_close(regrowth.regrow(0.0, 0.4, &mut delay, 1.0, 1.0), 0.4 * regrowth.spread);
+		assert_close(regrowth.regrow(0.5, 0.0, &mut delay, 1.0, 1.0), 0.75);
+	}
+
+	#[test]
+	fn delayed_regrowth() {
+		let regrowth = regrowth(RegrowthModel::Delayed);
+		let mut delay = 3.0;
+
+		assert_close(regrowth.regrow(0.0, 0.0, &mut delay, 0.1, 2.0), 0.0);
+		assert_close(delay, 1.0);
+
+		/* Only the second after the delay is up counts. */
+		assert_close(regrowth.regrow(0.0, 0.0, &mut delay, 0.1, 2.0), 0.1);
+		assert_close(delay, 0.0);
+
+		assert_close(regrowth.regrow(0.1, 0.0, &mut delay, 0.1, 2.0), 0.3);
+		assert_close(delay, 0.0);
+	}
+
+	/** Schedule of the given kind through the given keyframes, repeating
+	 * every given period. */
+	fn keyframes(kind: ScheduleKind, period: f32, keyframes: &[[f32; 2]]) -> Schedule {
+		Schedule { kind, period, keyframes: keyframes.to_vec(), ..Default::default() }
+	}
+
+	#[test]
+	fn default_schedule() {
+		let schedule = Schedule::default();
+
+		assert_close(schedule.factor(0.0), 1.0);
+		assert_close(schedule.factor(123.0), 1.0);
+	}
+
+	#[test]
+	fn sine_schedule() {
+		let mut schedule = Schedule { period: 4.0, amplitude: 0.5, ..Default::default() };
+
+		assert_close(schedule.factor(0.0), 1.0);
+		assert_close(schedule.factor(1.0), 1.5);
+		assert_close(schedule.factor(3.0), 0.5);
+		assert_close(schedule.factor(5.0), 1.5);
+
+		schedule.phase = 0.25;
+		assert_close(schedule.factor(0.0), 1.5);
+	}
+
+	#[test]
+	fn factors_are_never_negative() {
+		let schedule = Schedule { period: 4.0, amplitude: 2.0, ..Default::default() };
+		assert_close(schedule.factor(3.0), 0.0);
+
+		let schedule = keyframes(ScheduleKind::Keyframes, 0.0, &[[0.0, 1.0], [10.0, -1.0]]);
+		assert_close(schedule.factor(10.0), 0.0);
+	}
+
+	#[test]
+	fn keyframes_schedule() {
+		let schedule = keyframes(ScheduleKind::Keyframes, 0.0, &[[0.0, 1.0], [10.0, 3.0]]);
+
+		assert_close(schedule.factor(-1.0), 1.0);
+		assert_close(schedule.factor(0.0), 1.0);
+		assert_close(schedule.factor(5.0), 2.0);
+		/* Holds the last keyframe without a period. */
+		assert_close(schedule.factor(20.0), 3.0);
+
+		let schedule = keyframes(ScheduleKind::Keyframes, 20.0, &[[0.0, 1.0], [10.0, 3.0]]);
+		assert_close(schedule.factor(25.0), 2.0);
+	}
+
+	#[test]
+	fn step_schedule() {
+		let schedule = keyframes(ScheduleKind::Step, 0.0, &[[0.0, 1.0], [10.0, 3.0]]);
+
+		assert_close(schedule.factor(5.0), 1.0);
+		assert_close(schedule.factor(10.0), 3.0);
+		assert_close(schedule.factor(20.0), 3.0);
+	}
+
+	#[test]
+	fn schedule_without_keyframes() {
+		let schedule = keyframes(ScheduleKind::Keyframes, 0.0, &[]);
+		assert_close(schedule.factor(5.0), 1.0);
+	}
+
+	const PLANE: [f32; 2] = [100.0, 100.0];
+
+	/** Asserts that two positions are the same, up to rounding errors. */
+	fn assert_close_position(left: [f32; 2], right: [f32; 2]) {
+		assert_close(left[0], right[0]);
+		assert_close(left[1], right[1]);
+	}
+
+	#[test]
+	fn bound_within_the_plane() {
+		for &boundary in [Boundary::Torus, Boundary::Clamp, Boundary::Reflect].iter() {
+			assert_close_position(boundary.bound([30.0, 70.0], PLANE), [30.0, 70.0]);
+		}
+	}
+
+	#[test]
+	fn torus_wraps() {
+		assert_close_position(Boundary::Torus.bound([105.0, -5.0], PLANE), [5.0, 95.0]);
+		assert_close_position(Boundary::Torus.bound([250.0, 100.0], PLANE), [50.0, 0.0]);
+
+		/* The shortest way goes across the edge. */
+		assert_close_position(Boundary::Torus.offset([95.0, 50.0], [5.0, 50.0], PLANE), [10.0, 0.0]);
+		assert_close_position(Boundary::Torus.offset([50.0, 2.0], [50.0, 98.0], PLANE), [0.0, -4.0]);
+		assert_close(Boundary::Torus.distance([95.0, 50.0], [5.0, 50.0], PLANE), 10.0);
+
+		assert_eq!(Boundary::Torus.span(-2, 3, 10), -2..=3);
+		assert_eq!(Boundary::Torus.span(-5, 20, 10), -5..=4);
+		assert_eq!(Boundary::Torus.cell(-1, 10), Some(9));
+		assert_eq!(Boundary::Torus.cell(10, 10), Some(0));
+		assert_eq!(Boundary::Torus.cell(3, 10), Some(3));
+	}
+
+	#[test]
+	fn clamp_stops_at_the_edges() {
+		assert_close_position(Boundary::Clamp.bound([105.0, -5.0], PLANE), [100.0, 0.0]);
+		assert_close_position(Boundary::Clamp.bound([250.0, 100.0], PLANE), [100.0, 100.0]);
+	}
+
+	#[test]
+	fn reflect_bounces_off_the_edges() {
+		assert_close_position(Boundary::Reflect.bound([105.0, -5.0], PLANE), [95.0, 5.0]);
+		assert_close_position(Boundary::Reflect.bound([250.0, 100.0], PLANE), [50.0, 100.0]);
+	}
+
+	#[test]
+	fn walls_do_not_wrap() {
+		for &boundary in [Boundary::Clamp, Boundary::Reflect].iter() {
+			assert_close_position(boundary.offset([95.0, 50.0], [5.0, 50.0], PLANE), [-90.0, 0.0]);
+			assert_close(boundary.distance([95.0, 50.0], [5.0, 50.0], PLANE), 90.0);
+
+			assert_eq!(boundary.span(-2, 3, 10), 0..=3);
+			assert_eq!(boundary.span(8, 12, 10), 8..=9);
+			assert_eq!(boundary.cell(-1, 10), None);
+			assert_eq!(boundary.cell(10, 10), None);
+			assert_eq!(boundary.cell(3, 10), Some(3));
 		}
 	}
 }
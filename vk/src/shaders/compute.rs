@@ -1,55 +1,37 @@
 use wgpu::ShaderModuleSource;
 
-/** Compute shaders for the herbivore group. */
-pub mod herbivores {
+/** Compute shaders that run once for every species, with the parameters of
+ * the species being picked by the dispatch parameters. */
+pub mod species {
 	use wgpu::ShaderModuleSource;
 
-	/** Pre run shader for setting everything up. */
-	pub fn pre_run() -> ShaderModuleSource<'static> {
+	/** The shader sorting the individuals alive into the spatial grid. */
+	pub fn fill_grid() -> ShaderModuleSource<'static> {
 		wgpu::include_spirv!(
 			concat!(env!("OUT_DIR"),
-			"/shaders/Compute/Herbivore/PreRun.spv"))
+			"/shaders/Compute/Species/FillGrid.spv"))
 	}
 
 	/** The shader performing one step of the simulation. */
 	pub fn simulate() -> ShaderModuleSource<'static> {
 		wgpu::include_spirv!(
 			concat!(env!("OUT_DIR"),
-			"/shaders/Compute/Herbivore/Simulate.spv"))
+			"/shaders/Compute/Species/Simulate.spv"))
 	}
 
 	/** The shader performing the shuffling and evolution step. */
 	pub fn shuffle() -> ShaderModuleSource<'static> {
 		wgpu::include_spirv!(
 			concat!(env!("OUT_DIR"),
-			"/shaders/Compute/Herbivore/Shuffle.spv"))
+			"/shaders/Compute/Species/Shuffle.spv"))
 	}
 }
 
-/** Compute shaders for the predator group. */
-pub mod predators {
-	use wgpu::ShaderModuleSource;
-
-	/** Pre run shader for setting everything up. */
-	pub fn pre_run() -> ShaderModuleSource<'static> {
-		wgpu::include_spirv!(
-			concat!(env!("OUT_DIR"),
-			"/shaders/Compute/Predator/PreRun.spv"))
-	}
-
-	/** The shader performing one step of the simulation. */
-	pub fn simulate() -> ShaderModuleSource<'static> {
-		wgpu::include_spirv!(
-			concat!(env!("OUT_DIR"),
-			"/shaders/Compute/Predator/Simulate.spv"))
-	}
-
-	/** The shader performing the shuffling and evolution step. */
-	pub fn shuffle() -> ShaderModuleSource<'static> {
-		wgpu::include_spirv!(
-			concat!(env!("OUT_DIR"),
-			"/shaders/Compute/Predator/Shuffle.spv"))
-	}
+/** Pre run shader for setting everything up. */
+pub fn pre_run() -> ShaderModuleSource<'static> {
+	wgpu::include_spirv!(
+		concat!(env!("OUT_DIR"),
+		"/shaders/Compute/PreRun.spv"))
 }
 
 /** The shader responsible for weaving in data and updating the simulation
@@ -59,3 +41,18 @@ pub fn update_plane() -> ShaderModuleSource<'static> {
 		concat!(env!("OUT_DIR"),
 		"/shaders/Compute/UpdatePlane.spv"))
 }
+
+/** The shader spreading the chemicals on the simulation plane out to the
+ * neighbouring cells, writing the results to the scratch plane. */
+pub fn diffuse() -> ShaderModuleSource<'static> {
+	wgpu::include_spirv!(
+		concat!(env!("OUT_DIR"),
+		"/shaders/Compute/Diffuse.spv"))
+}
+
+/** The shader copying the scratch plane back over the simulation plane. */
+pub fn settle() -> ShaderModuleSource<'static> {
+	wgpu::include_spirv!(
+		concat!(env!("OUT_DIR"),
+		"/shaders/Compute/Settle.spv"))
+}
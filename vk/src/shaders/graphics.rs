@@ -1,29 +1,29 @@
 
-/** Shader modules for the geometry pass of the herbivore population. */
-pub mod herbivore_geometry_pass {
+/** Shader modules for the geometry pass of the populations of all species. */
+pub mod species_geometry_pass {
 	use wgpu::ShaderModuleSource;
 
 	pub fn vertex_shader() -> ShaderModuleSource<'static> {
 		wgpu::include_spirv!(
-			concat!(env!("OUT_DIR"), "/shaders/Graphics/HerbivoreGeometryPass/vert.spv"))
+			concat!(env!("OUT_DIR"), "/shaders/Graphics/SpeciesGeometryPass/vert.spv"))
 	}
 	pub fn fragment_shader() -> ShaderModuleSource<'static> {
 		wgpu::include_spirv!(
-			concat!(env!("OUT_DIR"), "/shaders/Graphics/HerbivoreGeometryPass/frag.spv"))
+			concat!(env!("OUT_DIR"), "/shaders/Graphics/SpeciesGeometryPass/frag.spv"))
 	}
 }
 
-/** Shader modules for geometry pass of the predator population. */
-pub mod predator_geometry_pass {
+/** Shader modules for the geometry pass of the simulation plane. */
+pub mod plane_geometry_pass {
 	use wgpu::ShaderModuleSource;
 
 	pub fn vertex_shader() -> ShaderModuleSource<'static> {
 		wgpu::include_spirv!(
-			concat!(env!("OUT_DIR"), "/shaders/Graphics/PredatorGeometryPass/vert.spv"))
+			concat!(env!("OUT_DIR"), "/shaders/Graphics/PlaneGeometryPass/vert.spv"))
 	}
 	pub fn fragment_shader() -> ShaderModuleSource<'static> {
 		wgpu::include_spirv!(
-			concat!(env!("OUT_DIR"), "/shaders/Graphics/PredatorGeometryPass/frag.spv"))
+			concat!(env!("OUT_DIR"), "/shaders/Graphics/PlaneGeometryPass/frag.spv"))
 	}
 }
 
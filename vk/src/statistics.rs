@@ -0,0 +1,383 @@
+use std::fmt;
+use rand::seq::SliceRandom;
+use crate::dataset::{Individual, NN_INPUTS, NN_OUTPUTS};
+use crate::settings::Diversity;
+
+/** Summary of the state of a group of individuals at a point in time. */
+#[derive(Debug, Clone, PartialEq)]
+pub struct GroupStatistics {
+	/** Number of individuals alive in the group. */
+	pub population: usize,
+	/** Average energy of the individuals alive in the group. */
+	pub mean_energy: f32,
+	/** Average age of the individuals alive in the group, in seconds. */
+	pub mean_age: f32,
+	/** Age of the oldest individual alive in the group, in seconds. */
+	pub oldest: f32,
+	/** Identifier and generation of the oldest individual alive in the group,
+	 * if there is any. */
+	pub eldest: Option<(u64, u32)>,
+	/** Individuals that joined or left the group since the start of the
+	 * simulation other than by being born or dying. */
+	pub turnover: Turnover,
+	/** Genetic diversity of the group, if it is being measured. */
+	pub diversity: Option<GeneticDiversity>,
+}
+impl GroupStatistics {
+	/** Gather the statistics of the given population, with the given
+	 * turnover, measuring its genetic diversity following the given settings.
+	 * Individuals with no energy left are considered to be dead and, like
+	 * individuals that have not been born yet, are not taken into account. */
+	pub fn new(population: &[Individual], turnover: Turnover, diversity: &Diversity) -> Self {
+		let alive = || population.iter().filter(|i| i.energy > 0.0 && i.born());
+
+		let count = alive().count();
+		let mean = |value: fn(&Individual) -> f32| if count == 0 {
+			0.0
+		} else {
+			alive().map(value).sum::<f32>() / count as f32
+		};
+
+		let eldest = alive().max_by(|a, b| a.age.total_cmp(&b.age));
+
+		Self {
+			population: count,
+			mean_energy: mean(|i| i.energy),
+			mean_age: mean(|i| i.age),
+			oldest: eldest.map(|i| i.age).unwrap_or(0.0),
+			eldest: eldest.map(|i| (i.id, i.generation)),
+			turnover,
+			diversity: if diversity.enabled {
+				Some(GeneticDiversity::new(population, diversity))
+			} else {
+				None
+			}
+		}
+	}
+}
+impl fmt::Display for GroupStatistics {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{} alive, mean energy {:.3}, mean age {:.1}s, oldest {:.1}s",
+			self.population,
+			self.mean_energy,
+			self.mean_age,
+			self.oldest)?;
+		if let Some((id, generation)) = self.eldest {
+			write!(f, " (individual {}, generation {})", id, generation)?;
+		}
+		write!(f, ", {} immigrants, {} births rejected, {} replaced",
+			self.turnover.immigrants,
+			self.turnover.rejected,
+			self.turnover.replaced)?;
+		if let Some(diversity) = &self.diversity {
+			write!(f, ", {}", diversity)?;
+		}
+
+		Ok(())
+	}
+}
+
+/** Number of genes in the genome of an individual, which are the weights of
+ * its neural network, row by row, followed by its biases. */
+pub const GENES: usize = NN_INPUTS * NN_OUTPUTS + NN_OUTPUTS;
+
+/** Genes of the given individual, in the order described by [`GENES`]. */
+fn genes(individual: &Individual) -> impl Iterator<Item = f32> + '_ {
+	individual.weights.iter()
+		.flatten()
+		.chain(&individual.biases)
+		.copied()
+}
+
+/** Euclidean distance between the genomes of two individuals. */
+pub fn genome_distance(a: &Individual, b: &Individual) -> f32 {
+	genes(a).zip(genes(b))
+		.map(|(a, b)| (a - b) * (a - b))
+		.sum::<f32>()
+		.sqrt()
+}
+
+/** Genetic diversity of a group of individuals at a point in time, measured
+ * over the weights and biases of their neural networks. */
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeneticDiversity {
+	/** Number of individuals, drawn at random from the group, the pairwise
+	 * distances and the genetic species were measured over. */
+	pub sampled: usize,
+	/** Average distance between the genomes of every two sampled
+	 * individuals. */
+	pub mean_distance: f32,
+	/** Variance of every gene over the whole group, in the order described by
+	 * [`GENES`]. */
+	pub gene_variance: Vec<f32>,
+	/** Sizes of the genetic species the sampled individuals cluster into,
+	 * from the largest to the smallest. Two individuals belong to the same
+	 * species whenever they are linked by a chain of individuals, each no
+	 * farther than the threshold away from the next one. */
+	pub species: Vec<usize>,
+}
+impl GeneticDiversity {
+	/** Measure the genetic diversity of the given population following the
+	 * given settings. Like with the rest of the statistics, individuals that
+	 * are dead or have not been born yet are not taken into account. */
+	pub fn new(population: &[Individual], settings: &Diversity) -> Self {
+		let alive = population.iter()
+			.filter(|i| i.energy > 0.0 && i.born())
+			.collect::<Vec<_>>();
+
+		/* Variance of every gene, over the whole group. */
+		let mut mean = vec![0.0f64; GENES];
+		for individual in &alive {
+			for (sum, gene) in mean.iter_mut().zip(genes(individual)) {
+				*sum += f64::from(gene);
+			}
+		}
+		mean.iter_mut().for_each(|sum| *sum /= alive.len().max(1) as f64);
+
+		let mut variance = vec![0.0f64; GENES];
+		for individual in &alive {
+			for ((sum, mean), gene) in variance.iter_mut().zip(&mean).zip(genes(individual)) {
+				*sum += (f64::from(gene) - mean).powi(2);
+			}
+		}
+		let gene_variance = variance.iter()
+			.map(|sum| (sum / alive.len().max(1) as f64) as f32)
+			.collect();
+
+		/* Pairwise distances and genetic species, over a sample of it. */
+		let count = match settings.sample_size {
+			0 => alive.len(),
+			size => alive.len().min(size as usize)
+		};
+		let sample = crate::random::with(|rng| alive.choose_multiple(rng, count)
+			.copied()
+			.collect::<Vec<_>>());
+
+		let mut roots = (0..sample.len()).collect::<Vec<_>>();
+		let root = |roots: &mut Vec<usize>, mut i: usize| {
+			while roots[i] != i {
+				roots[i] = roots[roots[i]];
+				i = roots[i];
+			}
+			i
+		};
+
+		let mut total = 0.0f64;
+		for a in 0..sample.len() {
+			for b in a + 1..sample.len() {
+				let distance = genome_distance(sample[a], sample[b]);
+				total += f64::from(distance);
+
+				if distance <= settings.threshold {
+					let (left, right) = (root(&mut roots, a), root(&mut roots, b));
+					roots[left] = right;
+				}
+			}
+		}
+
+		let pairs = sample.len() * sample.len().saturating_sub(1) / 2;
+		let mut species = vec![0; sample.len()];
+		for i in 0..sample.len() {
+			species[root(&mut roots, i)] += 1;
+		}
+		species.retain(|&size| size > 0);
+		species.sort_unstable_by(|a, b| b.cmp(a));
+
+		Self {
+			sampled: sample.len(),
+			mean_distance: if pairs == 0 {
+				0.0
+			} else {
+				(total / pairs as f64) as f32
+			},
+			gene_variance,
+			species
+		}
+	}
+
+	/** Average of the variances of all genes. */
+	pub fn mean_variance(&self) -> f32 {
+		self.gene_variance.iter().sum::<f32>() / self.gene_variance.len().max(1) as f32
+	}
+}
+impl fmt::Display for GeneticDiversity {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		/* Largest number of genetic species whose sizes are shown. */
+		const SHOWN: usize = 8;
+
+		write!(f, "mean genome distance {:.3} over {} sampled, mean gene \
+			variance {:.4} (highest {:.4}), {} genetic species",
+			self.mean_distance,
+			self.sampled,
+			self.mean_variance(),
+			self.gene_variance.iter().copied().fold(0.0, f32::max),
+			self.species.len())?;
+
+		if !self.species.is_empty() {
+			let sizes = self.species.iter()
+				.take(SHOWN)
+				.map(usize::to_string)
+				.collect::<Vec<_>>();
+			write!(f, " of sizes {}", sizes.join(" "))?;
+			if self.species.len() > SHOWN {
+				write!(f, " ...")?;
+			}
+		}
+
+		Ok(())
+	}
+}
+
+/** Count of the individuals that joined or left a group since the start of
+ * the simulation, other than by being born or dying of hunger, old age or
+ * predation. */
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct Turnover {
+	/** Individuals that immigrated into the group. */
+	pub immigrants: u64,
+	/** Births that did not happen because the group had reached its
+	 * budget. */
+	pub rejected: u64,
+	/** Individuals that made room for a newborn because the group had
+	 * reached its budget. */
+	pub replaced: u64,
+}
+
+/** Account of the energy that moved around the simulation during a single
+ * step. All of the entries are non-negative amounts of energy. */
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct EnergyLedger {
+	/** Energy created on the field by the growth of grass. */
+	pub grown: f32,
+	/** Energy taken off the field by grazing individuals. */
+	pub grazed: f32,
+	/** Energy taken out of prey killed by hunting individuals. */
+	pub preyed: f32,
+	/** Energy individuals actually gained from feeding on grass and prey. */
+	pub assimilated: f32,
+	/** Energy individuals spent on their metabolism. */
+	pub metabolised: f32,
+	/** Energy parents paid to reproduce. */
+	pub invested: f32,
+	/** Energy newborns started off with. */
+	pub endowed: f32,
+	/** Energy individuals still had when they died of old age or made room
+	 * for a newborn. */
+	pub expired: f32,
+}
+impl EnergyLedger {
+	/** Number of entries in the ledger. */
+	pub const ENTRIES: usize = 8;
+	/** Scaling factor of the fixed point representation of the ledger used
+	 * by the shaders, which have no atomic operations on floats. */
+	pub const FIXED_POINT_SCALE: f64 = 1048576.0;
+
+	/** Create a ledger from its fixed point representation. */
+	pub fn from_fixed(entries: [u64; Self::ENTRIES]) -> Self {
+		let f = |i: usize| (entries[i] as f64 / Self::FIXED_POINT_SCALE) as f32;
+		Self {
+			grown: f(0),
+			grazed: f(1),
+			preyed: f(2),
+			assimilated: f(3),
+			metabolised: f(4),
+			invested: f(5),
+			endowed: f(6),
+			expired: f(7)
+		}
+	}
+
+	/** Fixed point representation of this ledger. */
+	pub fn to_fixed(&self) -> [u64; Self::ENTRIES] {
+		let f = |val: f32| (f64::from(val) * Self::FIXED_POINT_SCALE).round() as u64;
+		[
+			f(self.grown),
+			f(self.grazed),
+			f(self.preyed),
+			f(self.assimilated),
+			f(self.metabolised),
+			f(self.invested),
+			f(self.endowed),
+			f(self.expired)
+		]
+	}
+
+	/** Energy that was lost because feeding is not perfectly efficient. */
+	pub fn feeding_loss(&self) -> f32 {
+		self.grazed + self.preyed - self.assimilated
+	}
+
+	/** Energy newborns got on top of what their parents paid for them. This
+	 * will be negative when reproduction loses energy. */
+	pub fn reproduction_surplus(&self) -> f32 {
+		self.endowed - self.invested
+	}
+
+	/** Net change in the total amount of energy in the simulation. */
+	pub fn balance(&self) -> f32 {
+		self.grown
+			- self.feeding_loss()
+			+ self.reproduction_surplus()
+			- self.metabolised
+			- self.expired
+	}
+}
+impl fmt::Display for EnergyLedger {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "grown {:.4}, grazed {:.4}, preyed {:.4}, assimilated {:.4} \
+			(feeding loss {:.4}), metabolised {:.4}, invested {:.4}, endowed \
+			{:.4} (reproduction surplus {:.4}), expired {:.4}, balance {:+.4}",
+			self.grown,
+			self.grazed,
+			self.preyed,
+			self.assimilated,
+			self.feeding_loss(),
+			self.metabolised,
+			self.invested,
+			self.endowed,
+			self.reproduction_surplus(),
+			self.expired,
+			self.balance())
+	}
+}
+
+/** Summary of the state of the whole simulation at a point in time. */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Statistics {
+	/** Name and statistics of every species, in the order the species were
+	 * declared in. */
+	pub species: Vec<(String, GroupStatistics)>,
+	/** Energy ledger of the last step of the simulation. */
+	pub ledger: EnergyLedger,
+}
+impl Statistics {
+	/** Gather the statistics of the given populations, each paired with the
+	 * name of its species and its turnover, measuring their genetic diversity
+	 * following the given settings. */
+	pub fn new<'a, I, P>(
+		populations: I,
+		turnover: &[Turnover],
+		ledger: EnergyLedger,
+		diversity: &Diversity) -> Self
+		where I: IntoIterator<Item = (&'a str, P)>,
+			  P: AsRef<[Individual]> {
+
+		Self {
+			species: populations.into_iter()
+				.zip(turnover)
+				.map(|((name, population), turnover)| (
+					name.to_owned(),
+					GroupStatistics::new(population.as_ref(), *turnover, diversity)))
+				.collect(),
+			ledger
+		}
+	}
+}
+impl fmt::Display for Statistics {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		for (name, statistics) in &self.species {
+			write!(f, "{}: {}; ", name, statistics)?;
+		}
+		write!(f, "energy: {}", self.ledger)
+	}
+}
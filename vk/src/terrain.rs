@@ -0,0 +1,349 @@
+use std::error::Error;
+use crate::settings::{Simulation, Regrowth, RegrowthModel, Temperature, TemperatureSource};
+use image::imageops::FilterType;
+
+/** Properties of a single cell of the terrain. */
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Tile {
+	/** Whether this cell is an impassable wall. */
+	pub wall: bool,
+	/** Factor by which individuals are slowed down while crossing this cell.
+	 * Always at least one. */
+	pub cost: f32,
+	/** Factor by which the growth rate of the grass on this cell is scaled,
+	 * between zero and one. Walls are never fertile. */
+	pub fertility: f32,
+	/** Distance, in simulation board units, from the center of this cell to
+	 * the center of the closest wall. */
+	pub clearance: f32,
+	/** Temperature of this cell, before it gets scaled by the schedule of the
+	 * temperature. */
+	pub temperature: f32,
+}
+
+/** Static terrain of the simulation plane, made up of walls, regions that are
+ * costly to cross, regions where grass grows at different rates and regions
+ * of different temperatures. The terrain has one tile for every pheromone cell
+ * of the plane.
+ *
+ * # Maps
+ * Every property of the terrain is loaded from its own map, an image in
+ * either the PNG or the PGM formats, whose gray levels are resampled to the
+ * granularity of the plane. The top left pixel of a map lies at the origin of
+ * the plane. See [`TerrainMaps`] for what the gray levels of every map mean.
+ *
+ * [`TerrainMaps`]: crate::settings::TerrainMaps
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Terrain {
+	tiles: Vec<Tile>,
+	width: u32,
+	height: u32,
+	/** Size of every cell, in simulation board units. */
+	cell: [f32; 2],
+}
+impl Terrain {
+	/** Creates a terrain without any walls, which is equally easy to cross,
+	 * fertile and warm everywhere, at the lowest temperature. */
+	pub fn open(simulation: &Simulation) -> Self {
+		let width = simulation.horizontal_granularity;
+		let height = simulation.vertical_granularity;
+		let clearance = f32::hypot(simulation.plane_width, simulation.plane_height);
+
+		Self {
+			tiles: vec![Tile {
+				wall: false,
+				cost: 1.0,
+				fertility: 1.0,
+				clearance,
+				temperature: simulation.temperature.low
+			}; (width * height) as usize],
+			width,
+			height,
+			cell: [
+				simulation.plane_width / width as f32,
+				simulation.plane_height / height as f32
+			]
+		}
+	}
+
+	/** Loads the terrain from the maps given in the simulation settings. Any
+	 * properties without a map are left as they are on an open terrain. */
+	pub fn load(simulation: &Simulation) -> Result<Self, Box<dyn Error>> {
+		let maps = &simulation.terrain;
+		let mut terrain = Self::open(simulation);
+
+		if let Some(path) = &maps.walls {
+			let walls = terrain.layer(path)?;
+			for (tile, level) in terrain.tiles.iter_mut().zip(walls) {
+				tile.wall = level < 0.5;
+			}
+		}
+		if let Some(path) = &maps.movement_cost {
+			let costs = terrain.layer(path)?;
+			for (tile, level) in terrain.tiles.iter_mut().zip(costs) {
+				tile.cost = 1.0 + level * (maps.max_movement_cost - 1.0).max(0.0);
+			}
+		}
+		if let Some(path) = &maps.fertility {
+			let fertility = terrain.layer(path)?;
+			for (tile, level) in terrain.tiles.iter_mut().zip(fertility) {
+				tile.fertility = level;
+			}
+		}
+
+		for tile in terrain.tiles.iter_mut().filter(|tile| tile.wall) {
+			tile.fertility = 0.0;
+		}
+		terrain.measure_clearance();
+		terrain.heat(&simulation.temperature)?;
+
+		Ok(terrain)
+	}
+
+	/** Loads the map at the given path, resampled to the size of the terrain,
+	 * with gray levels between zero and one, in row-major order. */
+	fn layer(&self, path: &str) -> Result<Vec<f32>, Box<dyn Error>> {
+		let image = image::open(path)
+			.map_err(|what| format!("could not load terrain map {}: {}", path, what))?
+			.into_luma8();
+		let image = image::imageops::resize(
+			&image,
+			self.width,
+			self.height,
+			FilterType::Nearest);
+
+		Ok(image.pixels()
+			.map(|pixel| f32::from(pixel.0[0]) / 255.0)
+			.collect())
+	}
+
+	/** Lays the temperature out over the terrain following the given
+	 * settings. Gradients and noise are stretched out such that they go all
+	 * the way from the lowest temperature to the highest one. */
+	fn heat(&mut self, settings: &Temperature) -> Result<(), Box<dyn Error>> {
+		let levels = match settings.source {
+			TemperatureSource::Uniform => vec![0.0; self.tiles.len()],
+			TemperatureSource::Gradient => {
+				let angle = settings.angle.to_radians();
+				let (dx, dy) = (angle.cos(), angle.sin());
+
+				let [cell_x, cell_y] = self.cell;
+				let width = self.width;
+				normalize((0..self.tiles.len() as u32)
+					.map(|index| {
+						let x = ((index % width) as f32 + 0.5) * cell_x;
+						let y = ((index / width) as f32 + 0.5) * cell_y;
+						x * dx + y * dy
+					})
+					.collect())
+			},
+			TemperatureSource::Noise => normalize(self.noise(settings.noise_scale)),
+			TemperatureSource::Map => match &settings.map {
+				Some(path) => self.layer(path)?,
+				None => return Err("no map was given for the temperature".into())
+			}
+		};
+
+		for (tile, level) in self.tiles.iter_mut().zip(levels) {
+			tile.temperature = settings.at_level(level);
+		}
+		Ok(())
+	}
+
+	/** Smooth value noise over the terrain, interpolated between random values
+	 * on a lattice whose cells are the given size, in simulation board units,
+	 * in row-major order. */
+	fn noise(&self, scale: f32) -> Vec<f32> {
+		let [cell_x, cell_y] = self.cell;
+		let scale = scale.max(f32::EPSILON);
+		let columns = (self.width as f32 * cell_x / scale).ceil() as usize + 2;
+		let rows = (self.height as f32 * cell_y / scale).ceil() as usize + 2;
+		let lattice = (0..columns * rows)
+			.map(|_| crate::random::random::<f32>())
+			.collect::<Vec<_>>();
+
+		let smooth = |t: f32| t * t * (3.0 - 2.0 * t);
+		let width = self.width;
+		(0..self.tiles.len() as u32)
+			.map(|index| {
+				let x = ((index % width) as f32 + 0.5) * cell_x / scale;
+				let y = ((index / width) as f32 + 0.5) * cell_y / scale;
+				let (column, row) = (x.floor() as usize, y.floor() as usize);
+				let (tx, ty) = (smooth(x.fract()), smooth(y.fract()));
+
+				let at = |column: usize, row: usize| lattice[row * columns + column];
+				let top = at(column, row) + (at(column + 1, row) - at(column, row)) * tx;
+				let bottom = at(column, row + 1) + (at(column + 1, row + 1) - at(column, row + 1)) * tx;
+				top + (bottom - top) * ty
+			})
+			.collect()
+	}
+
+	/** Measures the distance from every tile to its closest wall, by
+	 * propagating the closest wall known to every tile to its neighbours, in
+	 * two passes over the terrain. The result is not exact, but it is close
+	 * enough for individuals to feel walls coming. */
+	fn measure_clearance(&mut self) {
+		let (width, height) = (self.width as i64, self.height as i64);
+		let mut closest = self.tiles.iter()
+			.enumerate()
+			.map(|(index, tile)| if tile.wall {
+				Some((index as i64 % width, index as i64 / width))
+			} else {
+				None
+			})
+			.collect::<Vec<_>>();
+
+		let [cell_x, cell_y] = self.cell;
+		let distance = |(x, y): (i64, i64), (wx, wy): (i64, i64)| f32::hypot(
+			(x - wx) as f32 * cell_x,
+			(y - wy) as f32 * cell_y);
+
+		let mut relax = |x: i64, y: i64, neighbours: &[(i64, i64)]| {
+			for (dx, dy) in neighbours {
+				let (nx, ny) = (x + dx, y + dy);
+				if nx < 0 || ny < 0 || nx >= width || ny >= height {
+					continue
+				}
+
+				let candidate = match closest[(ny * width + nx) as usize] {
+					Some(wall) => wall,
+					None => continue
+				};
+				let current = &mut closest[(y * width + x) as usize];
+				let better = match current {
+					Some(wall) => distance((x, y), candidate) < distance((x, y), *wall),
+					None => true
+				};
+				if better {
+					*current = Some(candidate);
+				}
+			}
+		};
+
+		for y in 0..height {
+			for x in 0..width {
+				relax(x, y, &[(-1, 0), (-1, -1), (0, -1), (1, -1)]);
+			}
+		}
+		for y in (0..height).rev() {
+			for x in (0..width).rev() {
+				relax(x, y, &[(1, 0), (1, 1), (0, 1), (-1, 1)]);
+			}
+		}
+
+		for (index, tile) in self.tiles.iter_mut().enumerate() {
+			let position = (index as i64 % width, index as i64 / width);
+			if let Some(wall) = closest[index] {
+				tile.clearance = distance(position, wall);
+			}
+		}
+	}
+
+	/** Width of the terrain, in tiles. */
+	pub fn width(&self) -> u32 {
+		self.width
+	}
+
+	/** Height of the terrain, in tiles. */
+	pub fn height(&self) -> u32 {
+		self.height
+	}
+
+	/** The tile at the given coordinates, in tiles. */
+	pub fn tile(&self, x: u32, y: u32) -> &Tile {
+		&self.tiles[(y * self.width + x) as usize]
+	}
+
+	/** The tile under the given position on the plane. Positions out of the
+	 * bounds of the plane are clamped to the closest tile. */
+	pub fn tile_at(&self, position: [f32; 2]) -> &Tile {
+		let x = (position[0] / self.cell[0]).floor().clamp(0.0, (self.width - 1) as f32);
+		let y = (position[1] / self.cell[1]).floor().clamp(0.0, (self.height - 1) as f32);
+
+		self.tile(x as u32, y as u32)
+	}
+
+	/** Moves from one position to another, sliding along any walls in the
+	 * way. Moves that would end up inside of a wall keep only the component
+	 * that does not, or get cancelled if both do. Individuals that somehow
+	 * ended up inside of a wall may always move out of it. */
+	pub fn slide(&self, from: [f32; 2], to: [f32; 2]) -> [f32; 2] {
+		if !self.tile_at(to).wall || self.tile_at(from).wall {
+			to
+		} else if !self.tile_at([to[0], from[1]]).wall {
+			[to[0], from[1]]
+		} else if !self.tile_at([from[0], to[1]]).wall {
+			[from[0], to[1]]
+		} else {
+			from
+		}
+	}
+
+	/** Density of the grass every cell of the field over this terrain starts
+	 * off with. Only the patches regrowth model starts off with any grass, in
+	 * circular patches scattered at random over the open ground. */
+	pub fn seed_grass(&self, regrowth: &Regrowth) -> Vec<f32> {
+		let mut grass = vec![0.0; self.tiles.len()];
+		if regrowth.model != RegrowthModel::Patches {
+			return grass
+		}
+
+		let [cell_x, cell_y] = self.cell;
+		let size = [self.width as f32 * cell_x, self.height as f32 * cell_y];
+		for _ in 0..regrowth.patches {
+			let center = [
+				crate::random::random::<f32>() * size[0],
+				crate::random::random::<f32>() * size[1]
+			];
+
+			for (index, (tile, grass)) in self.tiles.iter().zip(&mut grass).enumerate() {
+				let x = ((index as u32 % self.width) as f32 + 0.5) * cell_x;
+				let y = ((index as u32 / self.width) as f32 + 0.5) * cell_y;
+				if !tile.wall && f32::hypot(x - center[0], y - center[1]) <= regrowth.patch_radius {
+					*grass = 1.0;
+				}
+			}
+		}
+
+		grass
+	}
+
+	/** Write out the texels of the temperature texture, in `R32Float`, in
+	 * row-major order, holding the temperature of every tile before it gets
+	 * scaled by its schedule. */
+	pub fn temperature_texels(&self) -> Vec<u8> {
+		self.tiles.iter()
+			.flat_map(|tile| tile.temperature.to_ne_bytes().to_vec())
+			.collect()
+	}
+
+	/** Write out the texels of the terrain texture, in `Rgba32Float`, in
+	 * row-major order. The red component holds whether the tile is a wall,
+	 * the green one its cost, the blue one its fertility and the alpha one its
+	 * clearance. */
+	pub fn texels(&self) -> Vec<u8> {
+		let mut texels = Vec::with_capacity(self.tiles.len() * 16);
+		for tile in &self.tiles {
+			let wall = if tile.wall { 1.0 } else { 0.0 };
+			for component in [wall, tile.cost, tile.fertility, tile.clearance].iter() {
+				texels.extend_from_slice(&component.to_ne_bytes());
+			}
+		}
+
+		texels
+	}
+}
+
+/** Stretches the given values out such that the lowest of them becomes zero
+ * and the highest one. Values that are all the same become zero. */
+fn normalize(values: Vec<f32>) -> Vec<f32> {
+	let low = values.iter().copied().fold(f32::INFINITY, f32::min);
+	let high = values.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+	let range = (high - low).max(f32::EPSILON);
+
+	values.into_iter()
+		.map(|value| (value - low) / range)
+		.collect()
+}
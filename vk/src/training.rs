@@ -0,0 +1,219 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use std::time::Duration;
+use crate::dataset::Individual;
+use crate::events::Timeline;
+use crate::evolve::cpu::World;
+use crate::genome::{Genome, GenomeFile};
+use crate::immigration::Immigration;
+use crate::lineage::Lineage;
+use crate::settings::{Fitness, Preferences};
+use crate::terrain::Terrain;
+
+/** A genome in the hall of fame, along with how well it did. */
+#[derive(Debug, Clone)]
+struct Entry {
+	/** Fitness of the individual that carried the genome, the last time it
+	 * was seen alive. */
+	fitness: f32,
+	/** Genome of the individual. */
+	genome: Genome,
+}
+
+/** Best genomes come across over a training run, ranked by fitness. Every
+ * individual is only ever in it once, with the fitness it had the last time it
+ * was offered, which only ever grows over its life. */
+#[derive(Debug, Clone)]
+pub struct HallOfFame {
+	/** Measure the genomes are ranked by. */
+	fitness: Fitness,
+	/** Largest number of genomes kept. */
+	capacity: usize,
+	/** Genomes kept, by the episode and the identifier of the individual
+	 * that carried them, in order, such that genomes of equal fitness always
+	 * rank the same way. */
+	entries: BTreeMap<(u32, u64), Entry>,
+	/** Fitness no higher than that of any genome kept, once the hall is full.
+	 * Individuals that do no better than this are turned away right away. */
+	floor: f32,
+}
+impl HallOfFame {
+	/** Creates an empty hall of fame keeping up to the given number of the
+	 * best genomes by the given measure of fitness. */
+	pub fn new(fitness: Fitness, capacity: usize) -> Self {
+		Self {
+			fitness,
+			capacity,
+			entries: BTreeMap::new(),
+			floor: f32::NEG_INFINITY
+		}
+	}
+
+	/** Fitness of the given individual, as it is now. */
+	pub fn measure(&self, individual: &Individual) -> f32 {
+		match self.fitness {
+			Fitness::Lifetime => individual.age,
+			Fitness::Offspring => individual.offspring as f32,
+			Fitness::Energy => individual.gathered
+		}
+	}
+
+	/** Considers the given individual, alive in the episode at the given
+	 * index, for the hall of fame, taking the place of the worst genome in it,
+	 * if it is full and the individual did better. */
+	pub fn offer(&mut self, episode: u32, individual: &Individual) {
+		let fitness = self.measure(individual);
+		let key = (episode, individual.id);
+		if let Some(entry) = self.entries.get_mut(&key) {
+			entry.fitness = entry.fitness.max(fitness);
+			return
+		}
+
+		if self.entries.len() < self.capacity {
+			self.entries.insert(key, Entry { fitness, genome: individual.into() });
+			if self.entries.len() == self.capacity {
+				self.settle();
+			}
+			return
+		}
+		if fitness <= self.floor {
+			return
+		}
+
+		/* Entries may have gotten better since the floor was last worked out,
+		 * so it only ever is a lower bound for the worst of them. Of entries
+		 * that did equally badly, the one ranked last goes. */
+		let worst = self.entries.iter()
+			.min_by(|(a, x), (b, y)| x.fitness.total_cmp(&y.fitness).then(b.cmp(a)))
+			.map(|(key, entry)| (*key, entry.fitness));
+		if let Some((worst, lowest)) = worst {
+			if fitness > lowest {
+				self.entries.remove(&worst);
+				self.entries.insert(key, Entry { fitness, genome: individual.into() });
+			}
+		}
+		self.settle();
+	}
+
+	/** Works the floor out again from the genomes kept. */
+	fn settle(&mut self) {
+		self.floor = self.entries.values()
+			.map(|entry| entry.fitness)
+			.fold(f32::INFINITY, f32::min);
+	}
+
+	/** Genomes kept, along with their fitness, from the best down, with ties
+	 * broken by episode and then by identifier. */
+	fn ranked(&self) -> Vec<&Entry> {
+		let mut entries = self.entries.iter().collect::<Vec<_>>();
+		entries.sort_by(|(a, x), (b, y)| y.fitness.total_cmp(&x.fitness).then(a.cmp(b)));
+
+		entries.into_iter()
+			.map(|(_, entry)| entry)
+			.collect()
+	}
+
+	/** Genome file holding the genomes kept, from the best down. */
+	pub fn genome_file(&self) -> GenomeFile {
+		GenomeFile::new(self.ranked()
+			.into_iter()
+			.map(|entry| entry.genome.clone())
+			.collect())
+	}
+}
+impl fmt::Display for HallOfFame {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let ranked = self.ranked();
+		match (ranked.first(), ranked.last()) {
+			(Some(best), Some(worst)) =>
+				write!(f, "{} genomes, {:?} from {:.3} down to {:.3}",
+					ranked.len(),
+					self.fitness,
+					best.fitness,
+					worst.fitness),
+			_ => write!(f, "no genomes")
+		}
+	}
+}
+
+/** Runs the offline training set up in the given preferences on the CPU, as
+ * fast as possible, writing the hall of fame out to its genome file after
+ * every episode. Every episode starts the simulation over from scratch, with
+ * all of the random numbers drawn on the host following its seed, such that
+ * an episode always runs the same way given the same seed and settings. */
+pub fn train(prefs: &Preferences) -> Result<(), Box<dyn Error>> {
+	let training = &prefs.training;
+	let simulation = &prefs.simulation;
+
+	let species = if training.species.is_empty() {
+		0
+	} else {
+		simulation.species.iter()
+			.position(|group| group.name == training.species)
+			.ok_or_else(|| format!("training refers to species {}, which does \
+				not exist", training.species))?
+	};
+	let delta = Duration::from_secs_f32(training.time_step);
+
+	let immigration = Immigration::new(simulation)
+		.unwrap_or_else(|what| {
+			warn!("could not load the immigrants, closing off all species: {}", what);
+			Immigration::closed(simulation)
+		});
+	let timeline = Timeline::new(simulation)
+		.unwrap_or_else(|what| {
+			warn!("could not load the scripted events, running without any: {}", what);
+			Default::default()
+		});
+
+	let mut hall = HallOfFame::new(training.fitness, training.hall_of_fame as usize);
+	for episode in 0..training.episodes {
+		let seed = training.seed.wrapping_add(u64::from(episode));
+		crate::random::seed(seed);
+
+		let terrain = Terrain::load(simulation)
+			.unwrap_or_else(|what| {
+				warn!("could not load terrain, falling back to an open plane: {}", what);
+				Terrain::open(simulation)
+			});
+		let mut timeline = timeline.clone();
+		let mut world = World::new(
+			simulation,
+			&terrain,
+			immigration.clone(),
+			Lineage::unlogged(simulation));
+
+		let mut steps = 0;
+		while steps < training.steps {
+			for action in timeline.due() {
+				world.apply(&action);
+			}
+			world.step(delta);
+			timeline.advance(delta);
+			steps += 1;
+
+			let population = &world.current_state().populations[species];
+			for individual in population.iter().filter(|i| i.energy > 0.0 && i.born()) {
+				hall.offer(episode, individual);
+			}
+			if population.iter().all(|i| i.energy <= 0.0) {
+				break
+			}
+		}
+
+		info!("episode {} of {}, seeded with {}, ran for {} steps: {}",
+			episode + 1,
+			training.episodes,
+			seed,
+			steps,
+			world.statistics());
+		info!("hall of fame: {}", hall);
+
+		hall.genome_file()
+			.save(&training.archive)
+			.map_err(|what| format!("could not write the hall of fame: {}", what))?;
+	}
+
+	Ok(())
+}